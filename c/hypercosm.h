@@ -0,0 +1,214 @@
+/* Generated by hidl from the `hypercosm` namespace. Do not edit. */
+
+/*
+ * Ownership:
+ *
+ * `string`, `bytes`, arrays and dictionaries are views of memory owned by
+ * someone else, as a pointer and a length. Strings are UTF-8, and are not
+ * NUL terminated.
+ *
+ * - Arguments are borrowed by the callee for the duration of the call. If
+ *   the callee needs the data afterwards, it must copy it.
+ * - Return values are written through the `ret` pointer, and are owned by
+ *   the callee. They stay valid until the next call on the same object, or
+ *   until the object is released, whichever comes first.
+ * - Event arguments are only valid for the duration of the callback.
+ *
+ * A null pointer is only allowed when the length is 0.
+ */
+#ifndef HYPERCOSM_H
+#define HYPERCOSM_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/** Returned by every method. 0 for success, anything else is an error. */
+typedef int32_t hypercosm_status;
+#define HYPERCOSM_OK ((hypercosm_status)0)
+
+/** An object reference, see `object` in `spec/encoding.md` */
+typedef uint64_t hypercosm_object_id;
+
+typedef struct hypercosm_uuid {
+    uint8_t bytes[16];
+} hypercosm_uuid;
+
+/** Column major */
+typedef struct hypercosm_matrix4x4 {
+    float m[16];
+} hypercosm_matrix4x4;
+
+typedef struct hypercosm_string {
+    const char *ptr;
+    size_t len;
+} hypercosm_string;
+
+typedef struct hypercosm_bytes {
+    const uint8_t *ptr;
+    size_t len;
+} hypercosm_bytes;
+
+typedef struct hypercosm_Asset hypercosm_Asset;
+typedef struct hypercosm_EntityInfo hypercosm_EntityInfo;
+
+typedef uint64_t hypercosm_EntityAttrs;
+#define HYPERCOSM_ENTITY_ATTRS_NONE ((hypercosm_EntityAttrs)UINT64_C(0))
+#define HYPERCOSM_ENTITY_ATTRS_INTERACTABLE ((hypercosm_EntityAttrs)UINT64_C(1))
+#define HYPERCOSM_ENTITY_ATTRS_COLLIDABLE ((hypercosm_EntityAttrs)UINT64_C(2))
+
+typedef struct hypercosm_array_string {
+    const hypercosm_string *ptr;
+    size_t len;
+} hypercosm_array_string;
+
+typedef struct hypercosm_array_uuid {
+    const hypercosm_uuid *ptr;
+    size_t len;
+} hypercosm_array_uuid;
+
+typedef struct hypercosm_array_Asset {
+    const hypercosm_Asset *ptr;
+    size_t len;
+} hypercosm_array_Asset;
+
+typedef struct hypercosm_array_EntityInfo {
+    const hypercosm_EntityInfo *ptr;
+    size_t len;
+} hypercosm_array_EntityInfo;
+
+typedef struct hypercosm_array_Entity {
+    const hypercosm_object_id *ptr;
+    size_t len;
+} hypercosm_array_Entity;
+
+/** `keys[i]` maps to `values[i]` */
+typedef struct hypercosm_dict_string_string {
+    const hypercosm_string *keys;
+    const hypercosm_string *values;
+    size_t len;
+} hypercosm_dict_string_string;
+
+/**
+ * An asset: the type is given by TODO
+ */
+struct hypercosm_Asset {
+    hypercosm_uuid id;
+    hypercosm_string name;
+    hypercosm_bytes data;
+};
+
+struct hypercosm_EntityInfo {
+    hypercosm_uuid asset_id;
+    hypercosm_object_id entity;
+    hypercosm_matrix4x4 transformation;
+    hypercosm_EntityAttrs attrs;
+};
+
+/**
+ * The root interface, that all interfaces inherit from and
+ * all objects implement
+ */
+#define HYPERCOSM_OBJECT_NAME "hypercosm.Object"
+#define HYPERCOSM_OBJECT_VERSION "1.0.0"
+
+typedef struct hypercosm_Object_methods {
+    /**
+     * Get all interfaces implemented by an object
+     *
+     * The interfaces will be listed in the format `{namespace}.{extension}.{interface_name}.{version}`
+     */
+    hypercosm_status (*list_interfaces)(void *self, hypercosm_array_string *ret);
+    /**
+     * Remove the object from the object list
+     *
+     * Future attempts to make calls on the object ID **MUST** fail.
+     *
+     * The resources associated with the object may be released, but the
+     * object ID must not be reused, we have plenty of them
+     *
+     * It is an error to remove the root singleton (id 0)
+     */
+    hypercosm_status (*release)(void *self);
+} hypercosm_Object_methods;
+
+/**
+ * Singleton with known id 0
+ *
+ * This is the only object that is known at the start of connection
+ * and is used to discover other objects
+ */
+#define HYPERCOSM_ROOT_NAME "hypercosm.Root"
+#define HYPERCOSM_ROOT_VERSION "0.1.0"
+
+typedef struct hypercosm_Root_methods {
+    /**
+     * List the extensions implemented by the conected node
+     */
+    hypercosm_status (*list_extensions)(void *self, hypercosm_array_string *ret);
+    /**
+     * Check the conection status
+     */
+    hypercosm_status (*ping)(void *self);
+    hypercosm_status (*get_object_by_id)(void *self, hypercosm_uuid id, hypercosm_object_id *ret);
+    /**
+     * Get the id of a singleton by the name of the interface
+     */
+    hypercosm_status (*get_object_by_name)(void *self, hypercosm_string name, hypercosm_object_id *ret);
+} hypercosm_Root_methods;
+
+/**
+ * Singleton for asset delivery
+ */
+#define HYPERCOSM_ASSET_DELIVERY_NAME "hypercosm.asset_delivery.asset_delivery"
+#define HYPERCOSM_ASSET_DELIVERY_VERSION "0.1.0"
+
+typedef struct hypercosm_asset_delivery_methods {
+    hypercosm_status (*fetch_by_id)(void *self, hypercosm_uuid id, hypercosm_Asset *ret);
+    hypercosm_status (*fetch_by_name)(void *self, hypercosm_string name, hypercosm_Asset *ret);
+    hypercosm_status (*fetch_by_ids)(void *self, hypercosm_array_uuid ids, hypercosm_array_Asset *ret);
+    hypercosm_status (*fetch_by_names)(void *self, hypercosm_array_string names, hypercosm_array_Asset *ret);
+    hypercosm_status (*get_id)(void *self, hypercosm_string name, hypercosm_uuid *ret);
+} hypercosm_asset_delivery_methods;
+
+typedef struct hypercosm_asset_delivery_events {
+    void (*load_assets)(void *self, hypercosm_array_Asset assets);
+    void (*unload_assets)(void *self, hypercosm_array_uuid assets);
+} hypercosm_asset_delivery_events;
+
+#define HYPERCOSM_WORLD_NAME "hypercosm.world.world"
+#define HYPERCOSM_WORLD_VERSION "0.1.0"
+
+typedef struct hypercosm_world_events {
+    void (*add_entities)(void *self, hypercosm_array_EntityInfo entities);
+    void (*update_entities)(void *self, hypercosm_array_EntityInfo entities);
+    void (*remove_entities)(void *self, hypercosm_array_Entity entities);
+} hypercosm_world_events;
+
+#define HYPERCOSM_WORLD_ENTITY_NAME "hypercosm.world.Entity"
+#define HYPERCOSM_WORLD_ENTITY_VERSION "0.1.0"
+
+typedef struct hypercosm_world_Entity_methods {
+    hypercosm_status (*interact)(void *self);
+} hypercosm_world_Entity_methods;
+
+#define HYPERCOSM_EXECUTION_CONTEXT_NAME "hypercosm.execution_context.execution_context"
+#define HYPERCOSM_EXECUTION_CONTEXT_VERSION "0.1.0"
+
+typedef struct hypercosm_execution_context_methods {
+    hypercosm_status (*load_wasm_module)(void *self, hypercosm_uuid asset_id, hypercosm_dict_string_string exports, uint64_t *ret);
+    hypercosm_status (*load_lua_script)(void *self, hypercosm_uuid asset_id, uint64_t *ret);
+    hypercosm_status (*load_inline_lua_script)(void *self, hypercosm_string script, uint64_t *ret);
+    hypercosm_status (*begin_execution)(void *self, uint64_t module_or_script, hypercosm_string entry_point);
+    hypercosm_status (*begin_inline_lua_execution)(void *self, hypercosm_string script);
+} hypercosm_execution_context_methods;
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HYPERCOSM_H */
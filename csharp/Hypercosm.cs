@@ -0,0 +1,163 @@
+// Generated by hidl from the `hypercosm` namespace. Do not edit.
+
+using System;
+using System.Collections.Generic;
+using System.Threading.Tasks;
+
+#if UNITY_5_3_OR_NEWER
+using Matrix4x4 = UnityEngine.Matrix4x4;
+#else
+using Matrix4x4 = System.Numerics.Matrix4x4;
+#endif
+
+namespace Hypercosm
+{
+    /// <summary>
+    /// The name and version of the protocol interface a C# interface describes.
+    /// </summary>
+    [AttributeUsage(AttributeTargets.Interface, Inherited = false)]
+    public sealed class HidlInterfaceAttribute : Attribute
+    {
+        public string Name { get; }
+        public string Version { get; }
+
+        public HidlInterfaceAttribute(string name, string version)
+        {
+            Name = name;
+            Version = version;
+        }
+    }
+
+    /// <summary>
+    /// An asset: the type is given by TODO
+    /// </summary>
+    [Serializable]
+    public struct Asset
+    {
+        public Guid Id;
+        public string Name;
+        public byte[] Data;
+    }
+
+    [Serializable]
+    public struct EntityInfo
+    {
+        public Guid AssetId;
+        public ulong Entity;
+        public Matrix4x4 Transformation;
+        public EntityAttrs Attrs;
+    }
+
+    [Flags]
+    public enum EntityAttrs : ulong
+    {
+        None = 0,
+        Interactable = 1,
+        Collidable = 2,
+    }
+
+    /// <summary>
+    /// The root interface, that all interfaces inherit from and
+    /// all objects implement
+    /// </summary>
+    [HidlInterface("hypercosm.Object", "1.0.0")]
+    public interface IObject
+    {
+        /// <summary>
+        /// Get all interfaces implemented by an object
+        ///
+        /// The interfaces will be listed in the format `{namespace}.{extension}.{interface_name}.{version}`
+        /// </summary>
+        Task<string[]> ListInterfacesAsync();
+
+        /// <summary>
+        /// Remove the object from the object list
+        ///
+        /// Future attempts to make calls on the object ID **MUST** fail.
+        ///
+        /// The resources associated with the object may be released, but the
+        /// object ID must not be reused, we have plenty of them
+        ///
+        /// It is an error to remove the root singleton (id 0)
+        /// </summary>
+        Task ReleaseAsync();
+    }
+
+    /// <summary>
+    /// Singleton with known id 0
+    ///
+    /// This is the only object that is known at the start of connection
+    /// and is used to discover other objects
+    /// </summary>
+    [HidlInterface("hypercosm.Root", "0.1.0")]
+    public interface IRoot
+    {
+        /// <summary>
+        /// List the extensions implemented by the conected node
+        /// </summary>
+        Task<string[]> ListExtensionsAsync();
+
+        /// <summary>
+        /// Check the conection status
+        /// </summary>
+        Task PingAsync();
+
+        Task<ulong> GetObjectByIdAsync(Guid id);
+
+        /// <summary>
+        /// Get the id of a singleton by the name of the interface
+        /// </summary>
+        Task<ulong> GetObjectByNameAsync(string name);
+    }
+
+    /// <summary>
+    /// Singleton for asset delivery
+    /// </summary>
+    [HidlInterface("hypercosm.asset_delivery.asset_delivery", "0.1.0")]
+    public interface IAssetDelivery
+    {
+        event Action<Asset[]> LoadAssets;
+
+        event Action<Guid[]> UnloadAssets;
+
+        Task<Asset> FetchByIdAsync(Guid id);
+
+        Task<Asset> FetchByNameAsync(string name);
+
+        Task<Asset[]> FetchByIdsAsync(Guid[] ids);
+
+        Task<Asset[]> FetchByNamesAsync(string[] names);
+
+        Task<Guid> GetIdAsync(string name);
+    }
+
+    [HidlInterface("hypercosm.world.world", "0.1.0")]
+    public interface IWorld
+    {
+        event Action<EntityInfo[]> AddEntities;
+
+        event Action<EntityInfo[]> UpdateEntities;
+
+        event Action<ulong[]> RemoveEntities;
+    }
+
+    [HidlInterface("hypercosm.world.Entity", "0.1.0")]
+    public interface IEntity
+    {
+        Task InteractAsync();
+    }
+
+    [HidlInterface("hypercosm.execution_context.execution_context", "0.1.0")]
+    public interface IExecutionContext
+    {
+        Task<ulong> LoadWasmModuleAsync(Guid assetId, Dictionary<string, string> exports);
+
+        Task<ulong> LoadLuaScriptAsync(Guid assetId);
+
+        Task<ulong> LoadInlineLuaScriptAsync(string script);
+
+        Task BeginExecutionAsync(ulong moduleOrScript, string entryPoint);
+
+        Task BeginInlineLuaExecutionAsync(string script);
+    }
+}
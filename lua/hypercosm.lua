@@ -0,0 +1,140 @@
+---@meta hypercosm
+-- Generated by hidl from the `hypercosm` namespace. Do not edit.
+
+---@alias Uuid string
+---Column major
+---@alias Matrix4x4 number[]
+
+---An asset: the type is given by TODO
+---@class Asset
+---@field id Uuid
+---@field name string
+---@field data string
+
+---@class EntityInfo
+---@field asset_id Uuid
+---@field entity Entity
+---@field transformation Matrix4x4
+---@field attrs EntityAttrs
+
+---Flags, combine them with `|`
+---@enum EntityAttrs
+EntityAttrs = {
+    None = 0,
+    Interactable = 1,
+    Collidable = 2,
+}
+
+---The root interface, that all interfaces inherit from and
+---all objects implement
+---@class Object
+Object = {}
+
+---Get all interfaces implemented by an object
+---
+---The interfaces will be listed in the format `{namespace}.{extension}.{interface_name}.{version}`
+---@return string[]
+function Object:list_interfaces() end
+
+---Remove the object from the object list
+---
+---Future attempts to make calls on the object ID **MUST** fail.
+---
+---The resources associated with the object may be released, but the
+---object ID must not be reused, we have plenty of them
+---
+---It is an error to remove the root singleton (id 0)
+function Object:release() end
+
+---Singleton with known id 0
+---
+---This is the only object that is known at the start of connection
+---and is used to discover other objects
+---@class Root: Object
+Root = {}
+
+---List the extensions implemented by the conected node
+---@return string[]
+function Root:list_extensions() end
+
+---Check the conection status
+function Root:ping() end
+
+---@param id Uuid
+---@return Object
+function Root:get_object_by_id(id) end
+
+---Get the id of a singleton by the name of the interface
+---@param name string
+---@return Object
+function Root:get_object_by_name(name) end
+
+---Singleton for asset delivery
+---@class asset_delivery: Object
+asset_delivery = {}
+
+---@param id Uuid
+---@return Asset
+function asset_delivery:fetch_by_id(id) end
+
+---@param name string
+---@return Asset
+function asset_delivery:fetch_by_name(name) end
+
+---@param ids Uuid[]
+---@return Asset[]
+function asset_delivery:fetch_by_ids(ids) end
+
+---@param names string[]
+---@return Asset[]
+function asset_delivery:fetch_by_names(names) end
+
+---@param name string
+---@return Uuid
+function asset_delivery:get_id(name) end
+
+---Register a handler for an event
+---@overload fun(self: asset_delivery, event: "load_assets", handler: fun(assets: Asset[]))
+---@overload fun(self: asset_delivery, event: "unload_assets", handler: fun(assets: Uuid[]))
+---@param event string
+---@param handler function
+function asset_delivery:on(event, handler) end
+
+---@class world: Object
+world = {}
+
+---Register a handler for an event
+---@overload fun(self: world, event: "add_entities", handler: fun(entities: EntityInfo[]))
+---@overload fun(self: world, event: "update_entities", handler: fun(entities: EntityInfo[]))
+---@overload fun(self: world, event: "remove_entities", handler: fun(entities: Entity[]))
+---@param event string
+---@param handler function
+function world:on(event, handler) end
+
+---@class Entity: Object
+Entity = {}
+
+function Entity:interact() end
+
+---@class execution_context: Object
+execution_context = {}
+
+---@param asset_id Uuid
+---@param exports table<string, string>
+---@return integer
+function execution_context:load_wasm_module(asset_id, exports) end
+
+---@param asset_id Uuid
+---@return integer
+function execution_context:load_lua_script(asset_id) end
+
+---@param script string
+---@return integer
+function execution_context:load_inline_lua_script(script) end
+
+---@param module_or_script integer
+---@param entry_point string
+function execution_context:begin_execution(module_or_script, entry_point) end
+
+---@param script string
+function execution_context:begin_inline_lua_execution(script) end
@@ -0,0 +1,81 @@
+-- Generated by hidl from the `hypercosm` namespace. Do not edit.
+--
+-- Describes every interface, so hosts can register a native function
+-- for each method. Types are written in HIDL syntax.
+
+return {
+    namespace = "hypercosm",
+    interfaces = {
+        {
+            name = "Object",
+            qualified_name = "hypercosm.Object",
+            version = "1.0.0",
+            methods = {
+                { name = "list_interfaces", args = {}, ret = "[]string" },
+                { name = "release", args = {} },
+            },
+            events = {},
+        },
+        {
+            name = "Root",
+            qualified_name = "hypercosm.Root",
+            version = "0.1.0",
+            methods = {
+                { name = "list_extensions", args = {}, ret = "[]string" },
+                { name = "ping", args = {} },
+                { name = "get_object_by_id", args = { { name = "id", type = "uuid" } }, ret = "object" },
+                { name = "get_object_by_name", args = { { name = "name", type = "string" } }, ret = "object" },
+            },
+            events = {},
+        },
+        {
+            name = "asset_delivery",
+            qualified_name = "hypercosm.asset_delivery.asset_delivery",
+            version = "0.1.0",
+            methods = {
+                { name = "fetch_by_id", args = { { name = "id", type = "uuid" } }, ret = "Asset" },
+                { name = "fetch_by_name", args = { { name = "name", type = "string" } }, ret = "Asset" },
+                { name = "fetch_by_ids", args = { { name = "ids", type = "[]uuid" } }, ret = "[]Asset" },
+                { name = "fetch_by_names", args = { { name = "names", type = "[]string" } }, ret = "[]Asset" },
+                { name = "get_id", args = { { name = "name", type = "string" } }, ret = "uuid" },
+            },
+            events = {
+                { name = "load_assets", args = { { name = "assets", type = "[]Asset" } } },
+                { name = "unload_assets", args = { { name = "assets", type = "[]uuid" } } },
+            },
+        },
+        {
+            name = "world",
+            qualified_name = "hypercosm.world.world",
+            version = "0.1.0",
+            methods = {},
+            events = {
+                { name = "add_entities", args = { { name = "entities", type = "[]EntityInfo" } } },
+                { name = "update_entities", args = { { name = "entities", type = "[]EntityInfo" } } },
+                { name = "remove_entities", args = { { name = "entities", type = "[]Entity" } } },
+            },
+        },
+        {
+            name = "Entity",
+            qualified_name = "hypercosm.world.Entity",
+            version = "0.1.0",
+            methods = {
+                { name = "interact", args = {} },
+            },
+            events = {},
+        },
+        {
+            name = "execution_context",
+            qualified_name = "hypercosm.execution_context.execution_context",
+            version = "0.1.0",
+            methods = {
+                { name = "load_wasm_module", args = { { name = "asset_id", type = "uuid" }, { name = "exports", type = "[string]string" } }, ret = "vu64" },
+                { name = "load_lua_script", args = { { name = "asset_id", type = "uuid" } }, ret = "vu64" },
+                { name = "load_inline_lua_script", args = { { name = "script", type = "string" } }, ret = "vu64" },
+                { name = "begin_execution", args = { { name = "module_or_script", type = "vu64" }, { name = "entry_point", type = "string" } } },
+                { name = "begin_inline_lua_execution", args = { { name = "script", type = "string" } } },
+            },
+            events = {},
+        },
+    },
+}
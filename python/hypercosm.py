@@ -0,0 +1,223 @@
+# Generated by hidl from the `hypercosm` namespace. Do not edit.
+
+from __future__ import annotations
+
+import dataclasses
+import enum
+import uuid
+from typing import Dict, List
+
+from hidl_runtime import Client, Matrix4x4, ObjectId, Registry
+
+registry = Registry("hypercosm")
+
+
+@registry.struct(
+    "Asset",
+    [
+        ("id", {"Primitive": "Uuid"}),
+        ("name", {"Primitive": "String"}),
+        ("data", {"Primitive": "Bytes"}),
+    ],
+)
+@dataclasses.dataclass
+class Asset:
+    """An asset: the type is given by TODO"""
+    id: uuid.UUID
+    name: str
+    data: bytes
+
+
+@registry.struct(
+    "EntityInfo",
+    [
+        ("asset_id", {"Primitive": "Uuid"}),
+        ("entity", {"Custom": "Entity"}),
+        ("transformation", {"Primitive": "Matrix4x4"}),
+        ("attrs", {"Custom": "EntityAttrs"}),
+    ],
+)
+@dataclasses.dataclass
+class EntityInfo:
+    asset_id: uuid.UUID
+    entity: ObjectId
+    transformation: Matrix4x4
+    attrs: EntityAttrs
+
+
+@registry.flags("EntityAttrs", "VU64")
+class EntityAttrs(enum.IntFlag):
+    None_ = 0
+    Interactable = 1
+    Collidable = 2
+
+
+@registry.interface(
+    "Object",
+    "hypercosm.Object",
+    "1.0.0",
+    methods={
+        "list_interfaces": ([], {"Array": {"Primitive": "String"}}),
+        "release": ([], None),
+    },
+    events={},
+)
+class ObjectClient(Client):
+    """The root interface, that all interfaces inherit from and
+    all objects implement
+    """
+
+    async def list_interfaces(self) -> List[str]:
+        """Get all interfaces implemented by an object
+
+        The interfaces will be listed in the format `{namespace}.{extension}.{interface_name}.{version}`
+        """
+        return await self._call("list_interfaces")
+
+    async def release(self) -> None:
+        """Remove the object from the object list
+
+        Future attempts to make calls on the object ID **MUST** fail.
+
+        The resources associated with the object may be released, but the
+        object ID must not be reused, we have plenty of them
+
+        It is an error to remove the root singleton (id 0)
+        """
+        return await self._call("release")
+
+
+@registry.interface(
+    "Root",
+    "hypercosm.Root",
+    "0.1.0",
+    methods={
+        "list_extensions": ([], {"Array": {"Primitive": "String"}}),
+        "ping": ([], None),
+        "get_object_by_id": ([("id", {"Primitive": "Uuid"})], {"Primitive": "Object"}),
+        "get_object_by_name": ([("name", {"Primitive": "String"})], {"Primitive": "Object"}),
+    },
+    events={},
+)
+class RootClient(Client):
+    """Singleton with known id 0
+
+    This is the only object that is known at the start of connection
+    and is used to discover other objects
+    """
+
+    async def list_extensions(self) -> List[str]:
+        """List the extensions implemented by the conected node"""
+        return await self._call("list_extensions")
+
+    async def ping(self) -> None:
+        """Check the conection status"""
+        return await self._call("ping")
+
+    async def get_object_by_id(self, id: uuid.UUID) -> ObjectId:
+        return await self._call("get_object_by_id", id)
+
+    async def get_object_by_name(self, name: str) -> ObjectId:
+        """Get the id of a singleton by the name of the interface"""
+        return await self._call("get_object_by_name", name)
+
+
+@registry.interface(
+    "asset_delivery",
+    "hypercosm.asset_delivery.asset_delivery",
+    "0.1.0",
+    methods={
+        "fetch_by_id": ([("id", {"Primitive": "Uuid"})], {"Custom": "Asset"}),
+        "fetch_by_name": ([("name", {"Primitive": "String"})], {"Custom": "Asset"}),
+        "fetch_by_ids": ([("ids", {"Array": {"Primitive": "Uuid"}})], {"Array": {"Custom": "Asset"}}),
+        "fetch_by_names": ([("names", {"Array": {"Primitive": "String"}})], {"Array": {"Custom": "Asset"}}),
+        "get_id": ([("name", {"Primitive": "String"})], {"Primitive": "Uuid"}),
+    },
+    events={
+        "load_assets": [("assets", {"Array": {"Custom": "Asset"}})],
+        "unload_assets": [("assets", {"Array": {"Primitive": "Uuid"}})],
+    },
+)
+class AssetDeliveryClient(Client):
+    """Singleton for asset delivery"""
+    # Events:
+    # - load_assets(assets: List[Asset])
+    # - unload_assets(assets: List[uuid.UUID])
+
+    async def fetch_by_id(self, id: uuid.UUID) -> Asset:
+        return await self._call("fetch_by_id", id)
+
+    async def fetch_by_name(self, name: str) -> Asset:
+        return await self._call("fetch_by_name", name)
+
+    async def fetch_by_ids(self, ids: List[uuid.UUID]) -> List[Asset]:
+        return await self._call("fetch_by_ids", ids)
+
+    async def fetch_by_names(self, names: List[str]) -> List[Asset]:
+        return await self._call("fetch_by_names", names)
+
+    async def get_id(self, name: str) -> uuid.UUID:
+        return await self._call("get_id", name)
+
+
+@registry.interface(
+    "world",
+    "hypercosm.world.world",
+    "0.1.0",
+    methods={},
+    events={
+        "add_entities": [("entities", {"Array": {"Custom": "EntityInfo"}})],
+        "update_entities": [("entities", {"Array": {"Custom": "EntityInfo"}})],
+        "remove_entities": [("entities", {"Array": {"Custom": "Entity"}})],
+    },
+)
+class WorldClient(Client):
+    # Events:
+    # - add_entities(entities: List[EntityInfo])
+    # - update_entities(entities: List[EntityInfo])
+    # - remove_entities(entities: List[ObjectId])
+    pass
+
+
+@registry.interface(
+    "Entity",
+    "hypercosm.world.Entity",
+    "0.1.0",
+    methods={
+        "interact": ([], None),
+    },
+    events={},
+)
+class EntityClient(Client):
+    async def interact(self) -> None:
+        return await self._call("interact")
+
+
+@registry.interface(
+    "execution_context",
+    "hypercosm.execution_context.execution_context",
+    "0.1.0",
+    methods={
+        "load_wasm_module": ([("asset_id", {"Primitive": "Uuid"}), ("exports", {"Dictionary": {"key": {"Primitive": "String"}, "value": {"Primitive": "String"}}})], {"IntType": "VU64"}),
+        "load_lua_script": ([("asset_id", {"Primitive": "Uuid"})], {"IntType": "VU64"}),
+        "load_inline_lua_script": ([("script", {"Primitive": "String"})], {"IntType": "VU64"}),
+        "begin_execution": ([("module_or_script", {"IntType": "VU64"}), ("entry_point", {"Primitive": "String"})], None),
+        "begin_inline_lua_execution": ([("script", {"Primitive": "String"})], None),
+    },
+    events={},
+)
+class ExecutionContextClient(Client):
+    async def load_wasm_module(self, asset_id: uuid.UUID, exports: Dict[str, str]) -> int:
+        return await self._call("load_wasm_module", asset_id, exports)
+
+    async def load_lua_script(self, asset_id: uuid.UUID) -> int:
+        return await self._call("load_lua_script", asset_id)
+
+    async def load_inline_lua_script(self, script: str) -> int:
+        return await self._call("load_inline_lua_script", script)
+
+    async def begin_execution(self, module_or_script: int, entry_point: str) -> None:
+        return await self._call("begin_execution", module_or_script, entry_point)
+
+    async def begin_inline_lua_execution(self, script: str) -> None:
+        return await self._call("begin_inline_lua_execution", script)
@@ -3,4 +3,13 @@ set -eoxu pipefail
 
 cargo run --bin generate -- hypercosm.hidl json/hypercosm.json
 cargo run --bin document --  hypercosm.hidl proto-docs
+cargo run --bin codegen -- c hypercosm.hidl c/hypercosm.h
+cargo run --bin codegen -- csharp hypercosm.hidl csharp/Hypercosm.cs
+cargo run --bin codegen -- json-schema hypercosm.hidl json-schema/hypercosm.schema.json
+cargo run --bin codegen -- lua hypercosm.hidl lua
+cargo run --bin codegen -- openrpc hypercosm.hidl openrpc
+cargo run --bin codegen -- proto hypercosm.hidl proto/hypercosm.proto
+cargo run --bin codegen -- python hypercosm.hidl python
+cargo run --bin codegen -- typescript hypercosm.hidl typescript/hypercosm.ts
+cargo run --bin codegen -- wit hypercosm.hidl wit/hypercosm.wit
 mdbook build
\ No newline at end of file
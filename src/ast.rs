@@ -8,6 +8,33 @@ pub struct Namespace {
     pub interfaces: Vec<Interface>,
     pub types: Vec<TypeDef>,
     pub extensions: Vec<Extension>,
+    /// Found in the docs when lowering, so always empty in the AST
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub doc_links: Vec<DocLink>,
+}
+
+/// An intra-doc link, like `[Asset]` or `[Root.ping]`.
+#[derive(Debug, debug2::Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocLink {
+    /// The item with the docs the link is in: `Interface`,
+    /// `Interface.methods.method`, `Interface.events.event`, `Type`, or
+    /// `extensions.extension`
+    pub source: String,
+    /// Between the brackets
+    pub text: String,
+    pub kind: LinkKind,
+    /// The item linked to, named like `source`, or `Type.field` for fields
+    pub target: String,
+}
+
+#[derive(Debug, debug2::Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LinkKind {
+    Interface,
+    Method,
+    Event,
+    Type,
+    /// A struct field, enum variant or flag
+    Field,
 }
 
 #[derive(Debug, debug2::Debug, Clone, PartialEq, Serialize, Deserialize)]
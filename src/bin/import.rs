@@ -0,0 +1,6 @@
+use std::process::ExitCode;
+
+/// An alias for `hidl import`, kept for existing scripts.
+fn main() -> ExitCode {
+    hidl::cli::alias("import")
+}
@@ -0,0 +1,362 @@
+//! The `hidl` command line tool.
+//!
+//! Every subcommand takes either HIDL or the JSON written by `hidl json` as
+//! input (see [`crate::input`]), apart from `import`. Undefined or duplicate
+//! names in it are an error, as they are for `check`. The older
+//! `generate`, `document`, `codegen`, `import` and `print` binaries are
+//! aliases for `hidl json`, `hidl docs`, `hidl gen`, `hidl import` and
+//! `hidl print`.
+//!
+//! With `--check`, nothing is written, and any output that's missing or
+//! different from what would be generated is an error, with a diff.
+//! Commands that write into a directory also remove (or with `--check`,
+//! fail on) files in it they didn't generate, unless they match `--keep`.
+//! With `--archive`, everything is written into a tarball or zip instead.
+//!
+//! The exit status is 0 on success, 1 if something went wrong (or `diff`
+//! or `--check` found differences), and 2 if the arguments were wrong.
+
+use std::{ffi::OsString, io::Write, process::ExitCode};
+
+use anyhow::{bail, Result};
+use camino::Utf8PathBuf;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use fs_err as fs;
+
+use crate::{codegen, diff, document, hir, import, input, print, vfs};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "hidl",
+    version,
+    about = "Tools for the Hypercosm interface definition language",
+    after_help = "Exit status: 0 on success, 1 on errors or differences, 2 on bad usage"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Write nothing, and fail if any output (or, for `fmt`, input) is out
+    /// of date
+    #[arg(long, global = true)]
+    check: bool,
+    /// Don't remove files matching PATTERN from output directories. It's
+    /// relative to the directory, and `*` matches anything but `/`
+    #[arg(long, global = true, value_name = "PATTERN")]
+    keep: Vec<String>,
+    /// Write the output into a new `.tar` or `.zip` archive, instead of the
+    /// filesystem
+    #[arg(long, global = true, value_name = "PATH", conflicts_with = "check")]
+    archive: Option<Utf8PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check that a namespace is valid, and every type it uses is defined
+    Check { input: Utf8PathBuf },
+    /// Write a namespace as JSON
+    Json {
+        input: Utf8PathBuf,
+        output: Utf8PathBuf,
+    },
+    /// Write markdown docs for a namespace into a directory
+    Docs {
+        input: Utf8PathBuf,
+        out_dir: Utf8PathBuf,
+        /// Give every interface and type its own page, in a directory per
+        /// extension
+        #[arg(long)]
+        split: bool,
+        /// Write a standalone HTML site, with search, instead of markdown
+        #[arg(long, conflicts_with = "split")]
+        html: bool,
+    },
+    /// Reformat HIDL files in place
+    Fmt {
+        #[arg(required = true)]
+        inputs: Vec<Utf8PathBuf>,
+    },
+    /// Show what changed between two versions of a namespace
+    Diff { old: Utf8PathBuf, new: Utf8PathBuf },
+    /// Generate code for a target
+    Gen {
+        target: String,
+        input: Utf8PathBuf,
+        output: Utf8PathBuf,
+        /// Passed to the `template` target and plugins
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        parameters: Vec<String>,
+    },
+    /// Convert another IDL into HIDL
+    Import {
+        format: String,
+        input: Utf8PathBuf,
+        output: Utf8PathBuf,
+        /// Wrap everything in an extension with this name
+        extension: Option<String>,
+    },
+    /// Write a namespace (such as JSON from `hidl json`) as HIDL
+    Print {
+        input: Utf8PathBuf,
+        output: Utf8PathBuf,
+    },
+}
+
+/// Run `hidl` with the process's arguments.
+pub fn main() -> ExitCode {
+    run(std::env::args_os())
+}
+
+/// Run `hidl {subcommand}` with the rest of the process's arguments, for
+/// the older binaries.
+pub fn alias(subcommand: &str) -> ExitCode {
+    let args = std::env::args_os().skip(1);
+    run(["hidl".into(), subcommand.into()].into_iter().chain(args))
+}
+
+fn run(args: impl IntoIterator<Item = OsString>) -> ExitCode {
+    // Exits with 2 for bad usage, and 0 for `--help`.
+    let matches = command().get_matches_from(args);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    let mode = if cli.check {
+        vfs::Mode::Check
+    } else {
+        vfs::Mode::Write
+    };
+    let options = Options {
+        mode,
+        keep: cli.keep,
+        archive: cli.archive,
+    };
+    match execute(cli.command, &options) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {:?}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn command() -> clap::Command {
+    Cli::command()
+        .mut_subcommand("gen", |c| {
+            c.after_help(format!(
+                "Targets: {}, or any `hidl-gen-<target>` plugin on PATH",
+                codegen::TARGETS.join(", ")
+            ))
+        })
+        .mut_subcommand("import", |c| {
+            c.after_help(format!("Formats: {}", import::FORMATS.join(", ")))
+        })
+}
+
+/// What the global flags mean for writing output.
+struct Options {
+    mode: vfs::Mode,
+    keep: Vec<String>,
+    archive: Option<Utf8PathBuf>,
+}
+
+impl Options {
+    fn fs(&self) -> vfs::FS {
+        let mut fs = vfs::FS::new();
+        for pattern in &self.keep {
+            fs.keep(pattern.clone());
+        }
+        fs
+    }
+
+    fn save(&self, fs: vfs::FS) -> Result<()> {
+        match &self.archive {
+            Some(path) => fs.save_to(&mut vfs::Archive::from_path(path)?),
+            None => fs.save_to(&mut vfs::Disk::new(self.mode)),
+        }
+    }
+}
+
+fn execute(command: Command, options: &Options) -> Result<ExitCode> {
+    match command {
+        Command::Check { input } => {
+            // Loading checks it
+            input::load(&input)?;
+            eprintln!("{} is valid", input);
+        }
+        Command::Json { input, output } => {
+            let ns = input::load(&input)?;
+            let mut vfs = options.fs();
+            vfs.open(output)
+                .write_all(crate::json::to_string(&ns)?.as_bytes())?;
+            options.save(vfs)?;
+        }
+        Command::Docs {
+            input,
+            out_dir,
+            split,
+            html,
+        } => {
+            let ns = input::load(&input)?;
+            let layout = if split {
+                document::Layout::Item
+            } else {
+                document::Layout::Extension
+            };
+            let mut vfs = options.fs();
+            if html {
+                document::html::generate(&ns, &out_dir, &mut vfs)?;
+            } else {
+                document::generate(&ns, &out_dir, layout, &mut vfs)?;
+            }
+            options.save(vfs)?;
+        }
+        Command::Fmt { inputs } => {
+            let mut unformatted = false;
+            for input in inputs {
+                if !fmt(&input, options.mode)? {
+                    unformatted = true;
+                }
+            }
+            // Without `--check`, unformatted files have been fixed
+            if unformatted && options.mode == vfs::Mode::Check {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Diff { old, new } => {
+            let changes = diff::diff(&input::load(&old)?, &input::load(&new)?);
+            for change in &changes {
+                print!("{}", change);
+            }
+            if !changes.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Gen {
+            target,
+            input,
+            output,
+            parameters,
+        } => {
+            let ns = input::load(&input)?;
+            let mut vfs = options.fs();
+            codegen::generate(&target, &ns, &output, &parameters, &mut vfs)?;
+            options.save(vfs)?;
+        }
+        Command::Import {
+            format,
+            input,
+            output,
+            extension,
+        } => {
+            let imported = import::import(&format, &input)?;
+            for warning in &imported.warnings {
+                eprintln!("warning: {}", warning);
+            }
+            let mut ns = imported.namespace;
+            if let Some(extension) = extension {
+                ns = import::into_extension(ns, &extension);
+            }
+            let mut vfs = options.fs();
+            vfs.open(output)
+                .write_all(print::namespace(&ns)?.as_bytes())?;
+            options.save(vfs)?;
+        }
+        Command::Print { input, output } => {
+            let ns = input::load(&input)?;
+            let mut vfs = options.fs();
+            vfs.open(output)
+                .write_all(print::namespace(&ns)?.as_bytes())?;
+            options.save(vfs)?;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Format the HIDL in `path`, or with [`vfs::Mode::Check`] only report if
+/// it isn't formatted. Returns whether it already was.
+fn fmt(path: &Utf8PathBuf, mode: vfs::Mode) -> Result<bool> {
+    if path.extension() == Some("json") {
+        bail!("Can't format `{}`, only HIDL can be formatted", path);
+    }
+    let src = fs::read_to_string(path)?;
+    if has_line_comments(&src) {
+        bail!(
+            "`{}` has `//` comments, which formatting would remove. \
+             Make them doc comments (`/** */`), or remove them first",
+            path
+        );
+    }
+
+    let mut ns = input::parse(path, &src)?;
+    hir::lower_docs(&mut ns);
+    let formatted = print::namespace(&ns)?;
+
+    if formatted == src {
+        return Ok(true);
+    }
+    if mode == vfs::Mode::Check {
+        eprintln!("{} isn't formatted", path);
+    } else {
+        // Through the VFS, so a failed write can't leave it half formatted
+        let mut vfs = vfs::FS::new();
+        vfs.open(path).write_all(formatted.as_bytes())?;
+        vfs.save()?;
+    }
+    Ok(false)
+}
+
+/// Whether `src` has any `//` comments, which the parser skips.
+///
+/// HIDL has no strings, so anything outside a `/* */` comment counts.
+fn has_line_comments(src: &str) -> bool {
+    let mut rest = src;
+    while let Some(i) = rest.find('/') {
+        rest = &rest[i..];
+        if rest.starts_with("//") {
+            return true;
+        }
+        if rest.starts_with("/*") {
+            match rest[2..].find("*/") {
+                Some(end) => rest = &rest[2 + end + 2..],
+                None => return false,
+            }
+        } else {
+            rest = &rest[1..];
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli() {
+        command().debug_assert();
+
+        assert!(has_line_comments("namespace x\n// TODO\n"));
+        assert!(!has_line_comments(
+            "/** See https://example.com */ namespace x"
+        ));
+        assert!(!has_line_comments("/* a / b */ namespace x"));
+    }
+
+    #[test]
+    fn fmt_in_place() {
+        let dir = std::env::temp_dir().join(format!("hidl-fmt-{}", std::process::id()));
+        let dir = Utf8PathBuf::try_from(dir).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("x.hidl");
+        fs::write(&path, "namespace x   struct A {  a: u8 }").unwrap();
+
+        assert!(!fmt(&path, vfs::Mode::Check).unwrap());
+        assert!(!fmt(&path, vfs::Mode::Write).unwrap());
+        assert!(fmt(&path, vfs::Mode::Check).unwrap());
+        let formatted = fs::read_to_string(&path).unwrap();
+        // Only the formatted file, no temporaries
+        let files = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(formatted.starts_with("namespace x\n"), "{}", formatted);
+        assert_eq!(files, 1);
+    }
+}
@@ -0,0 +1,529 @@
+//! C header generator.
+//!
+//! This describes the protocol as a C ABI, for native engines to implement
+//! or consume. Every name is prefixed with the namespace, and every interface
+//! gets a vtable for its methods, and another for its events.
+
+use std::{collections::BTreeSet, io::Write};
+
+use anyhow::{bail, Result};
+use heck::ToShoutySnakeCase;
+
+use crate::{
+    ast::{Enum, Flags, Func, IntType, Namespace, PrimType, Struct, Type, TypeKind},
+    symbols::{InterfaceRef, Symbol, Symbols, TypeRef},
+};
+
+pub fn generate(ns: &Namespace, w: &mut dyn Write) -> Result<()> {
+    let syms = Symbols::new(ns);
+    let cx = Cx {
+        syms: &syms,
+        prefix: format!("{}_", ns.name),
+    };
+    let guard = format!("{}_H", ns.name.to_shouty_snake_case());
+
+    writeln!(
+        w,
+        "/* Generated by hidl from the `{}` namespace. Do not edit. */",
+        ns.name
+    )?;
+    writeln!(w, "{}", OWNERSHIP)?;
+    writeln!(w, "#ifndef {}", guard)?;
+    writeln!(w, "#define {}\n", guard)?;
+    writeln!(w, "#include <stdbool.h>")?;
+    writeln!(w, "#include <stddef.h>")?;
+    writeln!(w, "#include <stdint.h>\n")?;
+    writeln!(w, "#ifdef __cplusplus")?;
+    writeln!(w, "extern \"C\" {{")?;
+    writeln!(w, "#endif\n")?;
+
+    writeln!(
+        w,
+        "{}",
+        PRELUDE
+            .replace("hidl_", &cx.prefix)
+            .replace("HIDL_", &cx.prefix.to_uppercase())
+    )?;
+
+    // Everything that can be used by value without a definition.
+    for ty in syms.types() {
+        match &ty.def.kind {
+            TypeKind::Struct(_) => writeln!(w, "typedef struct {0} {0};", cx.name(&ty.def.name))?,
+            TypeKind::Enum(e) => {
+                writeln!(w)?;
+                docs(w, "", &ty.def.docs)?;
+                write_enum(w, &cx, &ty.def.name, e)?;
+            }
+            TypeKind::Flags(f) => {
+                writeln!(w)?;
+                docs(w, "", &ty.def.docs)?;
+                write_flags(w, &cx, &ty.def.name, f)?;
+            }
+        }
+    }
+
+    // Arrays and dictionaries only hold pointers, so can come before the
+    // structs they contain, but must come after the arrays and dictionaries
+    // they contain.
+    let mut compound = Vec::new();
+    let mut seen = BTreeSet::new();
+    for_each_type(&syms, |ty| {
+        collect_compound(&cx, ty, &mut compound, &mut seen)
+    })?;
+    for decl in &compound {
+        writeln!(w, "\n{}", decl)?;
+    }
+
+    for ty in struct_order(&syms)? {
+        if let TypeKind::Struct(s) = &ty.def.kind {
+            writeln!(w)?;
+            docs(w, "", &ty.def.docs)?;
+            write_struct(w, &cx, &ty.def.name, s)?;
+        }
+    }
+
+    for i in syms.interfaces() {
+        writeln!(w)?;
+        write_interface(w, &cx, i)?;
+    }
+
+    writeln!(w, "\n#ifdef __cplusplus")?;
+    writeln!(w, "}}")?;
+    writeln!(w, "#endif\n")?;
+    writeln!(w, "#endif /* {} */", guard)?;
+
+    Ok(())
+}
+
+const OWNERSHIP: &str = "
+/*
+ * Ownership:
+ *
+ * `string`, `bytes`, arrays and dictionaries are views of memory owned by
+ * someone else, as a pointer and a length. Strings are UTF-8, and are not
+ * NUL terminated.
+ *
+ * - Arguments are borrowed by the callee for the duration of the call. If
+ *   the callee needs the data afterwards, it must copy it.
+ * - Return values are written through the `ret` pointer, and are owned by
+ *   the callee. They stay valid until the next call on the same object, or
+ *   until the object is released, whichever comes first.
+ * - Event arguments are only valid for the duration of the callback.
+ *
+ * A null pointer is only allowed when the length is 0.
+ */";
+
+const PRELUDE: &str = "\
+/** Returned by every method. 0 for success, anything else is an error. */
+typedef int32_t hidl_status;
+#define HIDL_OK ((hidl_status)0)
+
+/** An object reference, see `object` in `spec/encoding.md` */
+typedef uint64_t hidl_object_id;
+
+typedef struct hidl_uuid {
+    uint8_t bytes[16];
+} hidl_uuid;
+
+/** Column major */
+typedef struct hidl_matrix4x4 {
+    float m[16];
+} hidl_matrix4x4;
+
+typedef struct hidl_string {
+    const char *ptr;
+    size_t len;
+} hidl_string;
+
+typedef struct hidl_bytes {
+    const uint8_t *ptr;
+    size_t len;
+} hidl_bytes;
+";
+
+struct Cx<'a> {
+    syms: &'a Symbols<'a>,
+    prefix: String,
+}
+
+impl Cx<'_> {
+    fn name(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    fn constant(&self, ty: &str, name: &str) -> String {
+        format!("{}{}_{}", self.prefix, ty, name).to_shouty_snake_case()
+    }
+
+    fn ty(&self, ty: &Type) -> Result<String> {
+        Ok(match ty {
+            Type::Primitive(p) => match p {
+                PrimType::Bool => "bool".to_owned(),
+                PrimType::F32 => "float".to_owned(),
+                PrimType::F64 => "double".to_owned(),
+                PrimType::Object => self.name("object_id"),
+                _ => self.name(&p.to_string()),
+            },
+            Type::IntType(i) => int_type(i).to_owned(),
+            Type::Custom(name) => match self.syms.resolve(name)? {
+                Symbol::Interface(_) => self.name("object_id"),
+                Symbol::Type(t) => self.name(&t.def.name),
+            },
+            Type::Array(_) | Type::Dictionary(_) => self.name(&mangle(ty)),
+        })
+    }
+}
+
+fn write_enum(w: &mut dyn Write, cx: &Cx, name: &str, e: &Enum) -> Result<()> {
+    let ty = cx.name(name);
+    writeln!(w, "typedef {} {};", int_type(&e.backing), ty)?;
+
+    let fits_int = e
+        .fields
+        .iter()
+        .all(|f| i32::try_from(f.value.unwrap()).is_ok());
+
+    if fits_int && !e.fields.is_empty() {
+        writeln!(w, "enum {{")?;
+        for f in &e.fields {
+            writeln!(
+                w,
+                "    {} = {},",
+                cx.constant(name, &f.name),
+                f.value.unwrap()
+            )?;
+        }
+        writeln!(w, "}};")?;
+    } else {
+        // Enumerators must fit in an `int`, so fall back to macros.
+        for f in &e.fields {
+            writeln!(
+                w,
+                "#define {} (({}){})",
+                cx.constant(name, &f.name),
+                ty,
+                literal(&e.backing, f.value.unwrap())
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_flags(w: &mut dyn Write, cx: &Cx, name: &str, f: &Flags) -> Result<()> {
+    let ty = cx.name(name);
+    writeln!(w, "typedef {} {};", int_type(&f.backing), ty)?;
+    for field in &f.fields {
+        writeln!(
+            w,
+            "#define {} (({}){})",
+            cx.constant(name, &field.name),
+            ty,
+            literal(&f.backing, field.value)
+        )?;
+    }
+    Ok(())
+}
+
+fn write_struct(w: &mut dyn Write, cx: &Cx, name: &str, s: &Struct) -> Result<()> {
+    writeln!(w, "struct {} {{", cx.name(name))?;
+    for f in &s.fields {
+        writeln!(w, "    {} {};", cx.ty(&f.ty)?, ident(&f.name))?;
+    }
+    // Empty structs aren't valid C.
+    if s.fields.is_empty() {
+        writeln!(w, "    uint8_t _unused;")?;
+    }
+    writeln!(w, "}};")?;
+    Ok(())
+}
+
+fn write_interface(w: &mut dyn Write, cx: &Cx, i: &InterfaceRef) -> Result<()> {
+    let name = match i.extension {
+        // Avoid `world_world` for implicit interfaces
+        Some(ext) if ext.name != i.name => cx.name(&format!("{}_{}", ext.name, i.name)),
+        _ => cx.name(i.name),
+    };
+
+    docs(w, "", i.docs)?;
+    writeln!(
+        w,
+        "#define {}_NAME \"{}\"",
+        name.to_shouty_snake_case(),
+        i.qualified_name(&cx.syms.namespace().name)
+    )?;
+    writeln!(
+        w,
+        "#define {}_VERSION \"{}.{}.{}\"",
+        name.to_shouty_snake_case(),
+        i.version.0,
+        i.version.1,
+        i.version.2
+    )?;
+
+    if !i.methods.is_empty() {
+        let methods = format!("{}_methods", name);
+        writeln!(w, "\ntypedef struct {} {{", methods)?;
+        for m in i.methods {
+            docs(w, "    ", &m.docs)?;
+            write!(
+                w,
+                "    {} (*{})(void *self",
+                cx.name("status"),
+                ident(&m.name)
+            )?;
+            write_args(w, cx, m)?;
+            if let Some(ret) = &m.ret {
+                write!(w, ", {} *ret", cx.ty(ret)?)?;
+            }
+            writeln!(w, ");")?;
+        }
+        writeln!(w, "}} {};", methods)?;
+    }
+
+    if !i.events.is_empty() {
+        let events = format!("{}_events", name);
+        writeln!(w, "\ntypedef struct {} {{", events)?;
+        for e in i.events {
+            docs(w, "    ", &e.docs)?;
+            write!(w, "    void (*{})(void *self", ident(&e.name))?;
+            write_args(w, cx, e)?;
+            writeln!(w, ");")?;
+        }
+        writeln!(w, "}} {};", events)?;
+    }
+
+    Ok(())
+}
+
+fn write_args(w: &mut dyn Write, cx: &Cx, f: &Func) -> Result<()> {
+    for a in &f.args {
+        write!(w, ", {} {}", cx.ty(&a.ty)?, ident(&a.name))?;
+    }
+    Ok(())
+}
+
+/// Call `f` on every type used anywhere in the namespace.
+fn for_each_type(syms: &Symbols, mut f: impl FnMut(&Type) -> Result<()>) -> Result<()> {
+    for ty in syms.types() {
+        if let TypeKind::Struct(s) = &ty.def.kind {
+            for field in &s.fields {
+                f(&field.ty)?;
+            }
+        }
+    }
+    for i in syms.interfaces() {
+        for func in i.methods.iter().chain(i.events) {
+            for a in &func.args {
+                f(&a.ty)?;
+            }
+            if let Some(ret) = &func.ret {
+                f(ret)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add declarations for `ty`, after any arrays or dictionaries it contains,
+/// unless they're in `seen` already.
+fn collect_compound(
+    cx: &Cx,
+    ty: &Type,
+    out: &mut Vec<String>,
+    seen: &mut BTreeSet<String>,
+) -> Result<()> {
+    if !matches!(ty, Type::Array(_) | Type::Dictionary(_)) || seen.contains(&mangle(ty)) {
+        return Ok(());
+    }
+    let decl = match ty {
+        Type::Array(elem) => {
+            collect_compound(cx, elem, out, seen)?;
+            format!(
+                "typedef struct {0} {{\n    const {1} *ptr;\n    size_t len;\n}} {0};",
+                cx.ty(ty)?,
+                cx.ty(elem)?
+            )
+        }
+        Type::Dictionary(d) => {
+            collect_compound(cx, &d.key, out, seen)?;
+            collect_compound(cx, &d.value, out, seen)?;
+            format!(
+                "/** `keys[i]` maps to `values[i]` */\n\
+                typedef struct {0} {{\n    const {1} *keys;\n    const {2} *values;\n    size_t len;\n}} {0};",
+                cx.ty(ty)?,
+                cx.ty(&d.key)?,
+                cx.ty(&d.value)?
+            )
+        }
+        _ => return Ok(()),
+    };
+    seen.insert(mangle(ty));
+    out.push(decl);
+    Ok(())
+}
+
+/// Structs must be defined after the structs they contain by value.
+fn struct_order<'a>(syms: &Symbols<'a>) -> Result<Vec<TypeRef<'a>>> {
+    fn visit<'a>(
+        syms: &Symbols<'a>,
+        ty: TypeRef<'a>,
+        done: &mut BTreeSet<&'a str>,
+        visiting: &mut BTreeSet<&'a str>,
+        out: &mut Vec<TypeRef<'a>>,
+    ) -> Result<()> {
+        let name = &ty.def.name[..];
+        if done.contains(name) {
+            return Ok(());
+        }
+        if !visiting.insert(name) {
+            bail!("Struct `{}` contains itself", name);
+        }
+        if let TypeKind::Struct(s) = &ty.def.kind {
+            for f in &s.fields {
+                if let Type::Custom(dep) = &f.ty {
+                    if let Symbol::Type(dep) = syms.resolve(dep)? {
+                        visit(syms, dep, done, visiting, out)?;
+                    }
+                }
+            }
+        }
+        visiting.remove(name);
+        done.insert(name);
+        out.push(ty);
+        Ok(())
+    }
+
+    let mut out = Vec::new();
+    let mut done = BTreeSet::new();
+    for &ty in syms.types() {
+        visit(syms, ty, &mut done, &mut BTreeSet::new(), &mut out)?;
+    }
+    Ok(out)
+}
+
+/// A name for an array or dictionary type, like `array_string`.
+fn mangle(ty: &Type) -> String {
+    match ty {
+        Type::Array(t) => format!("array_{}", mangle(t)),
+        Type::Dictionary(d) => format!("dict_{}_{}", mangle(&d.key), mangle(&d.value)),
+        _ => ty.to_string(),
+    }
+}
+
+fn int_type(i: &IntType) -> &'static str {
+    match i {
+        IntType::U8 | IntType::VU8 => "uint8_t",
+        IntType::U16 | IntType::VU16 => "uint16_t",
+        IntType::U32 | IntType::VU32 => "uint32_t",
+        IntType::U64 | IntType::VU64 => "uint64_t",
+        IntType::I8 | IntType::VI8 => "int8_t",
+        IntType::I16 | IntType::VI16 => "int16_t",
+        IntType::I32 | IntType::VI32 => "int32_t",
+        IntType::I64 | IntType::VI64 => "int64_t",
+    }
+}
+
+fn literal(i: &IntType, value: i64) -> String {
+    match i {
+        IntType::U64 | IntType::VU64 => format!("UINT64_C({})", value),
+        IntType::I64 | IntType::VI64 => format!("INT64_C({})", value),
+        _ => value.to_string(),
+    }
+}
+
+fn ident(name: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "auto", "bool", "break", "case", "char", "const", "continue", "default", "do", "double",
+        "else", "enum", "extern", "false", "float", "for", "goto", "if", "inline", "int", "long",
+        "register", "restrict", "ret", "return", "self", "short", "signed", "sizeof", "static",
+        "struct", "switch", "true", "typedef", "union", "unsigned", "void", "volatile", "while",
+    ];
+
+    if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+fn docs(w: &mut dyn Write, indent: &str, docs: &str) -> Result<()> {
+    super::write_docs(
+        w,
+        indent,
+        ["/**", " * ", " */"],
+        &docs.replace("*/", "*\\/"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn gen(hidl: &str) -> String {
+        let mut out = Vec::new();
+        generate(&crate::codegen::parse(hidl), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn enums_and_flags() {
+        let out = gen("namespace test
+            enum Small(u8) { A B = 10 }
+            enum Big(vi64) { A = 3000000000 }
+            flags Bits(vu64) { None = 0 SomeThing = 4 }
+        ");
+        let expected = "
+typedef uint8_t test_Small;
+enum {
+    TEST_SMALL_A = 0,
+    TEST_SMALL_B = 10,
+};
+
+typedef int64_t test_Big;
+#define TEST_BIG_A ((test_Big)INT64_C(3000000000))
+
+typedef uint64_t test_Bits;
+#define TEST_BITS_NONE ((test_Bits)UINT64_C(0))
+#define TEST_BITS_SOME_THING ((test_Bits)UINT64_C(4))
+";
+        let start = out.find("\ntypedef uint8_t test_Small;").unwrap();
+        assert_eq!(&out[start..start + expected.len()], expected);
+    }
+
+    #[test]
+    fn compiles() {
+        let hypercosm = include_str!("../../hypercosm.hidl");
+        // hypercosm.hidl has no arrays or dictionaries of them
+        let nested = "namespace test struct A { x: [][]u8 y: [][string]u8 }";
+        for hidl in [hypercosm, nested] {
+            let ns = crate::codegen::parse(hidl);
+            let mut header = Vec::new();
+            generate(&ns, &mut header).unwrap();
+
+            let dir = std::env::temp_dir().join(format!("hidl-c-{}", std::process::id()));
+            fs_err::create_dir_all(&dir).unwrap();
+            fs_err::write(dir.join("out.h"), &header).unwrap();
+            fs_err::write(dir.join("main.c"), "#include \"out.h\"\n").unwrap();
+
+            let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+            let output = Command::new(cc)
+                .args(["-std=c11", "-Wall", "-Wextra", "-pedantic", "-Werror"])
+                .args(["-fsyntax-only", "main.c"])
+                .current_dir(&dir)
+                .output()
+                .expect("Failed to run C compiler");
+
+            fs_err::remove_dir_all(&dir).unwrap();
+            assert!(
+                output.status.success(),
+                "{}\n{}",
+                String::from_utf8_lossy(&output.stderr),
+                String::from_utf8_lossy(&header)
+            );
+        }
+    }
+}
@@ -0,0 +1,367 @@
+//! C# generator, aimed at Unity.
+//!
+//! Types become structs and enums, and every interface becomes a C#
+//! interface with `Task` returning methods and `Action` events, for the
+//! client library to implement.
+
+use std::io::Write;
+
+use anyhow::Result;
+use heck::{ToLowerCamelCase, ToUpperCamelCase};
+
+use crate::{
+    ast::{Func, IntType, Namespace, PrimType, Type, TypeKind},
+    symbols::{InterfaceRef, Symbol, Symbols, TypeRef},
+};
+
+pub fn generate(ns: &Namespace, w: &mut dyn Write) -> Result<()> {
+    let syms = Symbols::new(ns);
+
+    writeln!(
+        w,
+        "// Generated by hidl from the `{}` namespace. Do not edit.\n",
+        ns.name
+    )?;
+    writeln!(w, "using System;")?;
+    writeln!(w, "using System.Collections.Generic;")?;
+    writeln!(w, "using System.Threading.Tasks;\n")?;
+    // Both are named `Matrix4x4`, but Unity stores columns, and
+    // System.Numerics stores rows of the transpose, so they have the same
+    // memory layout.
+    writeln!(w, "#if UNITY_5_3_OR_NEWER")?;
+    writeln!(w, "using Matrix4x4 = UnityEngine.Matrix4x4;")?;
+    writeln!(w, "#else")?;
+    writeln!(w, "using Matrix4x4 = System.Numerics.Matrix4x4;")?;
+    writeln!(w, "#endif\n")?;
+
+    writeln!(w, "namespace {}", ns.name.to_upper_camel_case())?;
+    writeln!(w, "{{")?;
+    writeln!(w, "{}", PRELUDE)?;
+
+    for ty in syms.types() {
+        writeln!(w)?;
+        write_type(w, &syms, ty)?;
+    }
+
+    for i in syms.interfaces() {
+        writeln!(w)?;
+        write_interface(w, &syms, i)?;
+    }
+
+    writeln!(w, "}}")?;
+
+    Ok(())
+}
+
+const PRELUDE: &str = "    /// <summary>
+    /// The name and version of the protocol interface a C# interface describes.
+    /// </summary>
+    [AttributeUsage(AttributeTargets.Interface, Inherited = false)]
+    public sealed class HidlInterfaceAttribute : Attribute
+    {
+        public string Name { get; }
+        public string Version { get; }
+
+        public HidlInterfaceAttribute(string name, string version)
+        {
+            Name = name;
+            Version = version;
+        }
+    }";
+
+fn write_type(w: &mut dyn Write, syms: &Symbols, ty: &TypeRef) -> Result<()> {
+    let name = ty.def.name.to_upper_camel_case();
+    docs(w, &ty.def.docs)?;
+
+    match &ty.def.kind {
+        TypeKind::Struct(s) => {
+            writeln!(w, "    [Serializable]")?;
+            writeln!(w, "    public struct {}", name)?;
+            writeln!(w, "    {{")?;
+            for f in &s.fields {
+                writeln!(
+                    w,
+                    "        public {} {};",
+                    cs_type(syms, &f.ty)?,
+                    f.name.to_upper_camel_case()
+                )?;
+            }
+            writeln!(w, "    }}")?;
+        }
+        TypeKind::Enum(e) => {
+            writeln!(w, "    public enum {} : {}", name, int_type(&e.backing))?;
+            writeln!(w, "    {{")?;
+            for f in &e.fields {
+                writeln!(
+                    w,
+                    "        {} = {},",
+                    f.name.to_upper_camel_case(),
+                    f.value.unwrap()
+                )?;
+            }
+            writeln!(w, "    }}")?;
+        }
+        TypeKind::Flags(f) => {
+            writeln!(w, "    [Flags]")?;
+            writeln!(w, "    public enum {} : {}", name, int_type(&f.backing))?;
+            writeln!(w, "    {{")?;
+            for field in &f.fields {
+                writeln!(
+                    w,
+                    "        {} = {},",
+                    field.name.to_upper_camel_case(),
+                    field.value
+                )?;
+            }
+            writeln!(w, "    }}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_interface(w: &mut dyn Write, syms: &Symbols, i: &InterfaceRef) -> Result<()> {
+    docs(w, i.docs)?;
+    writeln!(
+        w,
+        "    [HidlInterface(\"{}\", \"{}.{}.{}\")]",
+        i.qualified_name(&syms.namespace().name),
+        i.version.0,
+        i.version.1,
+        i.version.2
+    )?;
+    writeln!(w, "    public interface I{}", i.name.to_upper_camel_case())?;
+    writeln!(w, "    {{")?;
+
+    for (n, e) in i.events.iter().enumerate() {
+        if n != 0 {
+            writeln!(w)?;
+        }
+        docs_indent(w, "        ", &e.docs)?;
+        let args = e
+            .args
+            .iter()
+            .map(|a| cs_type(syms, &a.ty))
+            .collect::<Result<Vec<_>>>()?;
+        let action = if args.is_empty() {
+            "Action".to_owned()
+        } else {
+            format!("Action<{}>", args.join(", "))
+        };
+        writeln!(
+            w,
+            "        event {} {};",
+            action,
+            e.name.to_upper_camel_case()
+        )?;
+    }
+
+    for (n, m) in i.methods.iter().enumerate() {
+        if n != 0 || !i.events.is_empty() {
+            writeln!(w)?;
+        }
+        docs_indent(w, "        ", &m.docs)?;
+        let ret = match &m.ret {
+            Some(ret) => format!("Task<{}>", cs_type(syms, ret)?),
+            None => "Task".to_owned(),
+        };
+        writeln!(
+            w,
+            "        {} {}Async({});",
+            ret,
+            m.name.to_upper_camel_case(),
+            params(syms, m)?
+        )?;
+    }
+
+    writeln!(w, "    }}")?;
+
+    Ok(())
+}
+
+fn params(syms: &Symbols, f: &Func) -> Result<String> {
+    let params = f
+        .args
+        .iter()
+        .map(|a| Ok(format!("{} {}", cs_type(syms, &a.ty)?, ident(&a.name))))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(params.join(", "))
+}
+
+fn cs_type(syms: &Symbols, ty: &Type) -> Result<String> {
+    Ok(match ty {
+        Type::Primitive(p) => match p {
+            PrimType::String => "string",
+            PrimType::Object => "ulong",
+            PrimType::Uuid => "Guid",
+            PrimType::Bytes => "byte[]",
+            PrimType::Bool => "bool",
+            PrimType::Matrix4x4 => "Matrix4x4",
+            PrimType::F32 => "float",
+            PrimType::F64 => "double",
+        }
+        .to_owned(),
+        Type::IntType(i) => int_type(i).to_owned(),
+        Type::Custom(name) => match syms.resolve(name)? {
+            // Object IDs, like `object`
+            Symbol::Interface(_) => "ulong".to_owned(),
+            Symbol::Type(t) => t.def.name.to_upper_camel_case(),
+        },
+        Type::Array(t) => format!("{}[]", cs_type(syms, t)?),
+        Type::Dictionary(d) => format!(
+            "Dictionary<{}, {}>",
+            cs_type(syms, &d.key)?,
+            cs_type(syms, &d.value)?
+        ),
+    })
+}
+
+fn int_type(i: &IntType) -> &'static str {
+    match i {
+        IntType::U8 | IntType::VU8 => "byte",
+        IntType::U16 | IntType::VU16 => "ushort",
+        IntType::U32 | IntType::VU32 => "uint",
+        IntType::U64 | IntType::VU64 => "ulong",
+        IntType::I8 | IntType::VI8 => "sbyte",
+        IntType::I16 | IntType::VI16 => "short",
+        IntType::I32 | IntType::VI32 => "int",
+        IntType::I64 | IntType::VI64 => "long",
+    }
+}
+
+fn ident(name: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "abstract",
+        "as",
+        "base",
+        "bool",
+        "break",
+        "byte",
+        "case",
+        "catch",
+        "char",
+        "checked",
+        "class",
+        "const",
+        "continue",
+        "decimal",
+        "default",
+        "delegate",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "event",
+        "explicit",
+        "extern",
+        "false",
+        "finally",
+        "fixed",
+        "float",
+        "for",
+        "foreach",
+        "goto",
+        "if",
+        "implicit",
+        "in",
+        "int",
+        "interface",
+        "internal",
+        "is",
+        "lock",
+        "long",
+        "namespace",
+        "new",
+        "null",
+        "object",
+        "operator",
+        "out",
+        "override",
+        "params",
+        "private",
+        "protected",
+        "public",
+        "readonly",
+        "ref",
+        "return",
+        "sbyte",
+        "sealed",
+        "short",
+        "sizeof",
+        "stackalloc",
+        "static",
+        "string",
+        "struct",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typeof",
+        "uint",
+        "ulong",
+        "unchecked",
+        "unsafe",
+        "ushort",
+        "using",
+        "virtual",
+        "void",
+        "volatile",
+        "while",
+    ];
+
+    let name = name.to_lower_camel_case();
+    if RESERVED.contains(&&name[..]) {
+        format!("@{}", name)
+    } else {
+        name
+    }
+}
+
+fn docs(w: &mut dyn Write, docs: &str) -> Result<()> {
+    docs_indent(w, "    ", docs)
+}
+
+fn docs_indent(w: &mut dyn Write, indent: &str, docs: &str) -> Result<()> {
+    let docs = docs
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    super::write_docs(
+        w,
+        indent,
+        ["/// <summary>", "/// ", "/// </summary>"],
+        &docs,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hypercosm() {
+        let ns = crate::codegen::parse(include_str!("../../hypercosm.hidl"));
+        let mut out = Vec::new();
+        generate(&ns, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let expected = r#"    [HidlInterface("hypercosm.asset_delivery.asset_delivery", "0.1.0")]
+    public interface IAssetDelivery
+    {
+        event Action<Asset[]> LoadAssets;
+
+        event Action<Guid[]> UnloadAssets;
+
+        Task<Asset> FetchByIdAsync(Guid id);
+"#;
+        let start = out.find(expected.lines().next().unwrap()).unwrap();
+        assert_eq!(&out[start..start + expected.len()], expected);
+
+        assert!(out.contains("    [Flags]\n    public enum EntityAttrs : ulong\n"));
+        assert!(out.contains(
+            "        Task<ulong> LoadWasmModuleAsync(Guid assetId, Dictionary<string, string> exports);"
+        ));
+    }
+}
@@ -0,0 +1,378 @@
+//! [JSON Schema] for the *values* of a namespace, so JSON based tools (like
+//! the debugging bridge) can validate payloads. For the shape of the IDL
+//! itself, see `json/hypercosm.json`.
+//!
+//! Everything is in `$defs`:
+//!
+//! - Each type definition, by name.
+//! - `{Interface}.methods.{method}.args` and `.return` for each method, and
+//!   `{Interface}.events.{event}.args` for each event. Arguments are an
+//!   object keyed by argument name, and a method without a return type
+//!   returns `null`.
+//!
+//! Values are represented as:
+//!
+//! - Ints as JSON integers, bounded by their type. Tools should be careful
+//!   with 64 bit ints, as many JSON parsers lose precision past 2^53.
+//! - `uuid` as a string in the usual hyphenated form, `bytes` as a base64
+//!   string, and `matrix4x4` as an array of 16 numbers in column major order.
+//! - `object` and interfaces as the object ID.
+//! - Structs as objects with every field required.
+//! - Enums as the name of the variant, or its value.
+//! - Flags as the bitwise OR of the set flags. JSON Schema can't check
+//!   individual bits, so only the range is checked, and the flags are listed
+//!   in `x-hidl-flags`.
+//! - Dictionaries as objects. Keys must have a string form, so ints, `bool`,
+//!   `uuid`, objects, enums, and flags are allowed, but anything else is an
+//!   error.
+//!
+//! [JSON Schema]: https://json-schema.org/draft/2020-12/json-schema-core
+
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    ast::{Func, IntType, Namespace, PrimType, Type, TypeDef, TypeKind},
+    symbols::{Symbol, Symbols},
+};
+
+const UNSIGNED_PATTERN: &str = "^(0|[1-9][0-9]*)$";
+const SIGNED_PATTERN: &str = "^(0|-?[1-9][0-9]*)$";
+
+pub fn generate(ns: &Namespace, w: &mut dyn Write) -> Result<()> {
+    let syms = Symbols::new(ns);
+    let mut cx = Schemas::new(&syms, "#/$defs/");
+
+    let mut defs = cx.types();
+    for i in syms.interfaces() {
+        for (kind, funcs) in [("methods", i.methods), ("events", i.events)] {
+            for f in funcs {
+                let path = format!("{}.{}.{}", i.name, kind, f.name);
+                defs.insert(format!("{}.args", path), cx.args(&path, f));
+
+                if kind == "methods" {
+                    defs.insert(format!("{}.return", path), cx.ret(&path, f));
+                }
+            }
+        }
+    }
+    cx.finish()?;
+
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$comment": format!("Generated by hidl from the `{}` namespace. Do not edit.", ns.name),
+        "title": format!("{} values", ns.name),
+        "$defs": defs,
+    });
+    serde_json::to_writer_pretty(&mut *w, &schema)?;
+    writeln!(w)?;
+
+    Ok(())
+}
+
+/// Builds the schemas for a namespace, collecting errors for
+/// [`Schemas::finish`]. Also used by other JSON based targets.
+pub(super) struct Schemas<'a, 'b> {
+    syms: &'b Symbols<'a>,
+    /// Where type definitions are, for `$ref`s
+    refs: &'static str,
+    errors: Vec<String>,
+}
+
+impl<'a, 'b> Schemas<'a, 'b> {
+    pub(super) fn new(syms: &'b Symbols<'a>, refs: &'static str) -> Self {
+        Self {
+            syms,
+            refs,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Report every error found so far.
+    pub(super) fn finish(self) -> Result<()> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        bail!(
+            "`{}` can't be described with JSON Schema:\n{}",
+            self.syms.namespace().name,
+            self.errors
+                .iter()
+                .map(|e| format!("  - {}", e))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+
+    /// Every type definition, by name.
+    pub(super) fn types(&mut self) -> Map<String, Value> {
+        let syms = self.syms;
+        syms.types()
+            .iter()
+            .map(|t| (t.def.name.clone(), self.type_def(t.def)))
+            .collect()
+    }
+
+    fn type_def(&mut self, def: &TypeDef) -> Value {
+        let schema = match &def.kind {
+            TypeKind::Struct(s) => {
+                let mut properties = Map::new();
+                for f in &s.fields {
+                    let path = format!("{}.{}", def.name, f.name);
+                    properties.insert(f.name.clone(), self.ty(&path, &f.ty));
+                }
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": s.fields.iter().map(|f| &f.name).collect::<Vec<_>>(),
+                    "additionalProperties": false,
+                })
+            }
+            TypeKind::Enum(e) => {
+                let names = e.fields.iter().map(|f| json!(f.name));
+                let values = e.fields.iter().filter_map(|f| f.value).map(|v| json!(v));
+                json!({ "enum": names.chain(values).collect::<Vec<_>>() })
+            }
+            TypeKind::Flags(f) => {
+                let mask = f.fields.iter().fold(0, |mask, f| mask | f.value as u64);
+                // The smallest all ones value that covers every flag
+                let max = u64::MAX >> mask.leading_zeros();
+                json!({
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": max,
+                    "x-hidl-flags": f
+                        .fields
+                        .iter()
+                        .map(|f| (f.name.clone(), json!(f.value)))
+                        .collect::<Map<_, _>>(),
+                })
+            }
+        };
+        described(schema, &def.docs)
+    }
+
+    /// The arguments of `f`, as an object keyed by name.
+    pub(super) fn args(&mut self, path: &str, f: &Func) -> Value {
+        let mut properties = Map::new();
+        for a in &f.args {
+            let path = format!("{}.args.{}", path, a.name);
+            properties.insert(a.name.clone(), self.ty(&path, &a.ty));
+        }
+        let schema = json!({
+            "type": "object",
+            "properties": properties,
+            "required": f.args.iter().map(|a| &a.name).collect::<Vec<_>>(),
+            "additionalProperties": false,
+        });
+        described(schema, &f.docs)
+    }
+
+    /// What the method `f` returns, which is `null` if it has no return type.
+    pub(super) fn ret(&mut self, path: &str, f: &Func) -> Value {
+        match &f.ret {
+            Some(ty) => self.ty(&format!("{}.return", path), ty),
+            None => json!({ "type": "null" }),
+        }
+    }
+
+    pub(super) fn ty(&mut self, path: &str, ty: &Type) -> Value {
+        match ty {
+            Type::Primitive(p) => match p {
+                PrimType::Bool => json!({ "type": "boolean" }),
+                PrimType::String => json!({ "type": "string" }),
+                PrimType::Bytes => json!({ "type": "string", "contentEncoding": "base64" }),
+                PrimType::Uuid => json!({ "type": "string", "format": "uuid" }),
+                PrimType::Object => object(),
+                PrimType::F32 | PrimType::F64 => json!({ "type": "number" }),
+                PrimType::Matrix4x4 => json!({
+                    "type": "array",
+                    "items": { "type": "number" },
+                    "minItems": 16,
+                    "maxItems": 16,
+                }),
+            },
+            Type::IntType(i) => {
+                let (min, max) = int_range(i);
+                json!({ "type": "integer", "minimum": min, "maximum": max })
+            }
+            Type::Custom(name) => match self.syms.get(name) {
+                Some(Symbol::Interface(i)) => {
+                    described(object(), &format!("A `{}` object", i.name))
+                }
+                Some(Symbol::Type(t)) => json!({ "$ref": format!("{}{}", self.refs, t.def.name) }),
+                None => {
+                    self.errors
+                        .push(format!("`{}`: unknown type `{}`", path, name));
+                    Value::Null
+                }
+            },
+            Type::Array(inner) => json!({ "type": "array", "items": self.ty(path, inner) }),
+            Type::Dictionary(d) => {
+                let mut schema = json!({
+                    "type": "object",
+                    "additionalProperties": self.ty(path, &d.value),
+                });
+                if let Some(keys) = self.key(path, &d.key) {
+                    schema["propertyNames"] = keys;
+                }
+                schema
+            }
+        }
+    }
+
+    /// The schema for the string form of a dictionary key, if it needs one.
+    fn key(&mut self, path: &str, ty: &Type) -> Option<Value> {
+        let pattern = |p| Some(json!({ "pattern": p }));
+        match ty {
+            Type::Primitive(PrimType::String) => None,
+            Type::Primitive(PrimType::Uuid) => Some(json!({ "format": "uuid" })),
+            Type::Primitive(PrimType::Bool) => Some(json!({ "enum": ["false", "true"] })),
+            Type::Primitive(PrimType::Object) => pattern(UNSIGNED_PATTERN),
+            Type::IntType(i) if int_range(i).0 < 0 => pattern(SIGNED_PATTERN),
+            Type::IntType(_) => pattern(UNSIGNED_PATTERN),
+            Type::Custom(name) => match self.syms.get(name) {
+                Some(Symbol::Interface(_)) => pattern(UNSIGNED_PATTERN),
+                Some(Symbol::Type(t)) => match &t.def.kind {
+                    TypeKind::Enum(e) => {
+                        let names = e.fields.iter().map(|f| f.name.clone());
+                        let values = e.fields.iter().filter_map(|f| f.value);
+                        let values = values.map(|v| v.to_string());
+                        Some(json!({ "enum": names.chain(values).collect::<Vec<_>>() }))
+                    }
+                    TypeKind::Flags(_) => pattern(UNSIGNED_PATTERN),
+                    TypeKind::Struct(_) => self.bad_key(path, ty),
+                },
+                // Reported when the key is used as a type
+                None => None,
+            },
+            _ => self.bad_key(path, ty),
+        }
+    }
+
+    fn bad_key(&mut self, path: &str, ty: &Type) -> Option<Value> {
+        self.errors.push(format!(
+            "`{}`: `{}` can't be a dictionary key, as it has no string form",
+            path, ty
+        ));
+        None
+    }
+}
+
+/// An object ID.
+pub(super) fn object() -> Value {
+    json!({ "type": "integer", "minimum": 0, "maximum": u64::MAX })
+}
+
+pub(super) fn described(mut schema: Value, docs: &str) -> Value {
+    let docs = docs.trim();
+    if !docs.is_empty() {
+        schema["description"] = json!(docs);
+    }
+    schema
+}
+
+fn int_range(i: &IntType) -> (i64, u64) {
+    match i {
+        IntType::U8 | IntType::VU8 => (0, u8::MAX.into()),
+        IntType::U16 | IntType::VU16 => (0, u16::MAX.into()),
+        IntType::U32 | IntType::VU32 => (0, u32::MAX.into()),
+        IntType::U64 | IntType::VU64 => (0, u64::MAX),
+        IntType::I8 | IntType::VI8 => (i8::MIN.into(), i8::MAX as u64),
+        IntType::I16 | IntType::VI16 => (i16::MIN.into(), i16::MAX as u64),
+        IntType::I32 | IntType::VI32 => (i32::MIN.into(), i32::MAX as u64),
+        IntType::I64 | IntType::VI64 => (i64::MIN, i64::MAX as u64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn gen(hidl: &str) -> Result<Value> {
+        let mut out = Vec::new();
+        generate(&crate::codegen::parse(hidl), &mut out)?;
+        Ok(serde_json::from_slice(&out).unwrap())
+    }
+
+    /// Check payloads against the `def` schema.
+    fn validate(schema: &Value, def: &str, payload: Value) -> bool {
+        let mut schema = schema.clone();
+        schema["$ref"] = json!(format!("#/$defs/{}", def));
+        jsonschema::validator_for(&schema)
+            .unwrap()
+            .is_valid(&payload)
+    }
+
+    #[test]
+    fn hypercosm() {
+        let schema = gen(include_str!("../../hypercosm.hidl")).unwrap();
+        let info = json!({
+            "asset_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "entity": 12,
+            "transformation": vec![1.0; 16],
+            "attrs": 3,
+        });
+
+        assert!(validate(&schema, "EntityInfo", info.clone()));
+        let mut wrong = info.clone();
+        wrong["attrs"] = json!(256);
+        assert!(!validate(&schema, "EntityInfo", wrong));
+        let mut missing = info;
+        missing.as_object_mut().unwrap().remove("entity");
+        assert!(!validate(&schema, "EntityInfo", missing));
+
+        let args = "execution_context.methods.load_wasm_module.args";
+        let load =
+            json!({ "asset_id": "67e55044-10b1-426f-9247-bb680e5fe0c8", "exports": { "a": "b" } });
+        assert!(validate(&schema, args, load));
+        assert!(validate(&schema, "Root.methods.ping.return", Value::Null));
+    }
+
+    #[test]
+    fn types() {
+        let schema = gen("namespace test
+            enum Kind(vi8) { A = -1 B }
+            flags Bits(u16) { X = 1 Y = 4 }
+            struct Lookup { by_kind: [Kind]f32 by_id: [vi32]bool }")
+        .unwrap();
+
+        assert_eq!(
+            schema["$defs"]["Kind"],
+            json!({ "enum": ["A", "B", -1, 0] })
+        );
+        assert_eq!(
+            schema["$defs"]["Bits"],
+            json!({
+                "type": "integer",
+                "minimum": 0,
+                "maximum": 7,
+                "x-hidl-flags": { "X": 1, "Y": 4 },
+            })
+        );
+        assert!(validate(
+            &schema,
+            "Lookup",
+            json!({
+                "by_kind": { "A": 1.5, "0": 2 },
+                "by_id": { "-3": true },
+            })
+        ));
+        assert!(!validate(
+            &schema,
+            "Lookup",
+            json!({ "by_kind": { "C": 1 }, "by_id": {} })
+        ));
+
+        assert_eq!(
+            gen("namespace test struct Bad { a: [f32]u8 }")
+                .unwrap_err()
+                .to_string(),
+            "`test` can't be described with JSON Schema:
+  - `Bad.a`: `f32` can't be a dictionary key, as it has no string form"
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Lua generator, for scripts run by `execution_context`.
+//!
+//! Output is a directory with two files:
+//!
+//! - `{namespace}.lua`: [LuaLS] annotations for every type and interface,
+//!   so script authors get completion and type checking. It only declares
+//!   things, so isn't meant to be run.
+//! - `{namespace}_bindings.lua`: A table describing every interface, for
+//!   hosts to register their native functions from.
+//!
+//! Names that are Lua keywords get a `_` suffix (so `end` is `end_`). Such
+//! methods have a `lua_name` in the bindings, which is what scripts call.
+//!
+//! [LuaLS]: https://luals.github.io/wiki/annotations/
+
+use std::io::Write;
+
+use anyhow::Result;
+use camino::Utf8Path;
+
+use crate::{
+    ast::{Func, Namespace, PrimType, Type, TypeKind},
+    symbols::{InterfaceRef, Symbol, Symbols, TypeRef},
+    vfs,
+};
+
+/// The interface every other interface extends, if the namespace has it.
+const ROOT_INTERFACE: &str = "Object";
+
+pub fn generate(ns: &Namespace, out: &Utf8Path, fs: &mut vfs::FS) -> Result<()> {
+    let syms = Symbols::new(ns);
+
+    let w = fs.open(out.join(&ns.name).with_extension("lua"));
+    generate_annotations(&syms, w)?;
+
+    let w = fs.open(out.join(format!("{}_bindings.lua", ns.name)));
+    generate_bindings(&syms, w)
+}
+
+fn generate_annotations(syms: &Symbols, w: &mut dyn Write) -> Result<()> {
+    let ns = syms.namespace();
+
+    writeln!(w, "---@meta {}", ns.name)?;
+    writeln!(
+        w,
+        "-- Generated by hidl from the `{}` namespace. Do not edit.\n",
+        ns.name
+    )?;
+    writeln!(w, "---@alias Uuid string")?;
+    writeln!(w, "---Column major")?;
+    writeln!(w, "---@alias Matrix4x4 number[]")?;
+
+    for ty in syms.types() {
+        writeln!(w)?;
+        write_type(w, syms, ty)?;
+    }
+
+    for i in syms.interfaces() {
+        writeln!(w)?;
+        write_interface(w, syms, i)?;
+    }
+
+    Ok(())
+}
+
+fn write_type(w: &mut dyn Write, syms: &Symbols, ty: &TypeRef) -> Result<()> {
+    let def = ty.def;
+    docs(w, &def.docs)?;
+
+    match &def.kind {
+        TypeKind::Struct(s) => {
+            writeln!(w, "---@class {}", ident(&def.name))?;
+            for f in &s.fields {
+                writeln!(w, "---@field {} {}", ident(&f.name), lua_type(syms, &f.ty)?)?;
+            }
+        }
+        TypeKind::Enum(e) => {
+            writeln!(w, "---@enum {}", ident(&def.name))?;
+            writeln!(w, "{} = {{", ident(&def.name))?;
+            for f in &e.fields {
+                writeln!(w, "    {} = {},", ident(&f.name), f.value.unwrap())?;
+            }
+            writeln!(w, "}}")?;
+        }
+        TypeKind::Flags(f) => {
+            writeln!(w, "---Flags, combine them with `|`")?;
+            writeln!(w, "---@enum {}", ident(&def.name))?;
+            writeln!(w, "{} = {{", ident(&def.name))?;
+            for field in &f.fields {
+                writeln!(w, "    {} = {},", ident(&field.name), field.value)?;
+            }
+            writeln!(w, "}}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_interface(w: &mut dyn Write, syms: &Symbols, i: &InterfaceRef) -> Result<()> {
+    let name = ident(i.name);
+
+    docs(w, i.docs)?;
+    match root(syms) {
+        Some(root) if i.name != ROOT_INTERFACE => writeln!(w, "---@class {}: {}", name, root)?,
+        _ => writeln!(w, "---@class {}", name)?,
+    }
+    writeln!(w, "{} = {{}}", name)?;
+
+    for m in i.methods {
+        writeln!(w)?;
+        docs(w, &m.docs)?;
+        write_params(w, syms, m)?;
+        if let Some(ret) = &m.ret {
+            writeln!(w, "---@return {}", lua_type(syms, ret)?)?;
+        }
+        writeln!(w, "function {}:{}({}) end", name, ident(&m.name), names(m))?;
+    }
+
+    if !i.events.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "---Register a handler for an event")?;
+        for e in i.events {
+            let params = e
+                .args
+                .iter()
+                .map(|a| Ok(format!("{}: {}", ident(&a.name), lua_type(syms, &a.ty)?)))
+                .collect::<Result<Vec<_>>>()?;
+            writeln!(
+                w,
+                "---@overload fun(self: {}, event: \"{}\", handler: fun({}))",
+                name,
+                e.name,
+                params.join(", ")
+            )?;
+        }
+        writeln!(w, "---@param event string")?;
+        writeln!(w, "---@param handler function")?;
+        writeln!(w, "function {}:on(event, handler) end", name)?;
+    }
+
+    Ok(())
+}
+
+fn write_params(w: &mut dyn Write, syms: &Symbols, f: &Func) -> Result<()> {
+    for a in &f.args {
+        writeln!(w, "---@param {} {}", ident(&a.name), lua_type(syms, &a.ty)?)?;
+    }
+    Ok(())
+}
+
+fn names(f: &Func) -> String {
+    f.args
+        .iter()
+        .map(|a| ident(&a.name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The class every interface extends, if the namespace has one.
+fn root(syms: &Symbols) -> Option<&'static str> {
+    match syms.get(ROOT_INTERFACE) {
+        Some(Symbol::Interface(_)) => Some(ROOT_INTERFACE),
+        _ => None,
+    }
+}
+
+fn ident(name: &str) -> String {
+    const LUA_KEYWORDS: &[&str] = &[
+        "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if",
+        "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+    ];
+
+    if LUA_KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+fn generate_bindings(syms: &Symbols, w: &mut dyn Write) -> Result<()> {
+    let ns = syms.namespace();
+
+    writeln!(
+        w,
+        "-- Generated by hidl from the `{}` namespace. Do not edit.",
+        ns.name
+    )?;
+    writeln!(w, "--")?;
+    writeln!(
+        w,
+        "-- Describes every interface, so hosts can register a native function"
+    )?;
+    writeln!(w, "-- for each method. Types are written in HIDL syntax.\n")?;
+    writeln!(w, "return {{")?;
+    writeln!(w, "    namespace = \"{}\",", ns.name)?;
+    writeln!(w, "    interfaces = {{")?;
+    for i in syms.interfaces() {
+        writeln!(w, "        {{")?;
+        writeln!(w, "            name = \"{}\",", i.name)?;
+        writeln!(
+            w,
+            "            qualified_name = \"{}\",",
+            i.qualified_name(&ns.name)
+        )?;
+        writeln!(
+            w,
+            "            version = \"{}.{}.{}\",",
+            i.version.0, i.version.1, i.version.2
+        )?;
+        write_funcs(w, "methods", i.methods)?;
+        write_funcs(w, "events", i.events)?;
+        writeln!(w, "        }},")?;
+    }
+    writeln!(w, "    }},")?;
+    writeln!(w, "}}")?;
+
+    Ok(())
+}
+
+fn write_funcs(w: &mut dyn Write, name: &str, funcs: &[Func]) -> Result<()> {
+    if funcs.is_empty() {
+        writeln!(w, "            {} = {{}},", name)?;
+        return Ok(());
+    }
+
+    writeln!(w, "            {} = {{", name)?;
+    for f in funcs {
+        let args = f
+            .args
+            .iter()
+            .map(|a| format!("{{ name = \"{}\", type = \"{}\" }}", a.name, a.ty))
+            .collect::<Vec<_>>();
+        let args = if args.is_empty() {
+            "{}".to_owned()
+        } else {
+            format!("{{ {} }}", args.join(", "))
+        };
+        write!(w, "                {{ name = \"{}\"", f.name)?;
+        if ident(&f.name) != f.name {
+            write!(w, ", lua_name = \"{}\"", ident(&f.name))?;
+        }
+        write!(w, ", args = {}", args)?;
+        if let Some(ret) = &f.ret {
+            write!(w, ", ret = \"{}\"", ret)?;
+        }
+        writeln!(w, " }},")?;
+    }
+    writeln!(w, "            }},")?;
+    Ok(())
+}
+
+fn lua_type(syms: &Symbols, ty: &Type) -> Result<String> {
+    Ok(match ty {
+        Type::Primitive(p) => match p {
+            // Lua strings can hold arbitrary bytes
+            PrimType::String | PrimType::Bytes => "string",
+            // Any object, which is only known to be a table without a root
+            PrimType::Object => root(syms).unwrap_or("table"),
+            PrimType::Uuid => "Uuid",
+            PrimType::Bool => "boolean",
+            PrimType::Matrix4x4 => "Matrix4x4",
+            PrimType::F32 | PrimType::F64 => "number",
+        }
+        .to_owned(),
+        Type::IntType(_) => "integer".to_owned(),
+        Type::Custom(name) => {
+            // Check it exists
+            syms.resolve(name)?;
+            ident(name)
+        }
+        Type::Array(t) => match **t {
+            Type::Array(_) | Type::Dictionary(_) => format!("({})[]", lua_type(syms, t)?),
+            _ => format!("{}[]", lua_type(syms, t)?),
+        },
+        Type::Dictionary(d) => format!(
+            "table<{}, {}>",
+            lua_type(syms, &d.key)?,
+            lua_type(syms, &d.value)?
+        ),
+    })
+}
+
+fn docs(w: &mut dyn Write, docs: &str) -> Result<()> {
+    super::write_docs(w, "", ["", "---", ""], docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hypercosm() {
+        let ns = crate::codegen::parse(include_str!("../../hypercosm.hidl"));
+        let syms = Symbols::new(&ns);
+
+        let mut out = Vec::new();
+        generate_annotations(&syms, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let expected = r#"---@class world: Object
+world = {}
+
+---Register a handler for an event
+---@overload fun(self: world, event: "add_entities", handler: fun(entities: EntityInfo[]))
+---@overload fun(self: world, event: "update_entities", handler: fun(entities: EntityInfo[]))
+---@overload fun(self: world, event: "remove_entities", handler: fun(entities: Entity[]))
+---@param event string
+---@param handler function
+function world:on(event, handler) end
+"#;
+        assert!(out.contains(expected), "{}", out);
+        assert!(out.contains(
+            "---@param exports table<string, string>\n---@return integer\n\
+            function execution_context:load_wasm_module(asset_id, exports) end\n"
+        ));
+
+        let mut out = Vec::new();
+        generate_bindings(&syms, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let expected = r#"        {
+            name = "Entity",
+            qualified_name = "hypercosm.world.Entity",
+            version = "0.1.0",
+            methods = {
+                { name = "interact", args = {} },
+            },
+            events = {},
+        },
+"#;
+        assert_eq!(
+            &out[out
+                .find("        {\n            name = \"Entity\"")
+                .unwrap()..][..expected.len()],
+            expected
+        );
+    }
+
+    #[test]
+    fn keywords() {
+        let ns = crate::codegen::parse(
+            "namespace test
+            interface Loop { methods { end(function: u8, local: object) -> repeat } }
+            enum repeat(u8) { until nil }",
+        );
+        let syms = Symbols::new(&ns);
+
+        let mut out = Vec::new();
+        generate_annotations(&syms, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(
+            out.contains("repeat_ = {\n    until_ = 0,\n    nil_ = 1,\n}"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains(
+                "---@param function_ integer\n---@param local_ table\n---@return repeat_\n\
+            function Loop:end_(function_, local_) end\n"
+            ),
+            "{}",
+            out
+        );
+
+        let mut out = Vec::new();
+        generate_bindings(&syms, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(
+            "{ name = \"end\", lua_name = \"end_\", args = { { name = \"function\", type = \"u8\" }"
+        ), "{}", out);
+    }
+}
@@ -0,0 +1,109 @@
+//! Client library generators.
+//!
+//! Each backend takes a lowered [`Namespace`] (see [`crate::hir`]), and
+//! produces code following the encoding in `spec/encoding.md`.
+
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use camino::Utf8Path;
+
+use crate::{ast::Namespace, vfs};
+
+pub mod c;
+pub mod csharp;
+pub mod json_schema;
+pub mod lua;
+pub mod openrpc;
+pub mod plugin;
+pub mod proto;
+pub mod python;
+pub mod template;
+pub mod typescript;
+pub mod wit;
+
+/// Names accepted by [`generate`].
+///
+/// Most targets write a single file to `out`, but some write several files
+/// into `out` as a directory.
+pub const TARGETS: &[&str] = &[
+    "c",
+    "csharp",
+    "json-schema",
+    "lua",
+    "openrpc",
+    "proto",
+    "python",
+    "template",
+    "typescript",
+    "wit",
+];
+
+/// Built in targets that write into `out` as a directory.
+const DIRECTORY_TARGETS: &[&str] = &["lua", "openrpc", "python"];
+
+/// Run a built in target, or the [`plugin`] for `target` if there isn't one.
+///
+/// Only `template` and plugins take `parameters`. Plugins always write into
+/// `out` as a directory. Targets that write into a directory own it (see
+/// [`vfs::FS::own_dir`]).
+pub fn generate(
+    target: &str,
+    ns: &Namespace,
+    out: &Utf8Path,
+    parameters: &[String],
+    fs: &mut vfs::FS,
+) -> Result<()> {
+    if TARGETS.contains(&target) && target != "template" && !parameters.is_empty() {
+        bail!("The built in `{}` target doesn't take parameters", target);
+    }
+    if !TARGETS.contains(&target) || DIRECTORY_TARGETS.contains(&target) {
+        fs.own_dir(out);
+    }
+
+    match target {
+        "c" => c::generate(ns, fs.open(out)),
+        "csharp" => csharp::generate(ns, fs.open(out)),
+        "json-schema" => json_schema::generate(ns, fs.open(out)),
+        "lua" => lua::generate(ns, out, fs),
+        "openrpc" => openrpc::generate(ns, out, fs),
+        "proto" => proto::generate(ns, fs.open(out)),
+        "python" => python::generate(ns, out, fs),
+        "template" => template::generate(ns, out, parameters, fs),
+        "typescript" => typescript::generate(ns, fs.open(out)),
+        "wit" => wit::generate(ns, fs.open(out)),
+        _ => plugin::generate(target, ns, out, parameters, fs),
+    }
+}
+
+/// Write `docs` as a block comment, with each line starting with `prefix`.
+///
+/// Nothing is written if there are no docs.
+fn write_docs(
+    w: &mut dyn Write,
+    indent: &str,
+    [open, prefix, close]: [&str; 3],
+    docs: &str,
+) -> Result<()> {
+    let docs = docs.trim_end();
+    if docs.is_empty() {
+        return Ok(());
+    }
+
+    if !open.is_empty() {
+        writeln!(w, "{}{}", indent, open)?;
+    }
+    for line in docs.lines() {
+        let line = format!("{}{}{}", indent, prefix, line);
+        writeln!(w, "{}", line.trim_end())?;
+    }
+    if !close.is_empty() {
+        writeln!(w, "{}{}", indent, close)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) fn parse(hidl: &str) -> Namespace {
+    crate::hir::lower_namespace(crate::grammar::NamespaceParser::new().parse(hidl).unwrap())
+}
@@ -0,0 +1,303 @@
+//! [OpenRPC] and [AsyncAPI] descriptions of a JSON-RPC bridge to the
+//! protocol, so off the shelf JSON-RPC tools can drive a node.
+//!
+//! Writes `{namespace}.openrpc.json` and `{namespace}.asyncapi.json` into
+//! `out`. Values are represented as described in [`super::json_schema`].
+//!
+//! The bridge is assumed to work like this:
+//!
+//! - Each method is called as `{qualified interface name}.{method}`, with
+//!   params by name. The object to call it on is the extra `target` param,
+//!   so arguments named `target` are an error.
+//! - Each event is sent to the client as a notification, named
+//!   `{qualified interface name}.events.{event}`, with the object that
+//!   raised it as `target`. OpenRPC has no way to mark a method as sent by
+//!   the server, so events are also listed as methods, without a result and
+//!   tagged `event`. The AsyncAPI document describes the same notifications
+//!   properly, with a channel per interface.
+//!
+//! HIDL namespaces aren't versioned as a whole, so `info.version` is always
+//! `0.0.0`, and each method has its interface's version in
+//! `x-hidl-version`.
+//!
+//! [OpenRPC]: https://spec.open-rpc.org/
+//! [AsyncAPI]: https://www.asyncapi.com/docs/reference/specification/v3.0.0
+
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use camino::Utf8Path;
+use serde_json::{json, Map, Value};
+
+use super::json_schema::{described, object, Schemas};
+use crate::{
+    ast::{Func, Namespace, Version},
+    symbols::Symbols,
+    vfs,
+};
+
+const SCHEMAS: &str = "#/components/schemas/";
+
+pub fn generate(ns: &Namespace, out: &Utf8Path, fs: &mut vfs::FS) -> Result<()> {
+    let (openrpc, asyncapi) = documents(ns)?;
+    write(
+        fs.open(out.join(format!("{}.openrpc.json", ns.name))),
+        &openrpc,
+    )?;
+    write(
+        fs.open(out.join(format!("{}.asyncapi.json", ns.name))),
+        &asyncapi,
+    )
+}
+
+/// The OpenRPC and AsyncAPI documents.
+fn documents(ns: &Namespace) -> Result<(Value, Value)> {
+    let syms = Symbols::new(ns);
+    let mut cx = Schemas::new(&syms, SCHEMAS);
+    let schemas = cx.types();
+
+    let mut methods = Vec::new();
+    let mut channels = Map::new();
+    let mut operations = Map::new();
+    let mut messages = Map::new();
+
+    for i in syms.interfaces() {
+        let qualified = i.qualified_name(&ns.name);
+        let tag = json!({ "name": qualified });
+
+        for f in i.methods {
+            let path = format!("{}.methods.{}", i.name, f.name);
+            let mut method = json!({
+                "name": format!("{}.{}", qualified, f.name),
+                "tags": [tag],
+                "paramStructure": "by-name",
+                "params": params(&mut cx, &path, f)?,
+                "result": { "name": "result", "schema": cx.ret(&path, f) },
+                "x-hidl-version": version(i.version),
+            });
+            document(&mut method, &f.docs);
+            methods.push(method);
+        }
+
+        let mut channel_messages = Map::new();
+        for f in i.events {
+            let path = format!("{}.events.{}", i.name, f.name);
+            let name = format!("{}.events.{}", qualified, f.name);
+            let params = params(&mut cx, &path, f)?;
+
+            let mut method = json!({
+                "name": name,
+                "tags": [tag, { "name": "event" }],
+                "paramStructure": "by-name",
+                "params": params,
+                "x-hidl-version": version(i.version),
+            });
+            document(&mut method, &f.docs);
+            methods.push(method);
+
+            let mut message = json!({
+                "name": name,
+                "contentType": "application/json",
+                "payload": notification(&name, &params),
+            });
+            document(&mut message, &f.docs);
+            messages.insert(name.clone(), message);
+            channel_messages.insert(
+                f.name.clone(),
+                json!({ "$ref": format!("#/components/messages/{}", name) }),
+            );
+            operations.insert(
+                name,
+                json!({
+                    "action": "receive",
+                    "channel": { "$ref": format!("#/channels/{}", qualified) },
+                    "messages": [
+                        { "$ref": format!("#/channels/{}/messages/{}", qualified, f.name) },
+                    ],
+                }),
+            );
+        }
+
+        if !channel_messages.is_empty() {
+            let mut channel = json!({
+                "address": qualified,
+                "title": i.name,
+                "messages": channel_messages,
+            });
+            document(&mut channel, i.docs);
+            channels.insert(qualified, channel);
+        }
+    }
+    cx.finish()?;
+
+    let info = |title: String| {
+        json!({
+            "title": title,
+            "version": "0.0.0",
+            "description": format!("Generated by hidl from the `{}` namespace. Do not edit.", ns.name),
+        })
+    };
+
+    let openrpc = json!({
+        "openrpc": "1.3.2",
+        "info": info(format!("{} JSON-RPC bridge", ns.name)),
+        "methods": methods,
+        "components": { "schemas": schemas },
+    });
+
+    let asyncapi = json!({
+        "asyncapi": "3.0.0",
+        "info": info(format!("{} events", ns.name)),
+        "defaultContentType": "application/json",
+        "channels": channels,
+        "operations": operations,
+        "components": { "messages": messages, "schemas": schemas },
+    });
+
+    Ok((openrpc, asyncapi))
+}
+
+/// OpenRPC content descriptors for the arguments of `f`, after `target`.
+fn params(cx: &mut Schemas, path: &str, f: &Func) -> Result<Vec<Value>> {
+    if f.args.iter().any(|a| a.name == "target") {
+        bail!(
+            "`{}.args.target` clashes with the `target` param for the object",
+            path
+        );
+    }
+    let target = json!({
+        "name": "target",
+        "required": true,
+        "schema": described(object(), "The object ID"),
+    });
+    let args = f.args.iter().map(|a| {
+        json!({
+            "name": a.name,
+            "required": true,
+            "schema": cx.ty(&format!("{}.args.{}", path, a.name), &a.ty),
+        })
+    });
+    Ok(std::iter::once(target).chain(args).collect())
+}
+
+/// The JSON-RPC notification for an event, as a schema.
+fn notification(method: &str, params: &[Value]) -> Value {
+    let properties = params
+        .iter()
+        .map(|p| (p["name"].as_str().unwrap().to_owned(), p["schema"].clone()))
+        .collect::<Map<_, _>>();
+    json!({
+        "type": "object",
+        "properties": {
+            "jsonrpc": { "const": "2.0" },
+            "method": { "const": method },
+            "params": {
+                "type": "object",
+                "properties": properties,
+                "required": params.iter().map(|p| &p["name"]).collect::<Vec<_>>(),
+                "additionalProperties": false,
+            },
+        },
+        "required": ["jsonrpc", "method", "params"],
+    })
+}
+
+/// Add `docs` as the summary (the first line) and description.
+fn document(item: &mut Value, docs: &str) {
+    let docs = docs.trim();
+    if let Some(summary) = docs.lines().next() {
+        item["summary"] = json!(summary);
+        item["description"] = json!(docs);
+    }
+}
+
+fn version((major, minor, patch): Version) -> String {
+    format!("{}.{}.{}", major, minor, patch)
+}
+
+fn write(w: &mut dyn Write, doc: &Value) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *w, doc)?;
+    writeln!(w)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hypercosm() {
+        let ns = crate::codegen::parse(include_str!("../../hypercosm.hidl"));
+        let (openrpc, asyncapi) = documents(&ns).unwrap();
+
+        let method = openrpc["methods"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|m| m["name"] == "hypercosm.Root.get_object_by_name")
+            .unwrap();
+        assert_eq!(
+            method,
+            &json!({
+                "name": "hypercosm.Root.get_object_by_name",
+                "summary": "Get the id of a singleton by the name of the interface",
+                "description": "Get the id of a singleton by the name of the interface",
+                "tags": [{ "name": "hypercosm.Root" }],
+                "paramStructure": "by-name",
+                "params": [
+                    {
+                        "name": "target",
+                        "required": true,
+                        "schema": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "maximum": u64::MAX,
+                            "description": "The object ID",
+                        },
+                    },
+                    { "name": "name", "required": true, "schema": { "type": "string" } },
+                ],
+                "result": {
+                    "name": "result",
+                    "schema": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "maximum": u64::MAX,
+                    },
+                },
+                "x-hidl-version": "0.1.0",
+            })
+        );
+
+        // A notification the bridge could send matches the AsyncAPI message
+        let name = "hypercosm.world.world.events.remove_entities";
+        let mut schema = asyncapi.clone();
+        schema["$ref"] = json!(format!("#/components/messages/{}/payload", name));
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let params = json!({ "target": 4, "entities": [5, 6] });
+        let notification = |method| json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        assert!(validator.is_valid(&notification(name)));
+        assert!(!validator.is_valid(&notification("hypercosm.world.world.events.add_entities")));
+
+        assert_eq!(
+            asyncapi["operations"][name]["messages"][0]["$ref"],
+            "#/channels/hypercosm.world.world/messages/remove_entities"
+        );
+    }
+
+    #[test]
+    fn target_arg() {
+        let ns = crate::codegen::parse(
+            "namespace test
+            interface thing {
+                version 1 0 0
+                events { moved(target: u8) }
+            }",
+        );
+        assert_eq!(
+            documents(&ns).unwrap_err().to_string(),
+            "`thing.events.moved.args.target` clashes with the `target` param for the object"
+        );
+    }
+}
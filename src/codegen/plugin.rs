@@ -0,0 +1,185 @@
+//! Generators that live outside this crate, run as `hidl-gen-{target}`
+//! plugins, much like `protoc` plugins.
+//!
+//! The plugin is sent a [`Request`] as JSON on stdin, and replies with a
+//! [`Response`] as JSON on stdout. Anything it writes to stderr is passed
+//! through. The files it returns are written through [`vfs::FS`], so they're
+//! checked in CI like any other generated file. See `spec/plugins.md` for
+//! the protocol.
+
+use std::{
+    io::{self, ErrorKind, Write},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result};
+use camino::{Utf8Component, Utf8Path};
+use serde::{Deserialize, Serialize};
+
+use crate::{ast::Namespace, json, vfs};
+
+/// Bumped whenever [`Request`] or [`Response`] change incompatibly.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct Request<'a> {
+    pub protocol_version: u32,
+    /// Everything after the output path on the command line
+    pub parameters: &'a [String],
+    /// The same as the output of `generate`
+    pub namespace: json::Document,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Response {
+    #[serde(default)]
+    pub files: Vec<OutputFile>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Set if the plugin failed, in which case no files are written
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OutputFile {
+    /// Relative to the output directory
+    pub name: String,
+    pub content: String,
+}
+
+pub fn generate(
+    target: &str,
+    ns: &Namespace,
+    out: &Utf8Path,
+    parameters: &[String],
+    fs: &mut vfs::FS,
+) -> Result<()> {
+    let program = format!("hidl-gen-{}", target);
+    let response = match run(&program, ns, parameters) {
+        Err(e) if is_not_found(&e) => bail!(
+            "Unknown target `{}`, expected one of: {}, or a `{}` plugin on PATH",
+            target,
+            super::TARGETS.join(", "),
+            program
+        ),
+        response => response?,
+    };
+
+    for warning in &response.warnings {
+        eprintln!("warning: {}: {}", program, warning);
+    }
+    if let Some(error) = response.error {
+        bail!("`{}` failed: {}", program, error);
+    }
+
+    for file in response.files {
+        check_name(&file.name).with_context(|| format!("Invalid file from `{}`", program))?;
+        fs.open(out.join(&file.name))
+            .write_all(file.content.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Send `ns` to the plugin `program`, and parse what it sends back.
+fn run(program: &str, ns: &Namespace, parameters: &[String]) -> Result<Response> {
+    let request = serde_json::to_vec(&Request {
+        protocol_version: PROTOCOL_VERSION,
+        parameters,
+        namespace: json::Document {
+            format_version: json::FORMAT_VERSION,
+            namespace: ns.clone(),
+        },
+    })?;
+
+    let mut child = Command::new(program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("Can't run `{}`", program))?;
+
+    // Written from another thread, so a plugin that starts replying before
+    // it's read everything can't deadlock us.
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || stdin.write_all(&request));
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        bail!("`{}` failed with {}", program, output.status);
+    }
+    writer
+        .join()
+        .unwrap()
+        .with_context(|| format!("Can't send the request to `{}`", program))?;
+
+    serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("Invalid response from `{}`", program))
+}
+
+fn is_not_found(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<io::Error>(), Some(e) if e.kind() == ErrorKind::NotFound)
+}
+
+/// Plugins can only write inside the output directory.
+fn check_name(name: &str) -> Result<()> {
+    let path = Utf8Path::new(name);
+    let normal = path
+        .components()
+        .all(|c| matches!(c, Utf8Component::Normal(_)));
+    if name.is_empty() || !normal {
+        bail!("`{}` must be a relative path without `..`", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[cfg(unix)]
+    #[test]
+    fn protocol() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("hidl-plugin-{}", std::process::id()));
+        fs_err::create_dir_all(&dir).unwrap();
+        let plugin = dir.join("hidl-gen-test");
+        fs_err::write(
+            &plugin,
+            format!(
+                "#!/bin/sh\ncat > {}/request.json\n\
+                 echo '{{\"files\": [{{\"name\": \"a/b.txt\", \"content\": \"hi\"}}]}}'\n",
+                dir.display()
+            ),
+        )
+        .unwrap();
+        fs_err::set_permissions(&plugin, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let ns = crate::codegen::parse("namespace test struct Foo { a: u8 }");
+        let params = ["x=1".to_owned()];
+        let response = run(plugin.to_str().unwrap(), &ns, &params).unwrap();
+        let request = fs_err::read_to_string(dir.join("request.json")).unwrap();
+        fs_err::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(response.files[0].name, "a/b.txt");
+        assert_eq!(response.files[0].content, "hi");
+        assert_eq!(response.error, None);
+
+        let request: serde_json::Value = serde_json::from_str(&request).unwrap();
+        assert_eq!(request["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(request["parameters"], serde_json::json!(["x=1"]));
+        let namespace = json::from_str(&request["namespace"].to_string()).unwrap();
+        assert_eq!(namespace, ns);
+    }
+
+    #[test]
+    fn names() {
+        assert!(check_name("a/b.txt").is_ok());
+        assert!(check_name("../b.txt").is_err());
+        assert!(check_name("/etc/passwd").is_err());
+        assert!(check_name("").is_err());
+    }
+}
@@ -0,0 +1,496 @@
+//! Protocol Buffers (proto3) generator, for services that only speak
+//! protobuf.
+//!
+//! Everything goes in one package, named after the namespace.
+//!
+//! - Structs become messages. Fields are numbered from 1 by position, so
+//!   removing or reordering a HIDL field renumbers the fields after it, and
+//!   breaks peers built from the old `.proto`. Only append fields.
+//! - Enums become enums, with values prefixed by the enum name. proto3
+//!   requires a zero value, so enums without one are an error.
+//! - Flags become enums of their bit values, with a `_NONE = 0` value added
+//!   if needed. Fields using them have the backing int type, as proto3 enums
+//!   can't hold combinations.
+//! - Interfaces become services named `{Interface}Service`. Each method takes
+//!   a `{Interface}{Method}Request`, whose first field is the `target`
+//!   object, and returns a `{Interface}{Method}Response`, with the return
+//!   value as `value`. Events are streamed from an `Events` rpc, as a
+//!   `oneof` in `{Interface}Event`.
+//! - `object` and interfaces become the `Object` message, `uuid` becomes
+//!   `Uuid`, and `matrix4x4` becomes `Matrix4x4`.
+//! - Dictionaries become `map<>`s.
+//!
+//! proto3 has no equivalent for nested arrays, arrays or maps as map values,
+//! or maps with keys that aren't ints, bools or strings, and method arguments
+//! named `target` would clash with the request's own. All of these are
+//! reported together, so they can be fixed at once.
+
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use heck::{ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+
+use crate::{
+    ast::{Arg, Func, IntType, Namespace, PrimType, Type, TypeKind},
+    symbols::{InterfaceRef, Symbol, Symbols, TypeRef},
+};
+
+/// Messages always emitted, for HIDL primitives with no proto3 equivalent.
+const BUILTINS: &str = "// A reference to an object, used for `object` and interfaces
+message Object {
+  uint64 id = 1;
+}
+
+// A UUID, as its most then least significant 64 bits
+message Uuid {
+  fixed64 high = 1;
+  fixed64 low = 2;
+}
+
+// A 4x4 matrix, as 16 values in column major order
+message Matrix4x4 {
+  repeated float values = 1;
+}";
+
+pub fn generate(ns: &Namespace, w: &mut dyn Write) -> Result<()> {
+    let syms = Symbols::new(ns);
+    let mut cx = Cx {
+        syms: &syms,
+        errors: Vec::new(),
+    };
+
+    for name in ["Object", "Uuid", "Matrix4x4"] {
+        if let Some(Symbol::Type(_)) = syms.get(name) {
+            cx.errors.push(format!(
+                "`{}`: clashes with the message generated for a builtin",
+                name
+            ));
+        }
+    }
+
+    let mut body = Vec::new();
+    let b = &mut body;
+
+    for ty in syms.types() {
+        writeln!(b)?;
+        write_type(b, &mut cx, ty)?;
+    }
+
+    for i in syms.interfaces() {
+        writeln!(b)?;
+        write_service(b, &mut cx, i)?;
+    }
+
+    if !cx.errors.is_empty() {
+        bail!(
+            "`{}` can't be exported to proto3:\n{}",
+            ns.name,
+            cx.errors
+                .iter()
+                .map(|e| format!("  - {}", e))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+
+    writeln!(
+        w,
+        "// Generated by hidl from the `{}` namespace. Do not edit.\n\
+         //\n\
+         // Field numbers are positional: removing or reordering a field in\n\
+         // the HIDL renumbers the ones after it.\n",
+        ns.name
+    )?;
+    writeln!(w, "syntax = \"proto3\";\n")?;
+    writeln!(w, "package {};\n", ns.name.to_snake_case())?;
+    writeln!(w, "{}", BUILTINS)?;
+    w.write_all(&body)?;
+
+    Ok(())
+}
+
+struct Cx<'a, 'b> {
+    syms: &'b Symbols<'a>,
+    /// Everything with no proto3 equivalent
+    errors: Vec<String>,
+}
+
+fn write_type(w: &mut dyn Write, cx: &mut Cx, ty: &TypeRef) -> Result<()> {
+    let def = ty.def;
+    let name = def.name.to_upper_camel_case();
+    let prefix = def.name.to_shouty_snake_case();
+    docs(w, "", &def.docs)?;
+
+    match &def.kind {
+        TypeKind::Struct(s) => {
+            writeln!(w, "message {} {{", name)?;
+            for (n, f) in s.fields.iter().enumerate() {
+                let context = format!("{}.{}", def.name, f.name);
+                if let Some(ty) = field_type(cx, &context, &f.ty) {
+                    writeln!(w, "  {} {} = {};", ty, f.name.to_snake_case(), n + 1)?;
+                }
+            }
+            writeln!(w, "}}")?;
+        }
+        TypeKind::Enum(e) => {
+            if !e.fields.iter().any(|f| f.value == Some(0)) {
+                cx.errors.push(format!(
+                    "`{}`: proto3 enums need a zero value, to use as the default",
+                    def.name
+                ));
+            }
+
+            writeln!(w, "enum {} {{", name)?;
+            for f in &e.fields {
+                let value = f.value.unwrap();
+                if i32::try_from(value).is_err() {
+                    cx.errors.push(format!(
+                        "`{}.{}`: {} doesn't fit in a proto3 enum, which are 32 bit",
+                        def.name, f.name, value
+                    ));
+                }
+                writeln!(
+                    w,
+                    "  {}_{} = {};",
+                    prefix,
+                    f.name.to_shouty_snake_case(),
+                    value
+                )?;
+            }
+            writeln!(w, "}}")?;
+        }
+        TypeKind::Flags(f) => {
+            writeln!(
+                w,
+                "// Bit flags, stored in `{}` fields",
+                scalar_int(&f.backing)
+            )?;
+            writeln!(w, "enum {} {{", name)?;
+            if !f.fields.iter().any(|f| f.value == 0) {
+                writeln!(w, "  {}_NONE = 0;", prefix)?;
+            }
+            for field in &f.fields {
+                if i32::try_from(field.value).is_err() {
+                    cx.errors.push(format!(
+                        "`{}.{}`: {} doesn't fit in a proto3 enum, which are 32 bit",
+                        def.name, field.name, field.value
+                    ));
+                }
+                writeln!(
+                    w,
+                    "  {}_{} = {};",
+                    prefix,
+                    field.name.to_shouty_snake_case(),
+                    field.value
+                )?;
+            }
+            writeln!(w, "}}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_service(w: &mut dyn Write, cx: &mut Cx, i: &InterfaceRef) -> Result<()> {
+    let name = i.name.to_upper_camel_case();
+
+    docs(w, "", i.docs)?;
+    writeln!(w, "service {}Service {{", name)?;
+    for m in i.methods {
+        docs(w, "  ", &m.docs)?;
+        let method = format!("{}{}", name, m.name.to_upper_camel_case());
+        writeln!(
+            w,
+            "  rpc {}({}Request) returns ({}Response);",
+            m.name.to_upper_camel_case(),
+            method,
+            method
+        )?;
+    }
+    if !i.events.is_empty() {
+        writeln!(w, "  // Stream the events raised by an object")?;
+        writeln!(w, "  rpc Events(Object) returns (stream {}Event);", name)?;
+    }
+    writeln!(w, "}}")?;
+
+    for m in i.methods {
+        let method = format!("{}{}", name, m.name.to_upper_camel_case());
+        let context = format!("{}.{}", i.name, m.name);
+
+        writeln!(w)?;
+        writeln!(w, "message {}Request {{", method)?;
+        writeln!(w, "  // The object to call `{}` on", m.name)?;
+        writeln!(w, "  Object target = 1;")?;
+        if let Some(a) = m.args.iter().find(|a| a.name.to_snake_case() == "target") {
+            cx.errors.push(format!(
+                "`{}({})`: clashes with the `target` field requests have for the object",
+                context, a.name
+            ));
+        }
+        write_args(w, cx, &context, &m.args, 2)?;
+        writeln!(w, "}}")?;
+
+        writeln!(w)?;
+        write_response(w, cx, &context, &method, m)?;
+    }
+
+    if !i.events.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "message {}Event {{", name)?;
+        writeln!(w, "  oneof event {{")?;
+        for (n, e) in i.events.iter().enumerate() {
+            docs(w, "    ", &e.docs)?;
+            writeln!(
+                w,
+                "    {}{}Event {} = {};",
+                name,
+                e.name.to_upper_camel_case(),
+                e.name.to_snake_case(),
+                n + 1
+            )?;
+        }
+        writeln!(w, "  }}")?;
+        writeln!(w, "}}")?;
+
+        for e in i.events {
+            writeln!(w)?;
+            writeln!(
+                w,
+                "message {}{}Event {{",
+                name,
+                e.name.to_upper_camel_case()
+            )?;
+            write_args(w, cx, &format!("{}.{}", i.name, e.name), &e.args, 1)?;
+            writeln!(w, "}}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_args(
+    w: &mut dyn Write,
+    cx: &mut Cx,
+    context: &str,
+    args: &[Arg],
+    first: usize,
+) -> Result<()> {
+    for (n, a) in args.iter().enumerate() {
+        let context = format!("{}({})", context, a.name);
+        if let Some(ty) = field_type(cx, &context, &a.ty) {
+            writeln!(w, "  {} {} = {};", ty, a.name.to_snake_case(), n + first)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_response(
+    w: &mut dyn Write,
+    cx: &mut Cx,
+    context: &str,
+    method: &str,
+    m: &Func,
+) -> Result<()> {
+    match &m.ret {
+        Some(ret) => {
+            writeln!(w, "message {}Response {{", method)?;
+            let context = format!("{} return", context);
+            if let Some(ty) = field_type(cx, &context, ret) {
+                writeln!(w, "  {} value = 1;", ty)?;
+            }
+            writeln!(w, "}}")?;
+        }
+        None => writeln!(w, "message {}Response {{}}", method)?,
+    }
+    Ok(())
+}
+
+/// The type of a field, including `repeated` for arrays, or `None` if it
+/// has no equivalent, in which case an error is recorded.
+fn field_type(cx: &mut Cx, context: &str, ty: &Type) -> Option<String> {
+    let res = match ty {
+        Type::Array(t) => match &**t {
+            Type::Array(_) => Err("nested arrays have no proto3 equivalent".to_owned()),
+            Type::Dictionary(_) => Err("arrays of maps have no proto3 equivalent".to_owned()),
+            t => scalar(cx, t).map(|t| format!("repeated {}", t)),
+        },
+        Type::Dictionary(d) => match (&d.key, &d.value) {
+            (_, Type::Array(_) | Type::Dictionary(_)) => Err(format!(
+                "map values can't be arrays or maps in proto3, found `{}`",
+                d.value
+            )),
+            (key, value) => map_key(cx, key).and_then(|key| {
+                let value = scalar(cx, value)?;
+                Ok(format!("map<{}, {}>", key, value))
+            }),
+        },
+        ty => scalar(cx, ty),
+    };
+
+    match res {
+        Ok(ty) => Some(ty),
+        Err(e) => {
+            cx.errors.push(format!("`{}`: {}", context, e));
+            None
+        }
+    }
+}
+
+fn map_key(cx: &mut Cx, key: &Type) -> Result<String, String> {
+    let legal = match key {
+        Type::Primitive(p) => matches!(p, PrimType::String | PrimType::Bool),
+        Type::IntType(_) => true,
+        // Flags are stored as ints
+        Type::Custom(name) => matches!(
+            cx.syms.get(name),
+            Some(Symbol::Type(TypeRef { def, .. })) if matches!(def.kind, TypeKind::Flags(_))
+        ),
+        Type::Array(_) | Type::Dictionary(_) => false,
+    };
+
+    if legal {
+        scalar(cx, key)
+    } else {
+        Err(format!(
+            "map keys must be ints, bools or strings in proto3, found `{}`",
+            key
+        ))
+    }
+}
+
+/// The type of a field that isn't an array or map.
+fn scalar(cx: &mut Cx, ty: &Type) -> Result<String, String> {
+    Ok(match ty {
+        Type::Primitive(p) => match p {
+            PrimType::String => "string",
+            PrimType::Object => "Object",
+            PrimType::Uuid => "Uuid",
+            PrimType::Bytes => "bytes",
+            PrimType::Bool => "bool",
+            PrimType::Matrix4x4 => "Matrix4x4",
+            PrimType::F32 => "float",
+            PrimType::F64 => "double",
+        }
+        .to_owned(),
+        Type::IntType(i) => scalar_int(i).to_owned(),
+        Type::Custom(name) => match cx.syms.resolve(name).map_err(|e| e.to_string())? {
+            Symbol::Interface(_) => "Object".to_owned(),
+            Symbol::Type(t) => match &t.def.kind {
+                TypeKind::Flags(f) => scalar_int(&f.backing).to_owned(),
+                _ => t.def.name.to_upper_camel_case(),
+            },
+        },
+        Type::Array(_) | Type::Dictionary(_) => unreachable!("Handled by field_type"),
+    })
+}
+
+/// proto3 has no 8 or 16 bit ints, so they're widened.
+fn scalar_int(i: &IntType) -> &'static str {
+    match i {
+        IntType::U8 | IntType::U16 | IntType::VU8 | IntType::VU16 | IntType::VU32 => "uint32",
+        IntType::VU64 => "uint64",
+        IntType::U32 => "fixed32",
+        IntType::U64 => "fixed64",
+        IntType::I8 | IntType::I16 | IntType::VI8 | IntType::VI16 | IntType::VI32 => "sint32",
+        IntType::VI64 => "sint64",
+        IntType::I32 => "sfixed32",
+        IntType::I64 => "sfixed64",
+    }
+}
+
+fn docs(w: &mut dyn Write, indent: &str, docs: &str) -> Result<()> {
+    super::write_docs(w, indent, ["", "// ", ""], docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn gen(hidl: &str) -> Result<String> {
+        let ns = crate::codegen::parse(hidl);
+        let mut out = Vec::new();
+        generate(&ns, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    /// Check the output is valid proto3, with `protobuf-parse`.
+    #[test]
+    fn hypercosm() {
+        let out = gen(include_str!("../../hypercosm.hidl")).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("hidl-proto-{}", std::process::id()));
+        fs_err::create_dir_all(&dir).unwrap();
+        fs_err::write(dir.join("hypercosm.proto"), &out).unwrap();
+        let parsed = protobuf_parse::Parser::new()
+            .pure()
+            .include(&dir)
+            .input(dir.join("hypercosm.proto"))
+            .parse_and_typecheck()
+            .unwrap_or_else(|e| panic!("{:?}\n{}", e, out));
+        fs_err::remove_dir_all(&dir).unwrap();
+
+        let file = &parsed.file_descriptors[0];
+        let services = file.service.iter().map(|s| s.name()).collect::<Vec<_>>();
+        assert_eq!(
+            services,
+            [
+                "ObjectService",
+                "RootService",
+                "AssetDeliveryService",
+                "WorldService",
+                "EntityService",
+                "ExecutionContextService"
+            ]
+        );
+
+        let expected = "message ExecutionContextLoadWasmModuleRequest {
+  // The object to call `load_wasm_module` on
+  Object target = 1;
+  Uuid asset_id = 2;
+  map<string, string> exports = 3;
+}
+";
+        assert!(out.contains(expected), "{}", out);
+
+        // Pinned, as changing a field's number breaks existing peers
+        let expected = "message EntityInfo {
+  Uuid asset_id = 1;
+  Object entity = 2;
+  Matrix4x4 transformation = 3;
+  uint64 attrs = 4;
+}
+";
+        assert!(out.contains(expected), "{}", out);
+    }
+
+    #[test]
+    fn diagnostics() {
+        let err = gen("namespace test
+
+            interface thing {
+                version 1 0 0
+                methods { move(target: u8) }
+            }
+
+            enum Kind(u8) { A = 1 B }
+
+            struct Bad {
+                nested: [][]u8
+                key: [f32]string
+                value: [string][]u8
+                ok: [vu32]Kind
+            }")
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "`test` can't be exported to proto3:
+  - `Kind`: proto3 enums need a zero value, to use as the default
+  - `Bad.nested`: nested arrays have no proto3 equivalent
+  - `Bad.key`: map keys must be ints, bools or strings in proto3, found `f32`
+  - `Bad.value`: map values can't be arrays or maps in proto3, found `[]u8`
+  - `thing.move(target)`: clashes with the `target` field requests have for the object"
+        );
+    }
+}
@@ -0,0 +1,429 @@
+//! Python generator.
+//!
+//! Output is a directory, with `hidl_runtime.py` (from `runtime/python.py`)
+//! and a module for the namespace. The runtime can also build the same classes
+//! from the JSON at runtime, so names here must follow the same rules as
+//! `load` there.
+
+use std::io::Write;
+
+use anyhow::Result;
+use camino::Utf8Path;
+
+use crate::{
+    ast::{Func, Namespace, PrimType, Type, TypeKind},
+    symbols::{InterfaceRef, Symbol, Symbols, TypeRef},
+    vfs,
+};
+
+const RUNTIME: &str = include_str!("runtime/python.py");
+
+pub fn generate(ns: &Namespace, out: &Utf8Path, fs: &mut vfs::FS) -> Result<()> {
+    fs.open(out.join("hidl_runtime.py"))
+        .write_all(RUNTIME.as_bytes())?;
+
+    let w = fs.open(out.join(&ns.name).with_extension("py"));
+    generate_module(ns, w)
+}
+
+fn generate_module(ns: &Namespace, w: &mut dyn Write) -> Result<()> {
+    let syms = Symbols::new(ns);
+
+    writeln!(
+        w,
+        "# Generated by hidl from the `{}` namespace. Do not edit.\n",
+        ns.name
+    )?;
+    writeln!(w, "from __future__ import annotations\n")?;
+    writeln!(w, "import dataclasses")?;
+    writeln!(w, "import enum")?;
+    writeln!(w, "import uuid")?;
+    writeln!(w, "from typing import Dict, List\n")?;
+    writeln!(
+        w,
+        "from hidl_runtime import Client, Matrix4x4, ObjectId, Registry\n"
+    )?;
+    writeln!(w, "registry = Registry(\"{}\")", ns.name)?;
+
+    for ty in syms.types() {
+        writeln!(w, "\n")?;
+        write_type(w, &syms, ty)?;
+    }
+
+    for i in syms.interfaces() {
+        writeln!(w, "\n")?;
+        write_interface(w, &syms, i)?;
+    }
+
+    Ok(())
+}
+
+fn write_type(w: &mut dyn Write, syms: &Symbols, ty: &TypeRef) -> Result<()> {
+    let def = ty.def;
+    let name = ident(&def.name);
+
+    match &def.kind {
+        TypeKind::Struct(s) => {
+            writeln!(w, "@registry.struct(")?;
+            writeln!(w, "    \"{}\",", def.name)?;
+            writeln!(w, "    [")?;
+            for f in &s.fields {
+                writeln!(w, "        (\"{}\", {}),", f.name, descriptor(&f.ty))?;
+            }
+            writeln!(w, "    ],")?;
+            writeln!(w, ")")?;
+            writeln!(w, "@dataclasses.dataclass")?;
+            writeln!(w, "class {}:", name)?;
+            docs(w, &def.docs)?;
+            for f in &s.fields {
+                writeln!(w, "    {}: {}", ident(&f.name), py_type(syms, &f.ty)?)?;
+            }
+            if s.fields.is_empty() && def.docs.trim().is_empty() {
+                writeln!(w, "    pass")?;
+            }
+        }
+        TypeKind::Enum(e) => {
+            writeln!(w, "@registry.enum(\"{}\", \"{:?}\")", def.name, e.backing)?;
+            writeln!(w, "class {}(enum.IntEnum):", name)?;
+            docs(w, &def.docs)?;
+            for f in &e.fields {
+                writeln!(w, "    {} = {}", ident(&f.name), f.value.unwrap())?;
+            }
+            if e.fields.is_empty() && def.docs.trim().is_empty() {
+                writeln!(w, "    pass")?;
+            }
+        }
+        TypeKind::Flags(f) => {
+            writeln!(w, "@registry.flags(\"{}\", \"{:?}\")", def.name, f.backing)?;
+            writeln!(w, "class {}(enum.IntFlag):", name)?;
+            docs(w, &def.docs)?;
+            for field in &f.fields {
+                writeln!(w, "    {} = {}", ident(&field.name), field.value)?;
+            }
+            if f.fields.is_empty() && def.docs.trim().is_empty() {
+                writeln!(w, "    pass")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_interface(w: &mut dyn Write, syms: &Symbols, i: &InterfaceRef) -> Result<()> {
+    writeln!(w, "@registry.interface(")?;
+    writeln!(w, "    \"{}\",", i.name)?;
+    writeln!(w, "    \"{}\",", i.qualified_name(&syms.namespace().name))?;
+    writeln!(
+        w,
+        "    \"{}.{}.{}\",",
+        i.version.0, i.version.1, i.version.2
+    )?;
+    if i.methods.is_empty() {
+        writeln!(w, "    methods={{}},")?;
+    } else {
+        writeln!(w, "    methods={{")?;
+        for m in i.methods {
+            let ret = match &m.ret {
+                Some(ret) => descriptor(ret),
+                None => "None".to_owned(),
+            };
+            writeln!(w, "        \"{}\": ({}, {}),", m.name, args(m), ret)?;
+        }
+        writeln!(w, "    }},")?;
+    }
+    if i.events.is_empty() {
+        writeln!(w, "    events={{}},")?;
+    } else {
+        writeln!(w, "    events={{")?;
+        for e in i.events {
+            writeln!(w, "        \"{}\": {},", e.name, args(e))?;
+        }
+        writeln!(w, "    }},")?;
+    }
+    writeln!(w, ")")?;
+
+    writeln!(w, "class {}(Client):", class_name(i.name))?;
+    docs(w, i.docs)?;
+
+    if !i.events.is_empty() {
+        writeln!(w, "    # Events:")?;
+        for e in i.events {
+            let params = e
+                .args
+                .iter()
+                .map(|a| Ok(format!("{}: {}", a.name, py_type(syms, &a.ty)?)))
+                .collect::<Result<Vec<_>>>()?;
+            writeln!(w, "    # - {}({})", e.name, params.join(", "))?;
+        }
+    }
+
+    for (n, m) in i.methods.iter().enumerate() {
+        if n != 0 || !i.docs.trim().is_empty() || !i.events.is_empty() {
+            writeln!(w)?;
+        }
+        let mut params = vec!["self".to_owned()];
+        for a in &m.args {
+            params.push(format!("{}: {}", ident(&a.name), py_type(syms, &a.ty)?));
+        }
+        let ret = match &m.ret {
+            Some(ret) => py_type(syms, ret)?,
+            None => "None".to_owned(),
+        };
+        writeln!(
+            w,
+            "    async def {}({}) -> {}:",
+            ident(&m.name),
+            params.join(", "),
+            ret
+        )?;
+        indented_docs(w, "        ", &m.docs)?;
+        let args = m
+            .args
+            .iter()
+            .map(|a| format!(", {}", ident(&a.name)))
+            .collect::<String>();
+        writeln!(w, "        return await self._call(\"{}\"{})", m.name, args)?;
+    }
+
+    // Comments for events don't count as a body.
+    if i.methods.is_empty() && i.docs.trim().is_empty() {
+        writeln!(w, "    pass")?;
+    }
+
+    Ok(())
+}
+
+/// A Python literal for the type, in the same format as the JSON.
+fn descriptor(ty: &Type) -> String {
+    match ty {
+        Type::Primitive(p) => format!("{{\"Primitive\": \"{:?}\"}}", p),
+        Type::IntType(i) => format!("{{\"IntType\": \"{:?}\"}}", i),
+        Type::Custom(name) => format!("{{\"Custom\": \"{}\"}}", name),
+        Type::Array(t) => format!("{{\"Array\": {}}}", descriptor(t)),
+        Type::Dictionary(d) => format!(
+            "{{\"Dictionary\": {{\"key\": {}, \"value\": {}}}}}",
+            descriptor(&d.key),
+            descriptor(&d.value)
+        ),
+    }
+}
+
+fn args(f: &Func) -> String {
+    let args = f
+        .args
+        .iter()
+        .map(|a| format!("(\"{}\", {})", a.name, descriptor(&a.ty)))
+        .collect::<Vec<_>>();
+    format!("[{}]", args.join(", "))
+}
+
+fn py_type(syms: &Symbols, ty: &Type) -> Result<String> {
+    Ok(match ty {
+        Type::Primitive(p) => match p {
+            PrimType::String => "str",
+            PrimType::Object => "ObjectId",
+            PrimType::Uuid => "uuid.UUID",
+            PrimType::Bytes => "bytes",
+            PrimType::Bool => "bool",
+            PrimType::Matrix4x4 => "Matrix4x4",
+            PrimType::F32 | PrimType::F64 => "float",
+        }
+        .to_owned(),
+        Type::IntType(_) => "int".to_owned(),
+        Type::Custom(name) => match syms.resolve(name)? {
+            Symbol::Interface(_) => "ObjectId".to_owned(),
+            Symbol::Type(t) => ident(&t.def.name),
+        },
+        Type::Array(t) => format!("List[{}]", py_type(syms, t)?),
+        Type::Dictionary(d) => format!(
+            "Dict[{}, {}]",
+            py_type(syms, &d.key)?,
+            py_type(syms, &d.value)?
+        ),
+    })
+}
+
+/// Must match `class_name` in the runtime.
+fn class_name(name: &str) -> String {
+    let mut out = String::new();
+    for part in name.split('_') {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+    }
+    out + "Client"
+}
+
+/// Must match `ident` in the runtime.
+fn ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+        "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+        "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return",
+        "try", "while", "with", "yield",
+    ];
+
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+fn docs(w: &mut dyn Write, docs: &str) -> Result<()> {
+    indented_docs(w, "    ", docs)
+}
+
+fn indented_docs(w: &mut dyn Write, indent: &str, docs: &str) -> Result<()> {
+    let docs = docs.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\"");
+    let docs = docs.trim_end();
+    if docs.is_empty() {
+        return Ok(());
+    }
+
+    match docs.split_once('\n') {
+        None => writeln!(w, "{}\"\"\"{}\"\"\"", indent, docs)?,
+        Some((first, rest)) => {
+            writeln!(w, "{}\"\"\"{}", indent, first)?;
+            for line in rest.lines() {
+                let line = format!("{}{}", indent, line);
+                writeln!(w, "{}", line.trim_end())?;
+            }
+            writeln!(w, "{}\"\"\"", indent)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    #[test]
+    fn class_names() {
+        assert_eq!(class_name("asset_delivery"), "AssetDeliveryClient");
+        assert_eq!(class_name("Entity"), "EntityClient");
+    }
+
+    /// `python/hidl_runtime.py` is generated from `runtime/python.py`, so
+    /// edits to the copy would be overwritten.
+    #[test]
+    fn committed_runtime() {
+        assert!(
+            include_str!("../../python/hidl_runtime.py") == RUNTIME,
+            "python/hidl_runtime.py differs from src/codegen/runtime/python.py, \
+             edit that and rerun run.sh"
+        );
+    }
+
+    /// The generated module and the runtime loader must build the same
+    /// classes, and encode the same bytes.
+    #[test]
+    fn matches_loader() {
+        let ns = crate::codegen::parse(include_str!("../../hypercosm.hidl"));
+
+        let dir = std::env::temp_dir().join(format!("hidl-python-{}", std::process::id()));
+        fs_err::create_dir_all(&dir).unwrap();
+        fs_err::write(dir.join("hidl_runtime.py"), RUNTIME).unwrap();
+        let mut module = Vec::new();
+        generate_module(&ns, &mut module).unwrap();
+        fs_err::write(dir.join("hypercosm.py"), module).unwrap();
+        fs_err::write(
+            dir.join("hypercosm.json"),
+            crate::json::to_string(&ns).unwrap(),
+        )
+        .unwrap();
+        fs_err::write(dir.join("check.py"), CHECK).unwrap();
+
+        let output = Command::new("python3")
+            .arg("check.py")
+            .current_dir(&dir)
+            .output()
+            .expect("Failed to run python3");
+
+        fs_err::remove_dir_all(&dir).unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    const CHECK: &str = r#"
+import asyncio
+import uuid
+
+import hidl_runtime
+import hypercosm as static
+
+dynamic = hidl_runtime.load("hypercosm.json")
+
+
+def entity(ns):
+    return ns.EntityInfo(
+        asset_id=uuid.UUID("12345678-1234-5678-1234-567812345678"),
+        entity=300,
+        transformation=tuple(float(i) for i in range(16)),
+        attrs=ns.EntityAttrs.Interactable | ns.EntityAttrs.Collidable,
+    )
+
+
+def encode(ns, ty, v):
+    w = hidl_runtime.Writer()
+    ns.registry.encode(w, ty, v)
+    return w.finish()
+
+
+ty = {"Array": {"Custom": "EntityInfo"}}
+a = encode(static, ty, [entity(static)])
+b = encode(dynamic, ty, [entity(dynamic)])
+assert a == b, (a, b)
+assert a[:17] == b"\x01" + uuid.UUID("12345678-1234-5678-1234-567812345678").bytes
+assert a[17:19] == b"\xac\x02"
+
+r = hidl_runtime.Reader(a)
+[decoded] = static.registry.decode(r, ty)
+r.finish()
+assert decoded == entity(static)
+
+for name in ["Asset", "EntityInfo", "EntityAttrs"]:
+    s, d = getattr(static, name), getattr(dynamic, name)
+    assert s._hidl_kind == d._hidl_kind, name
+    if s._hidl_kind == "Struct":
+        assert s._hidl_fields == d._hidl_fields, name
+    else:
+        assert [(m.name, m.value) for m in s] == [(m.name, m.value) for m in d], name
+
+
+class Transport(hidl_runtime.Transport):
+    def __init__(self):
+        self.listeners = []
+
+    async def call(self, object_id, iface, method, args):
+        assert (object_id, iface, method) == (7, "hypercosm.asset_delivery.asset_delivery", "get_id")
+        r = hidl_runtime.Reader(args)
+        assert r.raw(r.length()) == b"tree"
+        r.finish()
+        return uuid.UUID(int=1).bytes
+
+    def listen(self, object_id, iface, listener):
+        self.listeners.append(listener)
+
+
+for ns in [static, dynamic]:
+    t = Transport()
+    client = ns.AssetDeliveryClient(t, 7)
+    assert client._hidl_methods == static.AssetDeliveryClient._hidl_methods
+    assert asyncio.run(client.get_id("tree")) == uuid.UUID(int=1)
+
+    seen = []
+    client.on("unload_assets", seen.append)
+    [listener] = t.listeners
+    listener("unload_assets", encode(ns, {"Array": {"Primitive": "Uuid"}}, [uuid.UUID(int=2)]))
+    assert seen == [[uuid.UUID(int=2)]]
+"#;
+}
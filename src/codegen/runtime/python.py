@@ -0,0 +1,420 @@
+"""Runtime support for code generated by hidl.
+
+This module encodes values as described in `spec/encoding.md`, and provides
+the base class for clients. It can also build the same classes as the
+generated code at runtime, from the JSON output of `generate`, see `load`.
+
+Types are described in the same format as the JSON, for example
+`{"Array": {"Primitive": "String"}}`.
+"""
+
+from __future__ import annotations
+
+import dataclasses
+import enum
+import json
+import keyword
+import struct
+import types
+import uuid
+from typing import Any, Awaitable, Callable, Dict, List, Optional, Tuple
+
+ObjectId = int
+# 16 floats, in column major order
+Matrix4x4 = Tuple[float, ...]
+Type = Any
+
+Args = List[Tuple[str, Type]]
+
+_FIXED = {
+    "U8": "<B",
+    "U16": "<H",
+    "U32": "<I",
+    "U64": "<Q",
+    "I8": "<b",
+    "I16": "<h",
+    "I32": "<i",
+    "I64": "<q",
+}
+
+_VARIABLE = {
+    "VU8": (8, False),
+    "VU16": (16, False),
+    "VU32": (32, False),
+    "VU64": (64, False),
+    "VI8": (8, True),
+    "VI16": (16, True),
+    "VI32": (32, True),
+    "VI64": (64, True),
+}
+
+
+class Transport:
+    """What clients use to talk to a node. Framing is up to the transport."""
+
+    async def call(self, object_id: ObjectId, iface: str, method: str, args: bytes) -> bytes:
+        """Call `method` on `object_id`, and return the encoded result."""
+        raise NotImplementedError
+
+    def listen(
+        self, object_id: ObjectId, iface: str, listener: Callable[[str, bytes], None]
+    ) -> None:
+        """Register a callback for every event `object_id` emits on `iface`."""
+        raise NotImplementedError
+
+
+class Writer:
+    def __init__(self) -> None:
+        self.buf = bytearray()
+
+    def finish(self) -> bytes:
+        return bytes(self.buf)
+
+    def pack(self, fmt: str, *v: Any) -> None:
+        self.buf += struct.pack(fmt, *v)
+
+    def varint(self, v: int, bits: int, signed: bool) -> None:
+        if signed:
+            if not -(1 << (bits - 1)) <= v < (1 << (bits - 1)):
+                raise ValueError(f"{v} doesn't fit in vi{bits}")
+            v = v * 2 if v >= 0 else -v * 2 - 1
+        elif not 0 <= v < (1 << bits):
+            raise ValueError(f"{v} doesn't fit in vu{bits}")
+        while True:
+            byte = v & 0x7F
+            v >>= 7
+            if v:
+                self.buf.append(byte | 0x80)
+            else:
+                self.buf.append(byte)
+                return
+
+    def length(self, n: int) -> None:
+        self.varint(n, 64, False)
+
+
+class Reader:
+    def __init__(self, buf: bytes) -> None:
+        self.buf = memoryview(buf)
+        self.pos = 0
+
+    def finish(self) -> None:
+        """Raise if there is unread input left."""
+        if self.pos != len(self.buf):
+            raise ValueError(f"{len(self.buf) - self.pos} trailing bytes")
+
+    def raw(self, n: int) -> bytes:
+        if self.pos + n > len(self.buf):
+            raise ValueError("Unexpected end of input")
+        v = bytes(self.buf[self.pos : self.pos + n])
+        self.pos += n
+        return v
+
+    def unpack(self, fmt: str) -> Tuple[Any, ...]:
+        return struct.unpack(fmt, self.raw(struct.calcsize(fmt)))
+
+    def varint(self, bits: int, signed: bool) -> int:
+        v = 0
+        shift = 0
+        while True:
+            byte = self.raw(1)[0]
+            v |= (byte & 0x7F) << shift
+            shift += 7
+            if not byte & 0x80:
+                break
+            if shift >= bits:
+                raise ValueError(f"Varint longer than {bits} bits")
+        if v >> bits:
+            raise ValueError(f"Varint doesn't fit in {bits} bits")
+        if signed:
+            v = -((v + 1) >> 1) if v & 1 else v >> 1
+        return v
+
+    def length(self) -> int:
+        return self.varint(64, False)
+
+
+class Registry:
+    """The types of a namespace, by their HIDL name.
+
+    Generated code registers its classes with the decorators, which is how the
+    encoder knows what they look like.
+    """
+
+    def __init__(self, namespace: str) -> None:
+        self.namespace = namespace
+        self.types: Dict[str, type] = {}
+        # Interfaces can be used as types, and are encoded as object IDs
+        self.interfaces: Dict[str, type] = {}
+
+    def struct(self, name: str, fields: Args) -> Callable[[type], type]:
+        def register(cls: type) -> type:
+            cls._hidl_kind = "Struct"
+            cls._hidl_fields = [(ident(f), ty) for f, ty in fields]
+            self.types[name] = cls
+            return cls
+
+        return register
+
+    def enum(self, name: str, backing: str) -> Callable[[type], type]:
+        def register(cls: type) -> type:
+            cls._hidl_kind = "Enum"
+            cls._hidl_backing = backing
+            self.types[name] = cls
+            return cls
+
+        return register
+
+    def flags(self, name: str, backing: str) -> Callable[[type], type]:
+        def register(cls: type) -> type:
+            cls._hidl_kind = "Flags"
+            cls._hidl_backing = backing
+            self.types[name] = cls
+            return cls
+
+        return register
+
+    def interface(
+        self,
+        name: str,
+        qualified_name: str,
+        version: str,
+        methods: Dict[str, Tuple[Args, Optional[Type]]],
+        events: Dict[str, Args],
+    ) -> Callable[[type], type]:
+        def register(cls: type) -> type:
+            cls._hidl_registry = self
+            cls._hidl_name = qualified_name
+            cls._hidl_version = version
+            cls._hidl_methods = methods
+            cls._hidl_events = events
+            self.interfaces[name] = cls
+            return cls
+
+        return register
+
+    def encode(self, w: Writer, ty: Type, v: Any) -> None:
+        [(kind, arg)] = ty.items()
+        if kind == "Primitive":
+            if arg == "String":
+                data = v.encode("utf-8")
+                w.length(len(data))
+                w.buf += data
+            elif arg == "Bytes":
+                w.length(len(v))
+                w.buf += v
+            elif arg == "Object":
+                w.varint(v, 64, False)
+            elif arg == "Uuid":
+                w.buf += v.bytes
+            elif arg == "Bool":
+                w.pack("<B", 1 if v else 0)
+            elif arg == "Matrix4x4":
+                w.pack("<16f", *v)
+            elif arg == "F32":
+                w.pack("<f", v)
+            elif arg == "F64":
+                w.pack("<d", v)
+            else:
+                raise ValueError(f"Unknown primitive {arg}")
+        elif kind == "IntType":
+            self.encode_int(w, arg, v)
+        elif kind == "Array":
+            w.length(len(v))
+            for x in v:
+                self.encode(w, arg, x)
+        elif kind == "Dictionary":
+            w.length(len(v))
+            for k, x in v.items():
+                self.encode(w, arg["key"], k)
+                self.encode(w, arg["value"], x)
+        elif kind == "Custom":
+            if arg in self.interfaces:
+                w.varint(v, 64, False)
+                return
+            cls = self.types[arg]
+            if cls._hidl_kind == "Struct":
+                for field, fty in cls._hidl_fields:
+                    self.encode(w, fty, getattr(v, field))
+            else:
+                self.encode_int(w, cls._hidl_backing, int(v))
+        else:
+            raise ValueError(f"Unknown type {ty}")
+
+    def decode(self, r: Reader, ty: Type) -> Any:
+        [(kind, arg)] = ty.items()
+        if kind == "Primitive":
+            if arg == "String":
+                return r.raw(r.length()).decode("utf-8")
+            elif arg == "Bytes":
+                return r.raw(r.length())
+            elif arg == "Object":
+                return r.varint(64, False)
+            elif arg == "Uuid":
+                return uuid.UUID(bytes=r.raw(16))
+            elif arg == "Bool":
+                [v] = r.unpack("<B")
+                if v > 1:
+                    raise ValueError(f"Invalid bool {v}")
+                return v == 1
+            elif arg == "Matrix4x4":
+                return r.unpack("<16f")
+            elif arg == "F32":
+                return r.unpack("<f")[0]
+            elif arg == "F64":
+                return r.unpack("<d")[0]
+            raise ValueError(f"Unknown primitive {arg}")
+        elif kind == "IntType":
+            return self.decode_int(r, arg)
+        elif kind == "Array":
+            return [self.decode(r, arg) for _ in range(r.length())]
+        elif kind == "Dictionary":
+            v = {}
+            for _ in range(r.length()):
+                k = self.decode(r, arg["key"])
+                if k in v:
+                    raise ValueError(f"Duplicate key {k!r}")
+                v[k] = self.decode(r, arg["value"])
+            return v
+        elif kind == "Custom":
+            if arg in self.interfaces:
+                return r.varint(64, False)
+            cls = self.types[arg]
+            if cls._hidl_kind == "Struct":
+                return cls(**{f: self.decode(r, fty) for f, fty in cls._hidl_fields})
+            return cls(self.decode_int(r, cls._hidl_backing))
+        raise ValueError(f"Unknown type {ty}")
+
+    @staticmethod
+    def encode_int(w: Writer, int_type: str, v: int) -> None:
+        if int_type in _FIXED:
+            w.pack(_FIXED[int_type], v)
+        else:
+            w.varint(v, *_VARIABLE[int_type])
+
+    @staticmethod
+    def decode_int(r: Reader, int_type: str) -> int:
+        if int_type in _FIXED:
+            return r.unpack(_FIXED[int_type])[0]
+        return r.varint(*_VARIABLE[int_type])
+
+
+class Client:
+    """Base class for interface clients."""
+
+    _hidl_registry: Registry
+    _hidl_name: str
+    _hidl_version: str
+    _hidl_methods: Dict[str, Tuple[Args, Optional[Type]]]
+    _hidl_events: Dict[str, Args]
+
+    def __init__(self, transport: Transport, object_id: ObjectId) -> None:
+        self.transport = transport
+        self.object_id = object_id
+        self._listeners: Dict[str, List[Callable[..., None]]] = {}
+        if self._hidl_events:
+            transport.listen(object_id, self._hidl_name, self._dispatch)
+
+    def on(self, event: str, listener: Callable[..., None]) -> None:
+        if event not in self._hidl_events:
+            raise ValueError(f"{self._hidl_name} has no event {event}")
+        self._listeners.setdefault(event, []).append(listener)
+
+    def off(self, event: str, listener: Callable[..., None]) -> None:
+        self._listeners.get(event, []).remove(listener)
+
+    def _dispatch(self, event: str, payload: bytes) -> None:
+        args = self._hidl_events.get(event)
+        if args is None:
+            return
+        r = Reader(payload)
+        values = [self._hidl_registry.decode(r, ty) for _, ty in args]
+        r.finish()
+        for listener in list(self._listeners.get(event, [])):
+            listener(*values)
+
+    async def _call(self, method: str, *values: Any) -> Any:
+        args, ret = self._hidl_methods[method]
+        w = Writer()
+        for (_, ty), v in zip(args, values, strict=True):
+            self._hidl_registry.encode(w, ty, v)
+        payload = await self.transport.call(self.object_id, self._hidl_name, method, w.finish())
+        r = Reader(payload)
+        result = None if ret is None else self._hidl_registry.decode(r, ret)
+        r.finish()
+        return result
+
+
+def ident(name: str) -> str:
+    """Escape names that are Python keywords."""
+    return name + "_" if keyword.iskeyword(name) else name
+
+
+def class_name(name: str) -> str:
+    """The name of the client class for an interface."""
+    return "".join(part[:1].upper() + part[1:] for part in name.split("_")) + "Client"
+
+
+def load(source: Any) -> types.SimpleNamespace:
+    """Build the classes for a namespace from the JSON output of `generate`.
+
+    `source` is either a path, or the already parsed JSON. The result has an
+    attribute for every type and client class, named the same as in the
+    generated code, as well as the `registry`.
+    """
+    if not isinstance(source, dict):
+        with open(source, encoding="utf-8") as f:
+            source = json.load(f)
+
+    registry = Registry(source["name"])
+    out = types.SimpleNamespace(registry=registry)
+
+    typedefs = list(source["types"])
+    interfaces = [(i, None, i["version"]) for i in source["interfaces"]]
+    for ext in source["extensions"]:
+        typedefs += ext["types"]
+        interfaces += [(i, ext["name"], ext["version"]) for i in ext["interfaces"]]
+
+    for t in typedefs:
+        [(kind, body)] = t["kind"].items()
+        if kind == "Struct":
+            fields = [(f["name"], f["ty"]) for f in body["fields"]]
+            cls = dataclasses.make_dataclass(t["name"], [(ident(f), Any) for f, _ in fields])
+            cls = registry.struct(t["name"], fields)(cls)
+        else:
+            base = enum.IntEnum if kind == "Enum" else enum.IntFlag
+            cls = base(t["name"], [(ident(f["name"]), f["value"]) for f in body["fields"]])
+            register = registry.enum if kind == "Enum" else registry.flags
+            cls = register(t["name"], body["backing"])(cls)
+        cls.__doc__ = t["docs"]
+        setattr(out, ident(t["name"]), cls)
+
+    for i, ext, version in interfaces:
+        qualified = ".".join(filter(None, [source["name"], ext, i["name"]]))
+        attrs: Dict[str, Any] = {"__doc__": i["docs"]}
+        for m in i["methods"]:
+            attrs[ident(m["name"])] = _method(m["name"], m["docs"])
+        cls = type(class_name(i["name"]), (Client,), attrs)
+        cls = registry.interface(
+            i["name"],
+            qualified,
+            ".".join(map(str, version)),
+            {m["name"]: (_args(m), m["ret"]) for m in i["methods"]},
+            {e["name"]: _args(e) for e in i["events"]},
+        )(cls)
+        setattr(out, cls.__name__, cls)
+
+    return out
+
+
+def _args(func: Dict[str, Any]) -> Args:
+    return [(a["name"], a["ty"]) for a in func["args"]]
+
+
+def _method(name: str, docs: str) -> Callable[..., Awaitable[Any]]:
+    async def method(self: Client, *args: Any) -> Any:
+        return await self._call(name, *args)
+
+    method.__name__ = ident(name)
+    method.__doc__ = docs
+    return method
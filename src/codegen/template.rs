@@ -0,0 +1,325 @@
+//! Render a user supplied [minijinja] template over a namespace, for small
+//! outputs that don't justify a backend of their own.
+//!
+//! The template is the only parameter:
+//!
+//! ```text
+//! codegen template hypercosm.hidl shaders/enums.glsl templates/enums.glsl.j2
+//! ```
+//!
+//! Templates are rendered with `trim_blocks` and `lstrip_blocks`, and
+//! undefined variables are an error. The context has:
+//!
+//! - `namespace`: The name of the namespace.
+//! - `interfaces`: Every interface, core first, with `name`, `docs`,
+//!   `version` (a list of 3 numbers), `qualified_name`, `extension` (its
+//!   name, or `none` in the core namespace), and `methods` and `events`.
+//!   Each of those has `name`, `docs`, `args` (each with `name` and `ty`),
+//!   and `ret` (`none` for events and methods without one).
+//! - `types`: Every type definition, core first, with `name`, `docs`,
+//!   `kind` (`struct`, `enum` or `flags`), `extension`, and `fields`. Struct
+//!   fields have `name` and `ty`, and the fields of enums and flags have
+//!   `name` and `value`. Enums and flags also have a `backing` int type.
+//! - `extensions`: Each extension, with `name`, `docs`, `version`, and its
+//!   own `interfaces` and `types`.
+//!
+//! Types render as HIDL (like `[]string`), and have a `kind`, which is one
+//! of `primitive`, `int`, `array`, `dictionary`, `struct`, `enum`, `flags`,
+//! or `interface`. Arrays have an `inner` type, dictionaries have `key` and
+//! `value` types, and everything else has a `name`.
+//!
+//! As well as minijinja's builtin filters, there are filters for each `heck`
+//! case conversion (`snake_case`, `upper_camel_case`, `lower_camel_case`,
+//! `shouty_snake_case`, `kebab_case` and `title_case`), and
+//! `comment(prefix)`, which starts every line of some docs with `prefix`.
+//!
+//! [minijinja]: https://docs.rs/minijinja
+
+use std::{fmt, io::Write, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use camino::Utf8Path;
+use fs_err as fs;
+use heck::{
+    ToKebabCase, ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase, ToTitleCase, ToUpperCamelCase,
+};
+use minijinja::{
+    context,
+    value::{Object, ObjectRepr},
+    Environment, UndefinedBehavior, Value,
+};
+
+use crate::{
+    ast::{Extension, Func, Namespace, Type, TypeKind},
+    symbols::{InterfaceRef, Symbol, Symbols, TypeRef},
+    vfs,
+};
+
+pub fn generate(
+    ns: &Namespace,
+    out: &Utf8Path,
+    parameters: &[String],
+    fs: &mut vfs::FS,
+) -> Result<()> {
+    let [template] = parameters else {
+        bail!("The `template` target takes the path to the template as its only parameter");
+    };
+    let source = fs::read_to_string(template)?;
+
+    let rendered = render(ns, template, &source)?;
+    fs.open(out).write_all(rendered.as_bytes())?;
+    Ok(())
+}
+
+fn render(ns: &Namespace, name: &str, source: &str) -> Result<String> {
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.set_keep_trailing_newline(true);
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+
+    env.add_filter("snake_case", |s: &str| s.to_snake_case());
+    env.add_filter("upper_camel_case", |s: &str| s.to_upper_camel_case());
+    env.add_filter("lower_camel_case", |s: &str| s.to_lower_camel_case());
+    env.add_filter("shouty_snake_case", |s: &str| s.to_shouty_snake_case());
+    env.add_filter("kebab_case", |s: &str| s.to_kebab_case());
+    env.add_filter("title_case", |s: &str| s.to_title_case());
+    env.add_filter("comment", |docs: &str, prefix: &str| {
+        docs.trim_end()
+            .lines()
+            .map(|line| format!("{}{}", prefix, line).trim_end().to_owned())
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+
+    env.add_template_owned(name.to_owned(), source.to_owned())
+        .with_context(|| format!("Invalid template `{}`", name))?;
+
+    let cx = Cx {
+        syms: &Symbols::new(ns),
+    };
+    let context = context! {
+        namespace => ns.name,
+        interfaces => cx.interfaces(cx.syms.interfaces().iter())?,
+        types => cx.types(cx.syms.types().iter())?,
+        extensions => ns
+            .extensions
+            .iter()
+            .map(|ext| cx.extension(ext))
+            .collect::<Result<Vec<_>>>()?,
+    };
+
+    env.get_template(name)?
+        .render(context)
+        .with_context(|| format!("Can't render `{}`", name))
+}
+
+struct Cx<'a, 'b> {
+    syms: &'b Symbols<'a>,
+}
+
+impl<'a> Cx<'a, '_> {
+    fn extension(&self, ext: &Extension) -> Result<Value> {
+        let in_ext = |e: Option<&Extension>| e.is_some_and(|e| e.name == ext.name);
+        let interfaces = self
+            .syms
+            .interfaces()
+            .iter()
+            .filter(|i| in_ext(i.extension));
+        let types = self.syms.types().iter().filter(|t| in_ext(t.extension));
+
+        Ok(context! {
+            name => ext.name,
+            docs => ext.docs,
+            version => ext.version,
+            interfaces => self.interfaces(interfaces)?,
+            types => self.types(types)?,
+        })
+    }
+
+    fn interfaces<'i>(
+        &self,
+        interfaces: impl Iterator<Item = &'i InterfaceRef<'a>>,
+    ) -> Result<Vec<Value>>
+    where
+        'a: 'i,
+    {
+        interfaces
+            .map(|i| {
+                Ok(context! {
+                    name => i.name,
+                    docs => i.docs,
+                    version => i.version,
+                    qualified_name => i.qualified_name(&self.syms.namespace().name),
+                    extension => i.extension.map(|e| &e.name),
+                    methods => self.funcs(i.methods)?,
+                    events => self.funcs(i.events)?,
+                })
+            })
+            .collect()
+    }
+
+    fn funcs(&self, funcs: &[Func]) -> Result<Vec<Value>> {
+        funcs
+            .iter()
+            .map(|f| {
+                let args = f
+                    .args
+                    .iter()
+                    .map(|a| Ok(context! { name => a.name, ty => self.ty(&a.ty)? }))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(context! {
+                    name => f.name,
+                    docs => f.docs,
+                    args => args,
+                    ret => f.ret.as_ref().map(|ty| self.ty(ty)).transpose()?,
+                })
+            })
+            .collect()
+    }
+
+    fn types<'i>(&self, types: impl Iterator<Item = &'i TypeRef<'a>>) -> Result<Vec<Value>>
+    where
+        'a: 'i,
+    {
+        types
+            .map(|t| {
+                let ext = t.extension.map(|e| &e.name);
+                let (name, docs) = (&t.def.name, &t.def.docs);
+                Ok(match &t.def.kind {
+                    TypeKind::Struct(s) => {
+                        let fields = s
+                            .fields
+                            .iter()
+                            .map(|f| Ok(context! { name => f.name, ty => self.ty(&f.ty)? }))
+                            .collect::<Result<Vec<_>>>()?;
+                        context! { name, docs, kind => "struct", extension => ext, fields }
+                    }
+                    TypeKind::Enum(e) => {
+                        let fields = e
+                            .fields
+                            .iter()
+                            .map(|f| context! { name => f.name, value => f.value })
+                            .collect::<Vec<_>>();
+                        let backing = e.backing.to_string();
+                        context! { name, docs, kind => "enum", extension => ext, backing, fields }
+                    }
+                    TypeKind::Flags(f) => {
+                        let fields = f
+                            .fields
+                            .iter()
+                            .map(|f| context! { name => f.name, value => f.value })
+                            .collect::<Vec<_>>();
+                        let backing = f.backing.to_string();
+                        context! { name, docs, kind => "flags", extension => ext, backing, fields }
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn ty(&self, ty: &Type) -> Result<Value> {
+        let kind = match ty {
+            Type::Primitive(_) => "primitive",
+            Type::IntType(_) => "int",
+            Type::Array(_) => "array",
+            Type::Dictionary(_) => "dictionary",
+            Type::Custom(name) => match self.syms.resolve(name)? {
+                Symbol::Interface(_) => "interface",
+                Symbol::Type(t) => match t.def.kind {
+                    TypeKind::Struct(_) => "struct",
+                    TypeKind::Enum(_) => "enum",
+                    TypeKind::Flags(_) => "flags",
+                },
+            },
+        };
+        let inner = match ty {
+            Type::Array(inner) => vec![self.ty(inner)?],
+            Type::Dictionary(d) => vec![self.ty(&d.key)?, self.ty(&d.value)?],
+            _ => Vec::new(),
+        };
+
+        Ok(Value::from_object(TypeObject {
+            ty: ty.clone(),
+            kind,
+            inner,
+        }))
+    }
+}
+
+/// A [`Type`] in a template, which renders as HIDL.
+#[derive(Debug)]
+struct TypeObject {
+    ty: Type,
+    kind: &'static str,
+    /// The element type of an array, or the key and value types of a
+    /// dictionary
+    inner: Vec<Value>,
+}
+
+impl Object for TypeObject {
+    fn repr(self: &Arc<Self>) -> ObjectRepr {
+        ObjectRepr::Plain
+    }
+
+    fn get_value(self: &Arc<Self>, key: &Value) -> Option<Value> {
+        match (key.as_str()?, &self.ty) {
+            ("kind", _) => Some(Value::from(self.kind)),
+            ("inner", Type::Array(_)) | ("key", Type::Dictionary(_)) => Some(self.inner[0].clone()),
+            ("value", Type::Dictionary(_)) => Some(self.inner[1].clone()),
+            ("name", Type::Primitive(p)) => Some(Value::from(p.to_string())),
+            ("name", Type::IntType(i)) => Some(Value::from(i.to_string())),
+            ("name", Type::Custom(name)) => Some(Value::from(name.as_str())),
+            _ => None,
+        }
+    }
+
+    fn render(self: &Arc<Self>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.ty, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn shader_enums() {
+        let ns = crate::codegen::parse(include_str!("../../hypercosm.hidl"));
+        let template = r#"// {{ namespace | title_case }}
+{% for t in types if t.kind == "flags" %}
+{% if t.docs %}
+{{ t.docs | comment("// ") }}
+{% endif %}
+{% for f in t.fields %}
+#define {{ t.name | shouty_snake_case }}_{{ f.name | shouty_snake_case }} {{ f.value }}u
+{% endfor %}
+{% endfor %}
+{% for ext in extensions if ext.name == "world" %}
+{% for i in ext.interfaces if i.events %}
+{{ i.qualified_name }} {{ i.version | join(".") }}
+{% for e in i.events %}
+  {{ e.name | upper_camel_case }}({% for a in e.args %}{{ a.name }}: {{ a.ty }} [{{ a.ty.kind }} of {{ a.ty.inner.kind }} {{ a.ty.inner.name }}]{% endfor %})
+{% endfor %}
+{% endfor %}
+{% endfor %}
+"#;
+
+        assert_eq!(
+            render(&ns, "test", template).unwrap(),
+            "// Hypercosm
+#define ENTITY_ATTRS_NONE 0u
+#define ENTITY_ATTRS_INTERACTABLE 1u
+#define ENTITY_ATTRS_COLLIDABLE 2u
+hypercosm.world.world 0.1.0
+  AddEntities(entities: []EntityInfo [array of struct EntityInfo])
+  UpdateEntities(entities: []EntityInfo [array of struct EntityInfo])
+  RemoveEntities(entities: []Entity [array of interface Entity])
+"
+        );
+
+        let err = render(&ns, "test", "{{ nope }}").unwrap_err();
+        assert_eq!(err.to_string(), "Can't render `test`");
+    }
+}
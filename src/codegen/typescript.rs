@@ -0,0 +1,628 @@
+//! TypeScript generator.
+//!
+//! Output is a single self contained module: the runtime from
+//! `runtime/typescript.ts`, followed by the types, their encoders and
+//! decoders, and a client class for every interface.
+//!
+//! Integers up to 32 bits are `number`s, 64 bit ones are `bigint`s.
+
+use std::io::Write;
+
+use anyhow::Result;
+use heck::{ToLowerCamelCase, ToUpperCamelCase};
+
+use crate::{
+    ast::{Enum, Flags, Func, IntType, Namespace, PrimType, Struct, Type, TypeKind},
+    symbols::{InterfaceRef, Symbol, Symbols, TypeRef},
+};
+
+const RUNTIME: &str = include_str!("runtime/typescript.ts");
+
+pub fn generate(ns: &Namespace, w: &mut dyn Write) -> Result<()> {
+    let syms = Symbols::new(ns);
+
+    writeln!(
+        w,
+        "// Generated by hidl from the `{}` namespace. Do not edit.\n",
+        ns.name
+    )?;
+    w.write_all(RUNTIME.as_bytes())?;
+
+    for ty in syms.types() {
+        writeln!(w)?;
+        write_type(w, &syms, ty)?;
+    }
+
+    for i in syms.interfaces() {
+        writeln!(w)?;
+        write_interface(w, &syms, ns, i)?;
+    }
+
+    Ok(())
+}
+
+fn write_type(w: &mut dyn Write, syms: &Symbols, ty: &TypeRef) -> Result<()> {
+    let name = ty.def.name.to_upper_camel_case();
+    docs(w, "", &ty.def.docs)?;
+
+    match &ty.def.kind {
+        TypeKind::Struct(s) => write_struct(w, syms, &name, s),
+        TypeKind::Enum(e) => write_enum(w, &name, e),
+        TypeKind::Flags(f) => write_flags(w, &name, f),
+    }
+}
+
+fn write_struct(w: &mut dyn Write, syms: &Symbols, name: &str, s: &Struct) -> Result<()> {
+    writeln!(w, "export interface {} {{", name)?;
+    for f in &s.fields {
+        writeln!(
+            w,
+            "    {}: {};",
+            f.name.to_lower_camel_case(),
+            ts_type(syms, &f.ty)?
+        )?;
+    }
+    writeln!(w, "}}\n")?;
+
+    writeln!(
+        w,
+        "export function encode{0}(w: Writer, v: {0}): void {{",
+        name
+    )?;
+    for f in &s.fields {
+        let field = format!("v.{}", f.name.to_lower_camel_case());
+        writeln!(w, "    {};", encode(syms, &f.ty, "w", &field)?)?;
+    }
+    writeln!(w, "}}\n")?;
+
+    writeln!(w, "export function decode{0}(r: Reader): {0} {{", name)?;
+    writeln!(w, "    return {{")?;
+    for f in &s.fields {
+        writeln!(
+            w,
+            "        {}: {},",
+            f.name.to_lower_camel_case(),
+            decode(syms, &f.ty, "r")?
+        )?;
+    }
+    writeln!(w, "    }};")?;
+    writeln!(w, "}}")?;
+
+    Ok(())
+}
+
+fn write_enum(w: &mut dyn Write, name: &str, e: &Enum) -> Result<()> {
+    // `const enum`s can only hold `number`s, so 64 bit ones are a union of
+    // `bigint`s, with an object of the values to use like an enum.
+    let big = is_big(&e.backing);
+    if big {
+        let values = e
+            .fields
+            .iter()
+            .map(|f| format!("{}n", f.value.unwrap()))
+            .collect::<Vec<_>>();
+        let union = if values.is_empty() {
+            "never".to_owned()
+        } else {
+            values.join(" | ")
+        };
+        writeln!(w, "export type {} = {};\n", name, union)?;
+        writeln!(w, "export const {} = {{", name)?;
+        for f in &e.fields {
+            writeln!(w, "    {}: {}n,", f.name, f.value.unwrap())?;
+        }
+        writeln!(w, "}} as const;\n")?;
+    } else {
+        writeln!(w, "export const enum {} {{", name)?;
+        for f in &e.fields {
+            writeln!(w, "    {} = {},", f.name, f.value.unwrap())?;
+        }
+        writeln!(w, "}}\n")?;
+    }
+    let suffix = if big { "n" } else { "" };
+
+    writeln!(
+        w,
+        "export function encode{0}(w: Writer, v: {0}): void {{",
+        name
+    )?;
+    writeln!(w, "    {};", encode_int(&e.backing, "w", "v"))?;
+    writeln!(w, "}}\n")?;
+
+    writeln!(w, "export function decode{0}(r: Reader): {0} {{", name)?;
+    writeln!(w, "    const v = {};", decode_int(&e.backing, "r"))?;
+    writeln!(w, "    switch (v) {{")?;
+    for f in &e.fields {
+        writeln!(w, "        case {}{}:", f.value.unwrap(), suffix)?;
+    }
+    if !e.fields.is_empty() {
+        // Switches don't narrow `bigint`s to literals
+        if big {
+            writeln!(w, "            return v as {};", name)?;
+        } else {
+            writeln!(w, "            return v;")?;
+        }
+    }
+    writeln!(w, "        default:")?;
+    writeln!(
+        w,
+        "            throw new Error(`Invalid {}: ${{v}}`);",
+        name
+    )?;
+    writeln!(w, "    }}")?;
+    writeln!(w, "}}")?;
+
+    Ok(())
+}
+
+fn write_flags(w: &mut dyn Write, name: &str, f: &Flags) -> Result<()> {
+    let big = is_big(&f.backing);
+    let (ty, suffix) = if big { ("bigint", "n") } else { ("number", "") };
+    // Bitwise operators on `number`s produce an i32, so make sure unsigned
+    // flags stay positive.
+    let norm = if big || is_signed(&f.backing) {
+        ""
+    } else {
+        " >>> 0"
+    };
+
+    writeln!(w, "export type {} = {};\n", name, ty)?;
+    writeln!(w, "export const {} = {{", name)?;
+    for field in &f.fields {
+        writeln!(
+            w,
+            "    {}: {}{} as {},",
+            field.name, field.value, suffix, name
+        )?;
+    }
+    writeln!(
+        w,
+        "    /** Check if all bits in `flag` are set in `value` */"
+    )?;
+    writeln!(
+        w,
+        "    has(value: {0}, flag: {0}): boolean {{ return (value & flag) === flag; }},",
+        name
+    )?;
+    writeln!(
+        w,
+        "    set(value: {0}, flag: {0}): {0} {{ return (value | flag){1}; }},",
+        name, norm
+    )?;
+    writeln!(
+        w,
+        "    clear(value: {0}, flag: {0}): {0} {{ return (value & ~flag){1}; }},",
+        name, norm
+    )?;
+    writeln!(
+        w,
+        "    /** The names of all non zero flags set in `value` */"
+    )?;
+    writeln!(w, "    names(value: {}): string[] {{", name)?;
+    writeln!(w, "        const names: string[] = [];")?;
+    for field in &f.fields {
+        if field.value != 0 {
+            writeln!(
+                w,
+                "        if ((value & {1}{2}) === {1}{2}) names.push(\"{0}\");",
+                field.name, field.value, suffix
+            )?;
+        }
+    }
+    writeln!(w, "        return names;")?;
+    writeln!(w, "    }},")?;
+    writeln!(w, "}} as const;\n")?;
+
+    writeln!(
+        w,
+        "export function encode{0}(w: Writer, v: {0}): void {{",
+        name
+    )?;
+    writeln!(w, "    {};", encode_int(&f.backing, "w", "v"))?;
+    writeln!(w, "}}\n")?;
+
+    writeln!(w, "export function decode{0}(r: Reader): {0} {{", name)?;
+    writeln!(w, "    return {};", decode_int(&f.backing, "r"))?;
+    writeln!(w, "}}")?;
+
+    Ok(())
+}
+
+fn write_interface(
+    w: &mut dyn Write,
+    syms: &Symbols,
+    ns: &Namespace,
+    i: &InterfaceRef,
+) -> Result<()> {
+    let name = i.name.to_upper_camel_case();
+    let events = format!("{}Events", name);
+    let class = format!("{}Client", name);
+
+    if !i.events.is_empty() {
+        writeln!(w, "export interface {} {{", events)?;
+        for e in i.events {
+            docs(w, "    ", &e.docs)?;
+            writeln!(
+                w,
+                "    {}: ({}) => void;",
+                e.name.to_lower_camel_case(),
+                params(syms, e)?
+            )?;
+        }
+        writeln!(w, "}}\n")?;
+    }
+
+    docs(w, "", i.docs)?;
+    if i.events.is_empty() {
+        writeln!(w, "export class {} {{", class)?;
+    } else {
+        writeln!(w, "export class {} extends Emitter<{}> {{", class, events)?;
+    }
+    writeln!(
+        w,
+        "    static readonly interfaceName = \"{}\";",
+        i.qualified_name(&ns.name)
+    )?;
+    writeln!(
+        w,
+        "    static readonly version = \"{}.{}.{}\";\n",
+        i.version.0, i.version.1, i.version.2
+    )?;
+
+    write!(
+        w,
+        "    constructor(readonly transport: Transport, readonly id: ObjectId) {{"
+    )?;
+    if i.events.is_empty() {
+        writeln!(w, "}}")?;
+    } else {
+        writeln!(w)?;
+        writeln!(w, "        super();")?;
+        writeln!(
+            w,
+            "        transport.listen(id, {}.interfaceName, (event, args) => {{",
+            class
+        )?;
+        writeln!(w, "            const _r = new Reader(args);")?;
+        writeln!(w, "            switch (event) {{")?;
+        for e in i.events {
+            writeln!(w, "                case \"{}\": {{", e.name)?;
+            for a in &e.args {
+                writeln!(
+                    w,
+                    "                    const {} = {};",
+                    ident(&a.name),
+                    decode(syms, &a.ty, "_r")?
+                )?;
+            }
+            writeln!(w, "                    _r.finish();")?;
+            let args = e
+                .args
+                .iter()
+                .map(|a| format!(", {}", ident(&a.name)))
+                .collect::<String>();
+            writeln!(
+                w,
+                "                    this.emit(\"{}\"{});",
+                e.name.to_lower_camel_case(),
+                args
+            )?;
+            writeln!(w, "                    break;")?;
+            writeln!(w, "                }}")?;
+        }
+        writeln!(w, "            }}")?;
+        writeln!(w, "        }});")?;
+        writeln!(w, "    }}")?;
+    }
+
+    for m in i.methods {
+        writeln!(w)?;
+        docs(w, "    ", &m.docs)?;
+        let ret = match &m.ret {
+            Some(ret) => ts_type(syms, ret)?,
+            None => "void".to_owned(),
+        };
+        writeln!(
+            w,
+            "    async {}({}): Promise<{}> {{",
+            m.name.to_lower_camel_case(),
+            params(syms, m)?,
+            ret
+        )?;
+        writeln!(w, "        const _w = new Writer();")?;
+        for a in &m.args {
+            writeln!(
+                w,
+                "        {};",
+                encode(syms, &a.ty, "_w", &ident(&a.name))?
+            )?;
+        }
+        writeln!(
+            w,
+            "        const _r = new Reader(await this.transport.call(this.id, {}.interfaceName, \"{}\", _w.finish()));",
+            class, m.name
+        )?;
+        match &m.ret {
+            Some(ret) => {
+                writeln!(w, "        const _ret = {};", decode(syms, ret, "_r")?)?;
+                writeln!(w, "        _r.finish();")?;
+                writeln!(w, "        return _ret;")?;
+            }
+            None => writeln!(w, "        _r.finish();")?,
+        }
+        writeln!(w, "    }}")?;
+    }
+
+    writeln!(w, "}}")?;
+
+    Ok(())
+}
+
+fn params(syms: &Symbols, f: &Func) -> Result<String> {
+    let params = f
+        .args
+        .iter()
+        .map(|a| Ok(format!("{}: {}", ident(&a.name), ts_type(syms, &a.ty)?)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(params.join(", "))
+}
+
+fn ts_type(syms: &Symbols, ty: &Type) -> Result<String> {
+    Ok(match ty {
+        Type::Primitive(p) => match p {
+            PrimType::String => "string",
+            PrimType::Object => "ObjectId",
+            PrimType::Uuid => "Uuid",
+            PrimType::Bytes => "Uint8Array",
+            PrimType::Bool => "boolean",
+            PrimType::Matrix4x4 => "Matrix4x4",
+            PrimType::F32 | PrimType::F64 => "number",
+        }
+        .to_owned(),
+        Type::IntType(i) => if is_big(i) { "bigint" } else { "number" }.to_owned(),
+        Type::Custom(name) => match syms.resolve(name)? {
+            Symbol::Interface(_) => "ObjectId".to_owned(),
+            Symbol::Type(t) => t.def.name.to_upper_camel_case(),
+        },
+        Type::Array(t) => format!("{}[]", ts_type(syms, t)?),
+        Type::Dictionary(d) => format!(
+            "Map<{}, {}>",
+            ts_type(syms, &d.key)?,
+            ts_type(syms, &d.value)?
+        ),
+    })
+}
+
+/// An expression that writes `v` to the `Writer` named `w`.
+fn encode(syms: &Symbols, ty: &Type, w: &str, v: &str) -> Result<String> {
+    Ok(match ty {
+        Type::Primitive(p) => format!("{}.{}({})", w, p, v),
+        Type::IntType(i) => encode_int(i, w, v),
+        Type::Custom(name) => match syms.resolve(name)? {
+            Symbol::Interface(_) => format!("{}.object({})", w, v),
+            Symbol::Type(t) => format!("encode{}({}, {})", t.def.name.to_upper_camel_case(), w, v),
+        },
+        Type::Array(t) => format!(
+            "{}.array({}, (w, x) => {})",
+            w,
+            v,
+            encode(syms, t, "w", "x")?
+        ),
+        Type::Dictionary(d) => format!(
+            "{}.map({}, (w, k) => {}, (w, x) => {})",
+            w,
+            v,
+            encode(syms, &d.key, "w", "k")?,
+            encode(syms, &d.value, "w", "x")?
+        ),
+    })
+}
+
+/// An expression that reads a value from the `Reader` named `r`.
+fn decode(syms: &Symbols, ty: &Type, r: &str) -> Result<String> {
+    Ok(match ty {
+        Type::Primitive(p) => format!("{}.{}()", r, p),
+        Type::IntType(i) => decode_int(i, r),
+        Type::Custom(name) => match syms.resolve(name)? {
+            Symbol::Interface(_) => format!("{}.object()", r),
+            Symbol::Type(t) => format!("decode{}({})", t.def.name.to_upper_camel_case(), r),
+        },
+        Type::Array(t) => format!("{}.array((r) => {})", r, decode(syms, t, "r")?),
+        Type::Dictionary(d) => format!(
+            "{}.map((r) => {}, (r) => {})",
+            r,
+            decode(syms, &d.key, "r")?,
+            decode(syms, &d.value, "r")?
+        ),
+    })
+}
+
+fn encode_int(i: &IntType, w: &str, v: &str) -> String {
+    match i {
+        IntType::VU8 | IntType::VU16 | IntType::VU32 => format!("{}.vu32({})", w, v),
+        IntType::VI8 | IntType::VI16 | IntType::VI32 => format!("{}.vi32({})", w, v),
+        _ => format!("{}.{}({})", w, i, v),
+    }
+}
+
+fn decode_int(i: &IntType, r: &str) -> String {
+    match i {
+        IntType::VU8 => format!("{}.vu32(8)", r),
+        IntType::VU16 => format!("{}.vu32(16)", r),
+        IntType::VI8 => format!("{}.vi32(8)", r),
+        IntType::VI16 => format!("{}.vi32(16)", r),
+        _ => format!("{}.{}()", r, i),
+    }
+}
+
+fn is_big(i: &IntType) -> bool {
+    matches!(
+        i,
+        IntType::U64 | IntType::I64 | IntType::VU64 | IntType::VI64
+    )
+}
+
+fn is_signed(i: &IntType) -> bool {
+    matches!(
+        i,
+        IntType::I8
+            | IntType::I16
+            | IntType::I32
+            | IntType::I64
+            | IntType::VI8
+            | IntType::VI16
+            | IntType::VI32
+            | IntType::VI64
+    )
+}
+
+/// Escape names that can't be used as parameters.
+fn ident(name: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "arguments",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "debugger",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "eval",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "function",
+        "if",
+        "implements",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "with",
+        "yield",
+    ];
+
+    let name = name.to_lower_camel_case();
+    if RESERVED.contains(&&name[..]) {
+        name + "_"
+    } else {
+        name
+    }
+}
+
+fn docs(w: &mut dyn Write, indent: &str, docs: &str) -> Result<()> {
+    super::write_docs(
+        w,
+        indent,
+        ["/**", " * ", " */"],
+        &docs.replace("*/", "*\\/"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn gen(hidl: &str) -> String {
+        let mut out = Vec::new();
+        generate(&crate::codegen::parse(hidl), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn enums() {
+        let out = gen("namespace test enum Kind(u16) { A B = 10 C }");
+        let expected = r#"export const enum Kind {
+    A = 0,
+    B = 10,
+    C = 11,
+}
+
+export function encodeKind(w: Writer, v: Kind): void {
+    w.u16(v);
+}
+
+export function decodeKind(r: Reader): Kind {
+    const v = r.u16();
+    switch (v) {
+        case 0:
+        case 10:
+        case 11:
+            return v;
+        default:
+            throw new Error(`Invalid Kind: ${v}`);
+    }
+}
+"#;
+        assert_eq!(&out[out.len() - expected.len()..], expected);
+
+        // Past 2^53, which a `number` can't hold exactly
+        let out = gen("namespace test enum Big(vu64) { A B = 9007199254740993 }");
+        let expected = r#"export type Big = 0n | 9007199254740993n;
+
+export const Big = {
+    A: 0n,
+    B: 9007199254740993n,
+} as const;
+
+export function encodeBig(w: Writer, v: Big): void {
+    w.vu64(v);
+}
+
+export function decodeBig(r: Reader): Big {
+    const v = r.vu64();
+    switch (v) {
+        case 0n:
+        case 9007199254740993n:
+            return v as Big;
+        default:
+            throw new Error(`Invalid Big: ${v}`);
+    }
+}
+"#;
+        assert_eq!(&out[out.len() - expected.len()..], expected);
+    }
+
+    #[test]
+    fn hypercosm() {
+        let ns = crate::codegen::parse(include_str!("../../hypercosm.hidl"));
+        let mut out = Vec::new();
+        generate(&ns, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(
+            out.contains("export class AssetDeliveryClient extends Emitter<AssetDeliveryEvents> {")
+        );
+        assert!(out.contains("    async fetchByIds(ids: Uuid[]): Promise<Asset[]> {"));
+        assert!(out.contains("        entity: r.object(),"));
+        assert!(out.contains("    static readonly interfaceName = \"hypercosm.world.Entity\";"));
+    }
+}
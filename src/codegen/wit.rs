@@ -0,0 +1,618 @@
+//! [WIT] generator, so wasm modules loaded by `execution_context` have an
+//! interface to compile against.
+//!
+//! The core namespace becomes the package `{namespace}:core`, and each
+//! extension becomes `{namespace}:{extension}@{version}`, all nested in one
+//! file, with the core package at the top level. Every package has a
+//! `types` interface, and an `events` interface if any of its interfaces
+//! have events.
+//!
+//! Mappings:
+//!
+//! - Structs become `record`s, enums become `enum`s, and flags become
+//!   `flags`.
+//! - Interfaces become `resource`s, with a method per HIDL method. Methods
+//!   borrow resources passed as arguments, and own everything else.
+//! - Events become functions in the `events` interface, named
+//!   `{interface}-{event}`, taking the resource that raised them as
+//!   `target`. Modules export them to receive events.
+//! - `object` becomes the `object` resource if the core namespace has an
+//!   `Object` interface, and `u64` otherwise.
+//! - `uuid` is `tuple<u64, u64>`, the most significant half first.
+//! - `matrix4x4` is a tuple of 16 `f32`s, in column major order.
+//! - `bytes` is `list<u8>`, and dictionaries are lists of key value tuples.
+//! - Variable length ints become the fixed width int of the same size, as
+//!   the canonical ABI doesn't care about the wire encoding.
+//!
+//! WIT can't represent everything HIDL can:
+//!
+//! - Enum cases are numbered in order, so if the HIDL values aren't
+//!   `0, 1, 2...` each case documents its wire value.
+//! - Each WIT flag is a single bit, numbered in order. Zero and multi bit
+//!   values are left out, and unused bits are filled with `reserved-{bit}`.
+//!   Values past bit 31 are an error.
+//!
+//! [WIT]: https://component-model.bytecodealliance.org/design/wit.html
+
+use std::{collections::BTreeSet, io::Write};
+
+use anyhow::{bail, Result};
+use heck::ToKebabCase;
+
+use crate::{
+    ast::{Func, IntType, Namespace, PrimType, Type, TypeKind},
+    symbols::{InterfaceRef, Symbol, Symbols, TypeRef},
+};
+
+/// The interface `object` refers to, if the namespace has it.
+const ROOT_INTERFACE: &str = "Object";
+
+pub fn generate(ns: &Namespace, w: &mut dyn Write) -> Result<()> {
+    let syms = Symbols::new(ns);
+    let has_object = syms
+        .interfaces()
+        .iter()
+        .any(|i| i.name == ROOT_INTERFACE && i.extension.is_none());
+
+    writeln!(
+        w,
+        "// Generated by hidl from the `{}` namespace. Do not edit.",
+        ns.name
+    )?;
+
+    // wit-parser needs a package at the top level, which the others nest
+    // under
+    let mut packages = vec![None];
+    packages.extend(ns.extensions.iter().map(|e| Some(&e.name[..])));
+
+    for pkg in packages {
+        let mut cx = Cx {
+            syms: &syms,
+            pkg,
+            has_object,
+            uses: BTreeSet::new(),
+        };
+
+        let mut body = Vec::new();
+        write_types(&mut body, &mut cx)?;
+        write_events(&mut body, &mut cx)?;
+        let body = String::from_utf8(body)?;
+
+        writeln!(w)?;
+        match pkg {
+            None => {
+                writeln!(w, "package {};\n", cx.package(None))?;
+                write!(w, "{}", body)?;
+            }
+            Some(ext) => {
+                let ext = ns.extensions.iter().find(|e| e.name == ext).unwrap();
+                docs(w, "", &ext.docs)?;
+                writeln!(w, "package {} {{", cx.package(Some(&ext.name)))?;
+                for line in body.lines() {
+                    if line.is_empty() {
+                        writeln!(w)?;
+                    } else {
+                        writeln!(w, "    {}", line)?;
+                    }
+                }
+                writeln!(w, "}}")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+struct Cx<'a, 'b> {
+    syms: &'b Symbols<'a>,
+    /// The extension being generated, or `None` for the core namespace
+    pkg: Option<&'a str>,
+    has_object: bool,
+    /// Every name used, with the extension it comes from
+    uses: BTreeSet<(Option<&'a str>, String)>,
+}
+
+impl<'a, 'b> Cx<'a, 'b> {
+    fn package(&self, ext: Option<&str>) -> String {
+        let ns = self.syms.namespace();
+        match ext {
+            None => format!("{}:core", ident(&ns.name.to_kebab_case())),
+            Some(ext) => {
+                let ext = ns.extensions.iter().find(|e| e.name == ext).unwrap();
+                let (a, b, c) = ext.version;
+                format!(
+                    "{}:{}@{}.{}.{}",
+                    ident(&ns.name.to_kebab_case()),
+                    ident(&ext.name.to_kebab_case()),
+                    a,
+                    b,
+                    c
+                )
+            }
+        }
+    }
+
+    fn types(&self) -> impl Iterator<Item = &TypeRef<'a>> {
+        let pkg = self.pkg;
+        self.syms
+            .types()
+            .iter()
+            .filter(move |t| t.extension.map(|e| &e.name[..]) == pkg)
+    }
+
+    fn interfaces(&self) -> impl Iterator<Item = &InterfaceRef<'a>> {
+        let pkg = self.pkg;
+        self.syms
+            .interfaces()
+            .iter()
+            .filter(move |i| i.extension.map(|e| &e.name[..]) == pkg)
+    }
+
+    fn use_name(&mut self, ext: Option<&'a str>, name: String) -> String {
+        self.uses.insert((ext, name.clone()));
+        ident(&name)
+    }
+
+    /// Write a `use` for every name from another package, and if
+    /// `local_from` is given, every name from this package too.
+    fn write_uses(&self, w: &mut dyn Write, local_from: Option<&str>) -> Result<()> {
+        let mut wrote = false;
+        let mut names = Vec::new();
+        let mut uses = self.uses.iter().peekable();
+
+        while let Some((e, name)) = uses.next() {
+            names.push(ident(name));
+            if uses.peek().map(|(next, _)| next) == Some(e) {
+                continue;
+            }
+
+            let from = if *e == self.pkg {
+                local_from.map(str::to_owned)
+            } else {
+                let pkg = self.package(*e);
+                Some(match pkg.split_once('@') {
+                    Some((pkg, version)) => format!("{}/types@{}", pkg, version),
+                    None => format!("{}/types", pkg),
+                })
+            };
+            if let Some(from) = from {
+                writeln!(w, "    use {}.{{{}}};", from, names.join(", "))?;
+                wrote = true;
+            }
+            names.clear();
+        }
+
+        if wrote {
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_types(w: &mut dyn Write, cx: &mut Cx) -> Result<()> {
+    let mut body = Vec::new();
+    let b = &mut body;
+
+    if cx.pkg.is_none() {
+        writeln!(
+            b,
+            "    /// A UUID, as its most then least significant 64 bits"
+        )?;
+        writeln!(b, "    type uuid = tuple<u64, u64>;")?;
+        writeln!(b)?;
+        writeln!(b, "    /// A 4x4 matrix, in column major order")?;
+        writeln!(b, "    type matrix4x4 = tuple<{}>;", ["f32"; 16].join(", "))?;
+    }
+
+    let types = cx.types().copied().collect::<Vec<_>>();
+    for (n, ty) in types.iter().enumerate() {
+        if n != 0 || cx.pkg.is_none() {
+            writeln!(b)?;
+        }
+        write_type(b, cx, ty)?;
+    }
+
+    let interfaces = cx.interfaces().copied().collect::<Vec<_>>();
+    for (n, i) in interfaces.iter().enumerate() {
+        if n != 0 || !types.is_empty() || cx.pkg.is_none() {
+            writeln!(b)?;
+        }
+        write_resource(b, cx, i)?;
+    }
+
+    writeln!(w, "interface types {{")?;
+    cx.write_uses(w, None)?;
+    w.write_all(&body)?;
+    writeln!(w, "}}")?;
+    Ok(())
+}
+
+fn write_type(w: &mut dyn Write, cx: &mut Cx, ty: &TypeRef) -> Result<()> {
+    let name = ident(&ty.def.name.to_kebab_case());
+    docs(w, "    ", &ty.def.docs)?;
+
+    match &ty.def.kind {
+        TypeKind::Struct(s) => {
+            writeln!(w, "    record {} {{", name)?;
+            for f in &s.fields {
+                let ty = wit_type(cx, &f.ty, false)?;
+                writeln!(w, "        {}: {},", ident(&f.name.to_kebab_case()), ty)?;
+            }
+            writeln!(w, "    }}")?;
+        }
+        TypeKind::Enum(e) => {
+            let sequential = e
+                .fields
+                .iter()
+                .enumerate()
+                .all(|(n, f)| f.value == Some(n as i64));
+            let mut fields = e.fields.iter().collect::<Vec<_>>();
+            fields.sort_by_key(|f| f.value);
+
+            writeln!(w, "    enum {} {{", name)?;
+            for f in fields {
+                if !sequential {
+                    writeln!(w, "        /// Wire value: {}", f.value.unwrap())?;
+                }
+                writeln!(w, "        {},", ident(&f.name.to_kebab_case()))?;
+            }
+            writeln!(w, "    }}")?;
+        }
+        TypeKind::Flags(f) => {
+            let mut bits = [None; 32];
+            for field in &f.fields {
+                let value = field.value as u64;
+                if value.count_ones() != 1 {
+                    continue;
+                }
+                let bit = value.trailing_zeros() as usize;
+                if bit >= bits.len() {
+                    bail!(
+                        "Flag `{}.{}` uses bit {}, but WIT only has 32 flags",
+                        ty.def.name,
+                        field.name,
+                        bit
+                    );
+                }
+                bits[bit] = Some(&field.name);
+            }
+            let len = bits.iter().rposition(Option::is_some).map_or(0, |n| n + 1);
+
+            writeln!(w, "    flags {} {{", name)?;
+            for (bit, field) in bits[..len].iter().enumerate() {
+                match field {
+                    Some(name) => writeln!(w, "        {},", ident(&name.to_kebab_case()))?,
+                    None => writeln!(w, "        reserved-{},", bit)?,
+                }
+            }
+            writeln!(w, "    }}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_resource(w: &mut dyn Write, cx: &mut Cx, i: &InterfaceRef) -> Result<()> {
+    docs(w, "    ", i.docs)?;
+    if !i.docs.trim().is_empty() {
+        writeln!(w, "    ///")?;
+    }
+    let (a, b, c) = i.version;
+    writeln!(w, "    /// Version: {}.{}.{}", a, b, c)?;
+
+    if i.methods.is_empty() {
+        writeln!(w, "    resource {};", ident(&i.name.to_kebab_case()))?;
+        return Ok(());
+    }
+
+    writeln!(w, "    resource {} {{", ident(&i.name.to_kebab_case()))?;
+    for (n, m) in i.methods.iter().enumerate() {
+        if n != 0 {
+            writeln!(w)?;
+        }
+        docs(w, "        ", &m.docs)?;
+        writeln!(
+            w,
+            "        {}: func({}){};",
+            ident(&m.name.to_kebab_case()),
+            params(cx, &m.args)?,
+            results(cx, m)?
+        )?;
+    }
+    writeln!(w, "    }}")?;
+
+    Ok(())
+}
+
+fn write_events(w: &mut dyn Write, cx: &mut Cx) -> Result<()> {
+    let interfaces = cx.interfaces().copied().collect::<Vec<_>>();
+    if interfaces.iter().all(|i| i.events.is_empty()) {
+        return Ok(());
+    }
+
+    cx.uses.clear();
+    let mut body = Vec::new();
+    let b = &mut body;
+    let mut first = true;
+
+    for i in interfaces {
+        let resource = cx.use_name(cx.pkg, i.name.to_kebab_case());
+
+        for e in i.events {
+            if !first {
+                writeln!(b)?;
+            }
+            first = false;
+
+            docs(b, "    ", &e.docs)?;
+            let mut params = format!("target: borrow<{}>", resource);
+            if !e.args.is_empty() {
+                params.push_str(", ");
+                params.push_str(&self::params(cx, &e.args)?);
+            }
+            writeln!(
+                b,
+                "    {}-{}: func({});",
+                i.name.to_kebab_case(),
+                e.name.to_kebab_case(),
+                params
+            )?;
+        }
+    }
+
+    writeln!(w)?;
+    writeln!(w, "/// Events, for modules to export")?;
+    writeln!(w, "interface events {{")?;
+    cx.write_uses(w, Some("types"))?;
+    w.write_all(&body)?;
+    writeln!(w, "}}")?;
+    Ok(())
+}
+
+fn params(cx: &mut Cx, args: &[crate::ast::Arg]) -> Result<String> {
+    let params = args
+        .iter()
+        .map(|a| {
+            Ok(format!(
+                "{}: {}",
+                ident(&a.name.to_kebab_case()),
+                wit_type(cx, &a.ty, true)?
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(params.join(", "))
+}
+
+fn results(cx: &mut Cx, f: &Func) -> Result<String> {
+    Ok(match &f.ret {
+        Some(ret) => format!(" -> {}", wit_type(cx, ret, false)?),
+        None => String::new(),
+    })
+}
+
+/// `borrow` is whether a resource here should be borrowed, which is only the
+/// case for arguments, and not anything nested in them.
+fn wit_type<'a>(cx: &mut Cx<'a, '_>, ty: &Type, borrow: bool) -> Result<String> {
+    let resource = |cx: &mut Cx<'a, '_>, ext: Option<&'a str>, name| {
+        let name = cx.use_name(ext, name);
+        if borrow {
+            format!("borrow<{}>", name)
+        } else {
+            name
+        }
+    };
+
+    Ok(match ty {
+        Type::Primitive(p) => match p {
+            PrimType::String => "string".to_owned(),
+            PrimType::Object if cx.has_object => resource(cx, None, ROOT_INTERFACE.to_kebab_case()),
+            PrimType::Object => "u64".to_owned(),
+            PrimType::Uuid => cx.use_name(None, "uuid".to_owned()),
+            PrimType::Bytes => "list<u8>".to_owned(),
+            PrimType::Bool => "bool".to_owned(),
+            PrimType::Matrix4x4 => cx.use_name(None, "matrix4x4".to_owned()),
+            PrimType::F32 => "f32".to_owned(),
+            PrimType::F64 => "f64".to_owned(),
+        },
+        Type::IntType(i) => int_type(i).to_owned(),
+        Type::Custom(name) => match cx.syms.resolve(name)? {
+            Symbol::Interface(i) => {
+                resource(cx, i.extension.map(|e| &e.name[..]), name.to_kebab_case())
+            }
+            Symbol::Type(t) => cx.use_name(t.extension.map(|e| &e.name[..]), name.to_kebab_case()),
+        },
+        Type::Array(t) => format!("list<{}>", wit_type(cx, t, false)?),
+        Type::Dictionary(d) => format!(
+            "list<tuple<{}, {}>>",
+            wit_type(cx, &d.key, false)?,
+            wit_type(cx, &d.value, false)?
+        ),
+    })
+}
+
+fn int_type(i: &IntType) -> &'static str {
+    match i {
+        IntType::U8 | IntType::VU8 => "u8",
+        IntType::U16 | IntType::VU16 => "u16",
+        IntType::U32 | IntType::VU32 => "u32",
+        IntType::U64 | IntType::VU64 => "u64",
+        IntType::I8 | IntType::VI8 => "s8",
+        IntType::I16 | IntType::VI16 => "s16",
+        IntType::I32 | IntType::VI32 => "s32",
+        IntType::I64 | IntType::VI64 => "s64",
+    }
+}
+
+/// Escape WIT keywords, assuming `name` is already kebab case.
+fn ident(name: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "as",
+        "async",
+        "bool",
+        "borrow",
+        "char",
+        "constructor",
+        "enum",
+        "error-context",
+        "export",
+        "f32",
+        "f64",
+        "flags",
+        "from",
+        "func",
+        "future",
+        "import",
+        "include",
+        "interface",
+        "list",
+        "option",
+        "own",
+        "package",
+        "record",
+        "resource",
+        "result",
+        "s16",
+        "s32",
+        "s64",
+        "s8",
+        "static",
+        "stream",
+        "string",
+        "tuple",
+        "type",
+        "u16",
+        "u32",
+        "u64",
+        "u8",
+        "use",
+        "variant",
+        "with",
+        "world",
+    ];
+
+    if RESERVED.contains(&name) {
+        format!("%{}", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+fn docs(w: &mut dyn Write, indent: &str, docs: &str) -> Result<()> {
+    super::write_docs(w, indent, ["", "/// ", ""], docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wit_parser::{Resolve, TypeDefKind, TypeOwner};
+
+    /// Parse the output with `wit-parser`, and check everything in the HIDL
+    /// made it through.
+    #[test]
+    fn round_trip() {
+        let ns = crate::codegen::parse(include_str!("../../hypercosm.hidl"));
+        let mut out = Vec::new();
+        generate(&ns, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let mut resolve = Resolve::new();
+        resolve
+            .push_str("hypercosm.wit", &out)
+            .unwrap_or_else(|e| panic!("{:?}\n{}", e, out));
+
+        let mut packages = resolve
+            .packages
+            .iter()
+            .map(|(_, p)| p.name.to_string())
+            .collect::<Vec<_>>();
+        packages.sort();
+        assert_eq!(
+            packages,
+            [
+                "hypercosm:asset-delivery@0.1.0",
+                "hypercosm:core",
+                "hypercosm:execution-context@0.1.0",
+                "hypercosm:world@0.1.0",
+            ]
+        );
+
+        let find = |name: &str| {
+            resolve
+                .types
+                .iter()
+                .find(|(_, t)| {
+                    t.name.as_deref() == Some(name) && matches!(t.owner, TypeOwner::Interface(_))
+                })
+                .map(|(_, t)| &t.kind)
+                .unwrap_or_else(|| panic!("No type `{}`", name))
+        };
+
+        let syms = Symbols::new(&ns);
+        for i in syms.interfaces() {
+            assert!(matches!(
+                find(&i.name.to_kebab_case()),
+                TypeDefKind::Resource
+            ));
+        }
+        for t in syms.types() {
+            let name = t.def.name.to_kebab_case();
+            match (&t.def.kind, find(&name)) {
+                (TypeKind::Struct(s), TypeDefKind::Record(r)) => {
+                    let fields = r.fields.iter().map(|f| &f.name[..]).collect::<Vec<_>>();
+                    let expected = s
+                        .fields
+                        .iter()
+                        .map(|f| f.name.to_kebab_case())
+                        .collect::<Vec<_>>();
+                    assert_eq!(fields, expected);
+                }
+                (TypeKind::Flags(_), TypeDefKind::Flags(f)) => {
+                    let flags = f.flags.iter().map(|f| &f.name[..]).collect::<Vec<_>>();
+                    assert_eq!(flags, ["interactable", "collidable"]);
+                }
+                (_, kind) => panic!("`{}` became {:?}", name, kind),
+            }
+        }
+
+        let expected = r#"    /// Events, for modules to export
+    interface events {
+        use hypercosm:core/types.{uuid};
+        use types.{asset, asset-delivery};
+
+        asset-delivery-load-assets: func(target: borrow<asset-delivery>, assets: list<asset>);
+
+        asset-delivery-unload-assets: func(target: borrow<asset-delivery>, assets: list<uuid>);
+    }
+"#;
+        assert!(out.contains(expected), "{}", out);
+    }
+
+    #[test]
+    fn enums() {
+        let ns = crate::codegen::parse(
+            "namespace test
+
+            enum Seq(u8) { A B C }
+            enum Sparse(vi32) { A = 1 B = 4 }
+            flags Gaps(u16) { None = 0 A = 1 C = 4 All = 5 }",
+        );
+        let mut out = Vec::new();
+        generate(&ns, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains("    enum seq {\n        a,\n        b,\n        c,\n    }\n"));
+        assert!(out.contains(
+            "    enum sparse {
+        /// Wire value: 1
+        a,
+        /// Wire value: 4
+        b,
+    }
+"
+        ));
+        assert!(
+            out.contains("    flags gaps {\n        a,\n        reserved-1,\n        c,\n    }\n")
+        );
+    }
+}
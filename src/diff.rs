@@ -0,0 +1,195 @@
+//! Differences between two versions of a namespace, in terms of what's on
+//! the wire rather than how it's written.
+//!
+//! Interfaces, methods, events, types, and their fields are compared by
+//! name. Docs and the order things are written in are ignored.
+
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    ast::{Func, Namespace, TypeKind, Version},
+    symbols::Symbols,
+};
+
+/// Something that was added, removed, or changed between two versions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// Everything that differs between `old` and `new`, sorted so members come
+/// straight after what they're in.
+///
+/// Both must be HIR.
+pub fn diff(old: &Namespace, new: &Namespace) -> Vec<Change> {
+    let old = items(old);
+    let mut new = items(new);
+
+    let mut changes = Vec::new();
+    for (key, old) in old {
+        match new.remove(&key) {
+            Some(new) if new == old => {}
+            new => changes.push((
+                key,
+                Change {
+                    old: Some(old),
+                    new,
+                },
+            )),
+        }
+    }
+    changes.extend(new.into_iter().map(|(key, new)| {
+        (
+            key,
+            Change {
+                old: None,
+                new: Some(new),
+            },
+        )
+    }));
+
+    changes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    changes.into_iter().map(|(_, change)| change).collect()
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(old) = &self.old {
+            writeln!(f, "- {}", old)?;
+        }
+        if let Some(new) = &self.new {
+            writeln!(f, "+ {}", new)?;
+        }
+        Ok(())
+    }
+}
+
+/// A description of everything in `ns`, keyed by the path to it.
+fn items(ns: &Namespace) -> BTreeMap<String, String> {
+    let syms = Symbols::new(ns);
+    let mut items = BTreeMap::new();
+
+    for i in syms.interfaces() {
+        let qualified = i.qualified_name(&ns.name);
+        items.insert(
+            qualified.clone(),
+            format!("interface `{}` {}", qualified, version(i.version)),
+        );
+        for (kind, funcs) in [("method", i.methods), ("event", i.events)] {
+            for f in funcs {
+                let path = format!("{}.{}", qualified, f.name);
+                // Methods and events can share a name
+                items.insert(
+                    format!("{} {}", path, kind),
+                    format!("{} `{}{}`", kind, path, signature(f)),
+                );
+            }
+        }
+    }
+
+    for t in syms.types() {
+        let name = &t.def.name;
+        let (kind, fields) = match &t.def.kind {
+            TypeKind::Struct(s) => (
+                "struct".to_owned(),
+                s.fields
+                    .iter()
+                    .map(|f| (&f.name, format!("field `{}.{}: {}`", name, f.name, f.ty)))
+                    .collect::<Vec<_>>(),
+            ),
+            TypeKind::Enum(e) => (
+                format!("enum({})", e.backing),
+                e.fields
+                    .iter()
+                    .map(|f| {
+                        let value = f.value.expect("HIR enums have values");
+                        (
+                            &f.name,
+                            format!("variant `{}.{} = {}`", name, f.name, value),
+                        )
+                    })
+                    .collect(),
+            ),
+            TypeKind::Flags(fl) => (
+                format!("flags({})", fl.backing),
+                fl.fields
+                    .iter()
+                    .map(|f| (&f.name, format!("flag `{}.{} = {}`", name, f.name, f.value)))
+                    .collect(),
+            ),
+        };
+
+        items.insert(name.clone(), format!("{} `{}`", kind, name));
+        for (field, description) in fields {
+            items.insert(format!("{}.{}", name, field), description);
+        }
+    }
+
+    items
+}
+
+fn signature(f: &Func) -> String {
+    let args = f
+        .args
+        .iter()
+        .map(|a| format!("{}: {}", a.name, a.ty))
+        .collect::<Vec<_>>()
+        .join(", ");
+    match &f.ret {
+        Some(ret) => format!("({}) -> {}", args, ret),
+        None => format!("({})", args),
+    }
+}
+
+fn version((major, minor, patch): Version) -> String {
+    format!("v{}.{}.{}", major, minor, patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn changes() {
+        let old = crate::codegen::parse(
+            "namespace test
+            interface Foo {
+                version 1 0 0
+                methods { get(a: u8) -> u8 gone() }
+            }
+            enum E(u8) { A B }
+            struct S { a: string }",
+        );
+        let new = crate::codegen::parse(
+            "namespace test
+            /** Docs don't matter */
+            interface Foo {
+                version 1 1 0
+                events { get(a: u8) }
+                methods { get(a: u16) -> u8 }
+            }
+            enum E(u8) { A B C }
+            struct S { a: string }",
+        );
+
+        let changes = diff(&old, &new)
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<String>();
+        assert_eq!(
+            changes,
+            "+ variant `E.C = 2`
+- interface `test.Foo` v1.0.0
++ interface `test.Foo` v1.1.0
++ event `test.Foo.get(a: u8)`
+- method `test.Foo.get(a: u8) -> u8`
++ method `test.Foo.get(a: u16) -> u8`
+- method `test.Foo.gone()`
+"
+        );
+
+        assert_eq!(diff(&new, &new), vec![]);
+    }
+}
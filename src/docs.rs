@@ -1,3 +1,17 @@
+//! Doc comments: stripping the comment syntax, and resolving intra-doc
+//! links like `[Asset]` or `[asset_delivery.fetch_by_id]`.
+//!
+//! A link names an interface or type, or a method, event or field of one,
+//! and is only a link if it's not in code, and isn't already a markdown
+//! link (`[text](url)`, `[text][ref]` or `[ref]: url`).
+
+use std::ops::Range;
+
+use crate::{
+    ast::{DocLink, LinkKind, TypeKind},
+    symbols::{Symbol, Symbols},
+};
+
 pub(crate) fn lower(docs: &str) -> String {
     let docs = docs.trim();
     let mut out = String::with_capacity(docs.len());
@@ -18,6 +32,147 @@ pub(crate) fn lower(docs: &str) -> String {
     out
 }
 
+/// Every intra-doc link in `docs`, as the range of it (including the
+/// brackets) and the text between the brackets.
+pub(crate) fn find_links(docs: &str) -> Vec<(Range<usize>, &str)> {
+    let bytes = docs.as_bytes();
+    let mut links = Vec::new();
+    let mut in_code = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => in_code = !in_code,
+            b'[' if !in_code && (i == 0 || bytes[i - 1] != b']') => {
+                if let Some(len) = docs[i + 1..].find(']') {
+                    let end = i + 1 + len;
+                    let text = &docs[i + 1..end];
+                    let followed = matches!(bytes.get(end + 1), Some(b'(' | b'[' | b':'));
+                    if is_path(text) && !followed {
+                        links.push((i..end + 1, text));
+                        i = end;
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    links
+}
+
+/// `Item` or `Item.member`
+fn is_path(text: &str) -> bool {
+    let parts = text.split('.').collect::<Vec<_>>();
+    parts.len() <= 2
+        && parts.iter().all(|p| {
+            p.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && p.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// Resolve the links in every doc comment in a lowered namespace, returning
+/// them with warnings for the ones that don't resolve.
+pub(crate) fn resolve_links(syms: &Symbols) -> (Vec<DocLink>, Vec<String>) {
+    let mut cx = Links {
+        syms,
+        links: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    for ext in &syms.namespace().extensions {
+        cx.docs(&format!("extensions.{}", ext.name), &ext.docs);
+    }
+    for i in syms.interfaces() {
+        cx.docs(i.name, i.docs);
+        for (kind, funcs) in [("methods", i.methods), ("events", i.events)] {
+            for f in funcs {
+                cx.docs(&format!("{}.{}.{}", i.name, kind, f.name), &f.docs);
+            }
+        }
+    }
+    for t in syms.types() {
+        cx.docs(&t.def.name, &t.def.docs);
+    }
+
+    (cx.links, cx.warnings)
+}
+
+struct Links<'a, 'b> {
+    syms: &'b Symbols<'a>,
+    links: Vec<DocLink>,
+    warnings: Vec<String>,
+}
+
+impl Links<'_, '_> {
+    fn docs(&mut self, source: &str, docs: &str) {
+        for (_, text) in find_links(docs) {
+            let seen = self
+                .links
+                .iter()
+                .any(|l| l.source == source && l.text == text);
+            if seen {
+                continue;
+            }
+            match self.resolve(text) {
+                Ok((kind, target)) => self.links.push(DocLink {
+                    source: source.to_owned(),
+                    text: text.to_owned(),
+                    kind,
+                    target,
+                }),
+                Err(why) => self
+                    .warnings
+                    .push(format!("Broken link `[{}]` in `{}`: {}", text, source, why)),
+            }
+        }
+    }
+
+    fn resolve(&self, text: &str) -> Result<(LinkKind, String), String> {
+        let (item, member) = match text.split_once('.') {
+            Some((item, member)) => (item, Some(member)),
+            None => (text, None),
+        };
+        let symbol = self
+            .syms
+            .get(item)
+            .ok_or_else(|| format!("there's no interface or type `{}`", item))?;
+
+        let Some(member) = member else {
+            return Ok(match symbol {
+                Symbol::Interface(_) => (LinkKind::Interface, item.to_owned()),
+                Symbol::Type(_) => (LinkKind::Type, item.to_owned()),
+            });
+        };
+
+        match symbol {
+            Symbol::Interface(i) => {
+                let has = |funcs: &[crate::ast::Func]| funcs.iter().any(|f| f.name == member);
+                if has(i.methods) {
+                    Ok((LinkKind::Method, format!("{}.methods.{}", item, member)))
+                } else if has(i.events) {
+                    Ok((LinkKind::Event, format!("{}.events.{}", item, member)))
+                } else {
+                    Err(format!("`{}` has no method or event `{}`", item, member))
+                }
+            }
+            Symbol::Type(t) => {
+                let found = match &t.def.kind {
+                    TypeKind::Struct(s) => s.fields.iter().any(|f| f.name == member),
+                    TypeKind::Enum(e) => e.fields.iter().any(|f| f.name == member),
+                    TypeKind::Flags(f) => f.fields.iter().any(|f| f.name == member),
+                };
+                if found {
+                    Ok((LinkKind::Field, text.to_owned()))
+                } else {
+                    Err(format!("`{}` has no field `{}`", item, member))
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -29,4 +184,60 @@ mod tests {
         let lowered = "Remove the object from the object list\n\nFuture attempts to make calls on the object ID **MUST** fail.\n\nThe resources associated with the object may be released, but the\nobject ID may not be reused, we have plenty of them\n\nIt is an error to remove the root singleton (id 0)\n";
         assert_eq!(lower(doc), lowered);
     }
+
+    #[test]
+    fn links() {
+        let texts = |docs| {
+            find_links(docs)
+                .into_iter()
+                .map(|(_, t)| t)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            texts("See [Asset] and [asset_delivery.fetch_by_id], but not `[Code]`"),
+            ["Asset", "asset_delivery.fetch_by_id"]
+        );
+        assert_eq!(
+            texts("[md](https://x.y) [full][md] [md]: url [a b] [a.b.c] [] [x]"),
+            ["x"]
+        );
+
+        let ns = crate::codegen::parse(
+            "namespace test
+            /** Gets an [Asset], see [Root.get] and [Root.changed] */
+            interface Root {
+                events { changed() }
+                methods { get() -> Asset }
+            }
+            /** With an [Asset.id], not an [Asset.size] or a [Blob] */
+            struct Asset { id: u64 }",
+        );
+        let syms = Symbols::new(&ns);
+        let (links, warnings) = resolve_links(&syms);
+        let links = links
+            .iter()
+            .map(|l| (&l.source[..], &l.text[..], l.kind, &l.target[..]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            [
+                ("Root", "Asset", LinkKind::Type, "Asset"),
+                ("Root", "Root.get", LinkKind::Method, "Root.methods.get"),
+                (
+                    "Root",
+                    "Root.changed",
+                    LinkKind::Event,
+                    "Root.events.changed"
+                ),
+                ("Asset", "Asset.id", LinkKind::Field, "Asset.id"),
+            ]
+        );
+        assert_eq!(
+            warnings,
+            [
+                "Broken link `[Asset.size]` in `Asset`: `Asset` has no field `size`",
+                "Broken link `[Blob]` in `Asset`: there's no interface or type `Blob`",
+            ]
+        );
+    }
 }
@@ -0,0 +1,554 @@
+//! Standalone HTML documentation, which (unlike the markdown) doesn't need
+//! `mdbook` to read.
+//!
+//! Pages are laid out like [`Layout::Extension`](super::Layout::Extension),
+//! with the same anchors: `index.html` for the core namespace,
+//! `{extension}.html` for each extension, and `symbols.html` listing
+//! everything. Every page has a sidebar linking to all the others, and a
+//! search box, which `search.js` answers from `search-index.js`. That's a
+//! script rather than JSON so search works from `file://` URLs too.
+//!
+//! Extensions named `index` or `symbols` are an error, as their pages would
+//! clash.
+
+use std::{fmt::Write as _, io::Write as _};
+
+use anyhow::Result;
+use camino::Utf8Path;
+use serde::Serialize;
+
+use pulldown_cmark::Event;
+
+use super::{check_pages, entries, flag_widths, link_docs, uses};
+use crate::{
+    ast::{Extension, Func, Namespace, Type, TypeDef, TypeKind, Version},
+    symbols::{InterfaceRef, Symbol, Symbols},
+    vfs,
+};
+
+const STYLE: &str = include_str!("html/style.css");
+const SEARCH: &str = include_str!("html/search.js");
+
+/// An entry in `search-index.js`.
+#[derive(Serialize)]
+struct SearchEntry<'a> {
+    name: &'a str,
+    kind: &'a str,
+    extension: Option<&'a str>,
+    href: String,
+    /// The first line of the docs, as plain text
+    summary: String,
+}
+
+pub fn generate(tree: &Namespace, out: &Utf8Path, fs: &mut vfs::FS) -> Result<()> {
+    fs.own_dir(out);
+
+    let syms = Symbols::new(tree);
+    let sidebar = sidebar(&syms)?;
+
+    let mut pages = vec![page(None), "symbols.html".to_owned()];
+    pages.extend(tree.extensions.iter().map(|e| page(Some(&e.name))));
+    check_pages(&pages)?;
+
+    let pages = std::iter::once(None).chain(tree.extensions.iter().map(Some));
+    for ext in pages {
+        let cx = Html {
+            syms: &syms,
+            ext: ext.map(|e| &e.name[..]),
+        };
+        let mut body = String::new();
+
+        let title = match ext {
+            None => {
+                writeln!(body, "<h1>Hypercosm Protocol Docs</h1>")?;
+                "Core".to_owned()
+            }
+            Some(ext) => {
+                writeln!(body, "<h1>Extension <code>{}</code></h1>", ext.name)?;
+                version(&mut body, ext.version)?;
+                let docs = cx.docs(&format!("extensions.{}", ext.name), &ext.docs)?;
+                body.push_str(&docs);
+                ext.name.clone()
+            }
+        };
+        cx.interfaces(&mut body)?;
+        cx.types(&mut body)?;
+        if ext.is_none() && !tree.extensions.is_empty() {
+            writeln!(body, "<h2>Extensions</h2>\n<ul>")?;
+            for ext in &tree.extensions {
+                let file = page(Some(&ext.name));
+                writeln!(body, "<li><a href=\"{}\">{}</a></li>", file, ext.name)?;
+            }
+            writeln!(body, "</ul>")?;
+        }
+
+        let w = fs.open(out.join(page(cx.ext)));
+        w.write_all(layout(&title, &sidebar, &body).as_bytes())?;
+    }
+
+    let cx = Html {
+        syms: &syms,
+        ext: None,
+    };
+    let mut body = String::new();
+    let mut search = Vec::new();
+    writeln!(body, "<h1>Index</h1>\n<ul class=\"index\">")?;
+    let entries = entries(&syms);
+    for e in &entries {
+        let href = cx.page_href(e.item, &e.anchor)?;
+        write!(
+            body,
+            "<li><a href=\"{}\"><code>{}</code></a> {}",
+            href, e.name, e.kind
+        )?;
+        match e.extension {
+            Some(ext) => writeln!(body, " in <code>{}</code></li>", ext.name)?,
+            None => writeln!(body, "</li>")?,
+        }
+        search.push(SearchEntry {
+            name: &e.name,
+            kind: e.kind,
+            extension: e.extension.map(|e| &e.name[..]),
+            href,
+            summary: cx.summary(&e.anchor, e.docs)?,
+        });
+    }
+    writeln!(body, "</ul>")?;
+    fs.open(out.join("symbols.html"))
+        .write_all(layout("Index", &sidebar, &body).as_bytes())?;
+
+    let search = serde_json::to_string_pretty(&search)?;
+    fs.open(out.join("search-index.js"))
+        .write_all(format!("window.HIDL_SEARCH_INDEX = {};\n", search).as_bytes())?;
+    fs.open(out.join("style.css")).write_all(STYLE.as_bytes())?;
+    fs.open(out.join("search.js"))
+        .write_all(SEARCH.as_bytes())?;
+
+    Ok(())
+}
+
+/// The page for the core namespace, or an extension.
+fn page(ext: Option<&str>) -> String {
+    match ext {
+        Some(ext) => format!("{}.html", ext),
+        None => "index.html".to_owned(),
+    }
+}
+
+/// A whole page, around `body`.
+fn layout(title: &str, sidebar: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{} - Hypercosm Protocol Docs</title>
+<link rel="stylesheet" href="style.css">
+</head>
+<body>
+{}<main>
+{}</main>
+<script src="search-index.js"></script>
+<script src="search.js"></script>
+</body>
+</html>
+"#,
+        title, sidebar, body
+    )
+}
+
+/// Links to every page, and everything on them, for the side of every page.
+fn sidebar(syms: &Symbols) -> Result<String> {
+    let mut out = String::new();
+    writeln!(out, "<nav class=\"sidebar\">")?;
+    writeln!(
+        out,
+        "<a class=\"home\" href=\"index.html\">Hypercosm Protocol Docs</a>"
+    )?;
+    writeln!(
+        out,
+        "<input id=\"search\" type=\"search\" placeholder=\"Search\" autocomplete=\"off\">"
+    )?;
+    writeln!(out, "<ul id=\"results\"></ul>")?;
+    writeln!(out, "<ul>")?;
+
+    let exts = syms.namespace().extensions.iter().map(Some);
+    for ext in std::iter::once(None).chain(exts) {
+        let file = page(ext.map(|e: &Extension| &e.name[..]));
+        let title = ext.map_or("Core", |e| &e.name);
+        writeln!(out, "<li><a href=\"{}\">{}</a>\n<ul>", file, title)?;
+
+        let on_page = |e: Option<&Extension>| e.map(|e| &e.name) == ext.map(|e| &e.name);
+        let interfaces = syms.interfaces().iter().filter(|i| on_page(i.extension));
+        let types = syms.types().iter().filter(|t| on_page(t.extension));
+        let names = interfaces
+            .map(|i| i.name)
+            .chain(types.map(|t| &t.def.name[..]));
+        for name in names {
+            writeln!(
+                out,
+                "<li><a href=\"{}#{}\"><code>{}</code></a></li>",
+                file, name, name
+            )?;
+        }
+        writeln!(out, "</ul></li>")?;
+    }
+
+    writeln!(out, "<li><a href=\"symbols.html\">Index</a></li>")?;
+    writeln!(out, "</ul>\n</nav>")?;
+    Ok(out)
+}
+
+fn version(out: &mut String, v: Version) -> Result<()> {
+    writeln!(out, "<p class=\"version\">v{}.{}.{}</p>", v.0, v.1, v.2)?;
+    Ok(())
+}
+
+/// A heading with an anchor, which links to itself.
+fn heading(out: &mut String, level: u8, id: &str, text: &str) -> Result<()> {
+    writeln!(
+        out,
+        "<h{level} id=\"{id}\"><a class=\"anchor\" href=\"#{id}\">#</a>{text}</h{level}>"
+    )?;
+    Ok(())
+}
+
+/// Documents the part of a namespace on one page.
+struct Html<'a, 'b> {
+    syms: &'b Symbols<'a>,
+    /// The extension the page is for, or `None` for the core namespace
+    ext: Option<&'b str>,
+}
+
+impl Html<'_, '_> {
+    fn on_page(&self, ext: Option<&Extension>) -> bool {
+        ext.map(|e| &e.name[..]) == self.ext
+    }
+
+    fn interfaces(&self, out: &mut String) -> Result<()> {
+        let interfaces = self.syms.interfaces().iter();
+        let interfaces = interfaces
+            .filter(|i| self.on_page(i.extension))
+            .collect::<Vec<_>>();
+        if !interfaces.is_empty() {
+            writeln!(out, "<h2>Interfaces</h2>")?;
+            for i in interfaces {
+                self.interface(out, i)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn types(&self, out: &mut String) -> Result<()> {
+        let types = self.syms.types().iter();
+        let types = types
+            .filter(|t| self.on_page(t.extension))
+            .collect::<Vec<_>>();
+        if !types.is_empty() {
+            writeln!(out, "<h2>Types</h2>")?;
+            for t in types {
+                self.type_def(out, t.def)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn interface(&self, out: &mut String, i: &InterfaceRef) -> Result<()> {
+        writeln!(out, "<section>")?;
+        let title = format!("Interface <code>{}</code>", i.name);
+        heading(out, 3, i.name, &title)?;
+        // Extension interfaces are versioned with the extension
+        if i.extension.is_none() {
+            version(out, i.version)?;
+        }
+        out.push_str(&self.docs(i.name, i.docs)?);
+
+        for (kind, title, funcs) in [
+            ("events", "Events", i.events),
+            ("methods", "Methods", i.methods),
+        ] {
+            if !funcs.is_empty() {
+                writeln!(out, "<h4>{}</h4>", title)?;
+                for f in funcs {
+                    self.func(out, &format!("{}.{}.{}", i.name, kind, f.name), f)?;
+                }
+            }
+        }
+        self.used_by(out, i.name)?;
+        writeln!(out, "</section>")?;
+
+        Ok(())
+    }
+
+    fn func(&self, out: &mut String, anchor: &str, f: &Func) -> Result<()> {
+        heading(out, 5, anchor, &format!("<code>{}</code>", f.name))?;
+
+        write!(
+            out,
+            "<pre class=\"signature\"><code><span class=\"fn\">{}</span>(",
+            f.name
+        )?;
+        for (n, arg) in f.args.iter().enumerate() {
+            if n != 0 {
+                out.push_str(", ");
+            }
+            write!(out, "<span class=\"arg\">{}</span>: ", arg.name)?;
+            self.ty(out, &arg.ty)?;
+        }
+        out.push(')');
+        if let Some(ret) = &f.ret {
+            out.push_str(" -&gt; ");
+            self.ty(out, ret)?;
+        }
+        writeln!(out, "</code></pre>")?;
+
+        out.push_str(&self.docs(anchor, &f.docs)?);
+        Ok(())
+    }
+
+    fn type_def(&self, out: &mut String, ty: &TypeDef) -> Result<()> {
+        writeln!(out, "<section>")?;
+        let title = match &ty.kind {
+            TypeKind::Struct(_) => format!("Struct <code>{}</code>", ty.name),
+            TypeKind::Enum(e) => {
+                format!("Enum <code>{}</code> (<code>{}</code>)", ty.name, e.backing)
+            }
+            TypeKind::Flags(f) => {
+                format!(
+                    "Flags <code>{}</code> (<code>{}</code>)",
+                    ty.name, f.backing
+                )
+            }
+        };
+        heading(out, 3, &ty.name, &title)?;
+        out.push_str(&self.docs(&ty.name, &ty.docs)?);
+
+        match &ty.kind {
+            TypeKind::Struct(s) => {
+                writeln!(out, "<ul class=\"fields\">")?;
+                for field in &s.fields {
+                    write!(out, "<li><code><span class=\"arg\">{}</span>: ", field.name)?;
+                    self.ty(out, &field.ty)?;
+                    writeln!(out, "</code></li>")?;
+                }
+                writeln!(out, "</ul>")?;
+            }
+            TypeKind::Enum(e) => {
+                writeln!(out, "<table>\n<tr><th>Name</th><th>Value</th></tr>")?;
+                for field in &e.fields {
+                    // Always set in HIR
+                    let value = field.value.unwrap();
+                    writeln!(
+                        out,
+                        "<tr><td>{}</td><td><code>{}</code></td></tr>",
+                        field.name, value
+                    )?;
+                }
+                writeln!(out, "</table>")?;
+            }
+            TypeKind::Flags(f) => {
+                let (width, hex_width) = flag_widths(f);
+                writeln!(
+                    out,
+                    "<table>\n<tr><th>Name</th><th>Value</th><th>Hex</th></tr>"
+                )?;
+                for field in &f.fields {
+                    writeln!(
+                        out,
+                        "<tr><td>{}</td><td><code>{:#0width$b}</code></td><td><code>{:#0hex_width$x}</code></td></tr>",
+                        field.name, field.value, field.value
+                    )?;
+                }
+                writeln!(out, "</table>")?;
+            }
+        }
+        self.used_by(out, &ty.name)?;
+        writeln!(out, "</section>")?;
+
+        Ok(())
+    }
+
+    fn used_by(&self, out: &mut String, name: &str) -> Result<()> {
+        let uses = uses(self.syms, name);
+        if uses.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(out, "<h4>Used by</h4>\n<ul>")?;
+        for u in uses {
+            write!(
+                out,
+                "<li>{} <a href=\"{}\"><code>{}.{}</code></a>",
+                u.kind,
+                self.href(u.item, &u.anchor)?,
+                u.item,
+                u.member
+            )?;
+            if u.in_dictionary {
+                out.push_str(", in a dictionary");
+            }
+            writeln!(out, "</li>")?;
+        }
+        writeln!(out, "</ul>")?;
+
+        Ok(())
+    }
+
+    /// Write `ty` highlighted, with custom types linked to their
+    /// definitions.
+    fn ty(&self, out: &mut String, ty: &Type) -> Result<()> {
+        match ty {
+            Type::Primitive(_) | Type::IntType(_) => {
+                write!(out, "<span class=\"prim\">{}</span>", ty)?
+            }
+            Type::Array(inner) => {
+                out.push_str("[]");
+                self.ty(out, inner)?;
+            }
+            Type::Dictionary(d) => {
+                out.push('[');
+                self.ty(out, &d.key)?;
+                out.push(']');
+                self.ty(out, &d.value)?;
+            }
+            Type::Custom(name) => write!(
+                out,
+                "<a class=\"ty\" href=\"{}\">{}</a>",
+                self.href(name, name)?,
+                name
+            )?,
+        }
+        Ok(())
+    }
+
+    /// `docs` from `source` as HTML.
+    fn docs(&self, source: &str, docs: &str) -> Result<String> {
+        let docs = link_docs(self.syms, source, docs, |item, anchor| {
+            self.href(item, anchor)
+        })?;
+        let mut out = String::new();
+        pulldown_cmark::html::push_html(&mut out, pulldown_cmark::Parser::new(&docs));
+        Ok(out)
+    }
+
+    /// The first line of `docs` from `source`, without any markdown, for
+    /// the search results.
+    fn summary(&self, source: &str, docs: &str) -> Result<String> {
+        let line = docs.lines().next().unwrap_or_default();
+        let line = link_docs(self.syms, source, line, |item, anchor| {
+            self.href(item, anchor)
+        })?;
+        let mut out = String::new();
+        for event in pulldown_cmark::Parser::new(&line) {
+            if let Event::Text(text) | Event::Code(text) = event {
+                out.push_str(&text);
+            }
+        }
+        Ok(out)
+    }
+
+    /// A link to `anchor`, on the page of the interface or type `item`.
+    fn href(&self, item: &str, anchor: &str) -> Result<String> {
+        let ext = self.extension_of(item)?;
+        if self.on_page(ext) {
+            Ok(format!("#{}", anchor))
+        } else {
+            self.page_href(item, anchor)
+        }
+    }
+
+    /// Like [`Html::href`], but always with the page, for links from other
+    /// pages.
+    fn page_href(&self, item: &str, anchor: &str) -> Result<String> {
+        let ext = self.extension_of(item)?;
+        Ok(format!("{}#{}", page(ext.map(|e| &e.name[..])), anchor))
+    }
+
+    fn extension_of(&self, item: &str) -> Result<Option<&Extension>> {
+        Ok(match self.syms.resolve(item)? {
+            Symbol::Interface(i) => i.extension,
+            Symbol::Type(t) => t.extension,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::Memory;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn site() {
+        let ns = crate::codegen::parse(
+            "namespace test
+            /** Holds a [Thing], *not* a `u8` */
+            struct Box { thing: Thing }
+            extension ext {
+                version 0 1 0
+                interface { methods { get(id: u64) -> [string]Box } }
+                struct Thing { a: u8 }
+            }",
+        );
+        let mut fs = vfs::FS::new();
+        generate(&ns, "html".into(), &mut fs).unwrap();
+        let mut memory = Memory::new();
+        fs.save_to(&mut memory).unwrap();
+
+        assert_eq!(
+            memory.files().map(|(p, _)| p.as_str()).collect::<Vec<_>>(),
+            [
+                "html/ext.html",
+                "html/index.html",
+                "html/search-index.js",
+                "html/search.js",
+                "html/style.css",
+                "html/symbols.html",
+            ]
+        );
+
+        let core = memory.read_to_string("html/index.html").unwrap();
+        assert!(core.contains(
+            "<p>Holds a <a href=\"ext.html#Thing\">Thing</a>, <em>not</em> a <code>u8</code></p>"
+        ));
+        assert!(core.contains("<li><a href=\"ext.html\">ext</a>\n<ul>\n<li><a href=\"ext.html#ext\"><code>ext</code></a></li>"));
+        let ext = memory.read_to_string("html/ext.html").unwrap();
+        assert!(ext.contains(
+            "<pre class=\"signature\"><code><span class=\"fn\">get</span>(<span class=\"arg\">id</span>: <span class=\"prim\">u64</span>) -&gt; [<span class=\"prim\">string</span>]<a class=\"ty\" href=\"index.html#Box\">Box</a></code></pre>"
+        ));
+
+        let search = memory.read_to_string("html/search-index.js").unwrap();
+        let search = search
+            .strip_prefix("window.HIDL_SEARCH_INDEX = ")
+            .and_then(|s| s.strip_suffix(";\n"))
+            .unwrap();
+        let search: serde_json::Value = serde_json::from_str(search).unwrap();
+        assert_eq!(
+            search[0],
+            serde_json::json!({
+                "name": "Box",
+                "kind": "struct",
+                "extension": null,
+                "href": "index.html#Box",
+                "summary": "Holds a Thing, not a u8",
+            })
+        );
+    }
+
+    #[test]
+    fn clashes() {
+        for name in ["index", "symbols", "Index"] {
+            let ns = crate::codegen::parse(&format!(
+                "namespace test extension {} {{ version 0 1 0 }}",
+                name
+            ));
+            let err = generate(&ns, "html".into(), &mut vfs::FS::new()).unwrap_err();
+            assert!(
+                err.to_string().contains("would be the same page"),
+                "{}",
+                err
+            );
+        }
+    }
+}
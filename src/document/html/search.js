@@ -0,0 +1,55 @@
+// Searches `HIDL_SEARCH_INDEX`, from `search-index.js`, as you type, showing
+// matches in the sidebar.
+
+(function () {
+  const input = document.getElementById("search");
+  const results = document.getElementById("results");
+  const index = window.HIDL_SEARCH_INDEX || [];
+
+  function message(text) {
+    results.replaceChildren();
+    const li = document.createElement("li");
+    li.textContent = text;
+    results.append(li);
+  }
+
+  function search() {
+    const query = input.value.trim().toLowerCase();
+    if (query === "") {
+      results.replaceChildren();
+      return;
+    }
+
+    const matches = index
+      .filter((e) => e.name.toLowerCase().includes(query))
+      // Names starting with the query first, then shortest
+      .sort((a, b) => {
+        const aStarts = a.name.toLowerCase().startsWith(query);
+        const bStarts = b.name.toLowerCase().startsWith(query);
+        return bStarts - aStarts || a.name.length - b.name.length;
+      })
+      .slice(0, 20);
+
+    if (matches.length === 0) {
+      message("No results");
+      return;
+    }
+    results.replaceChildren(
+      ...matches.map((e) => {
+        const li = document.createElement("li");
+        const a = document.createElement("a");
+        a.href = e.href;
+        const code = document.createElement("code");
+        code.textContent = e.name;
+        a.append(code);
+        li.append(a, " " + e.kind);
+        if (e.summary) {
+          li.title = e.summary;
+        }
+        return li;
+      })
+    );
+  }
+
+  input.addEventListener("input", search);
+})();
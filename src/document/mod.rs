@@ -0,0 +1,987 @@
+//! Markdown documentation for a namespace, laid out for `mdbook`.
+//!
+//! Writes `README.md` for the core namespace, a page per extension, and a
+//! `SUMMARY.md` linking them together. With [`Layout::Item`], every
+//! interface and type gets its own page too. Everything else in the output
+//! directory is removed.
+//!
+//! Every interface, method, event and type has an anchor, which is stable
+//! as long as its name is: `{Interface}`, `{Interface}.methods.{method}`,
+//! `{Interface}.events.{event}`, and `{Type}`. Custom types in signatures
+//! and fields link to their definition, on whichever page it's on, as do
+//! intra-doc links like `[Asset]` (see [`crate::docs`]).
+//!
+//! Interfaces and types also list where they're used, and `symbols.md`
+//! lists everything with an anchor.
+//!
+//! Extensions (and with [`Layout::Item`], interfaces and types) whose page
+//! would clash with another are an error. `mdbook` renders `README.md` as
+//! `index.html`, so `index` clashes with `README`, and `SUMMARY` and
+//! `symbols` are taken too.
+//!
+//! For docs that don't need `mdbook`, see [`html`].
+
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+use anyhow::{bail, Result};
+use camino::Utf8Path;
+use heck::ToTitleCase;
+
+pub mod html;
+
+use crate::{
+    ast::{Extension, Flags, Func, LinkKind, Namespace, Type, TypeDef, TypeKind, Version},
+    docs,
+    symbols::{InterfaceRef, Symbol, Symbols},
+    vfs,
+};
+
+/// How the docs are split into pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// `README.md` for the core namespace, and `{extension}.md` for each
+    /// extension
+    #[default]
+    Extension,
+    /// `README.md` and `{Item}.md` for the core namespace, and
+    /// `{extension}/README.md` and `{extension}/{Item}.md` for each
+    /// extension
+    Item,
+}
+
+pub fn generate(tree: &Namespace, out: &Utf8Path, layout: Layout, fs: &mut vfs::FS) -> Result<()> {
+    fs.own_dir(out);
+
+    let syms = Symbols::new(tree);
+    let cx = Docs::new(&syms, layout, None);
+    check_pages(&cx.pages(tree))?;
+    let w = &mut fs.handle(out.join("README.md"));
+    let summary = &mut fs.handle(out.join("SUMMARY.md"));
+
+    writeln!(w, "# Hypercosm Protocol Docs")?;
+
+    match layout {
+        Layout::Extension => {
+            cx.interfaces(w)?;
+            cx.types(w)?;
+            writeln!(summary, "- [Core](README.md)")?;
+        }
+        Layout::Item => {
+            writeln!(summary, "- [{}](README.md)", tree.name.to_title_case())?;
+            cx.item_pages(w, summary, out, fs)?;
+        }
+    }
+
+    if !tree.extensions.is_empty() {
+        writeln!(w, "## Extensions")?;
+        for ext in &tree.extensions {
+            let cx = Docs::new(&syms, layout, Some(&ext.name));
+            writeln!(w, "- [{}]({})", ext.name, cx.file)?;
+            match layout {
+                Layout::Extension => {
+                    writeln!(summary, "- [{}]({})", ext.name.to_title_case(), cx.file)?
+                }
+                Layout::Item => {
+                    writeln!(summary, "  - [{}]({})", ext.name.to_title_case(), cx.file)?
+                }
+            }
+            cx.extension(ext, summary, out, fs)?;
+        }
+    }
+
+    let cx = Docs {
+        file: SYMBOLS.to_owned(),
+        ..Docs::new(&syms, layout, None)
+    };
+    cx.index(&mut fs.handle(out.join(&cx.file)))?;
+    writeln!(w, "\nSee also the [index]({}) of everything.", SYMBOLS)?;
+    writeln!(summary, "- [Index]({})", SYMBOLS)?;
+
+    Ok(())
+}
+
+/// The page listing everything, from [`Docs::index`].
+const SYMBOLS: &str = "symbols.md";
+
+/// Fail if any of `pages` would be written to the same file, or rendered to
+/// the same HTML page by `mdbook`. Names are compared ignoring case, for
+/// case-insensitive filesystems.
+fn check_pages(pages: &[String]) -> Result<()> {
+    let rendered = |page: &str| {
+        let page = page.to_lowercase();
+        match page.strip_suffix("readme.md") {
+            Some(dir) if dir.is_empty() || dir.ends_with('/') => format!("{}index.md", dir),
+            _ => page,
+        }
+    };
+    for (n, a) in pages.iter().enumerate() {
+        if let Some(b) = pages[..n].iter().find(|b| rendered(b) == rendered(a)) {
+            bail!(
+                "`{}` and `{}` would be the same page, so an extension, interface or type \
+                 needs renaming",
+                b,
+                a
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The page for the core namespace, or an extension.
+fn page(layout: Layout, ext: Option<&str>) -> String {
+    match (ext, layout) {
+        (Some(ext), Layout::Extension) => format!("{}.md", ext),
+        (Some(ext), Layout::Item) => format!("{}/README.md", ext),
+        (None, _) => "README.md".to_owned(),
+    }
+}
+
+/// `to` relative to the directory of `from`, where both are relative to the
+/// output directory, and at most one directory deep.
+fn relative(from: &str, to: &str) -> String {
+    if from == to {
+        return String::new();
+    }
+    match from.rsplit_once('/') {
+        Some((dir, _)) => match to.strip_prefix(dir).and_then(|to| to.strip_prefix('/')) {
+            Some(to) => to.to_owned(),
+            None => format!("../{}", to),
+        },
+        None => to.to_owned(),
+    }
+}
+
+/// Documents the part of a namespace on one page.
+struct Docs<'a, 'b> {
+    syms: &'b Symbols<'a>,
+    layout: Layout,
+    /// The extension the page is for, or `None` for the core namespace
+    ext: Option<&'b str>,
+    /// The page, relative to the output directory
+    file: String,
+}
+
+impl<'a, 'b> Docs<'a, 'b> {
+    fn new(syms: &'b Symbols<'a>, layout: Layout, ext: Option<&'b str>) -> Self {
+        Self {
+            syms,
+            layout,
+            ext,
+            file: page(layout, ext),
+        }
+    }
+
+    fn on_page(&self, ext: Option<&Extension>) -> bool {
+        ext.map(|e| &e.name[..]) == self.ext
+    }
+
+    /// The page documenting `item` (an interface or type) from `ext`.
+    fn item_file(&self, ext: Option<&Extension>, item: &str) -> String {
+        match (ext, self.layout) {
+            (_, Layout::Extension) => page(self.layout, ext.map(|e| &e.name[..])),
+            (Some(ext), Layout::Item) => format!("{}/{}.md", ext.name, item),
+            (None, Layout::Item) => format!("{}.md", item),
+        }
+    }
+
+    /// Every page [`generate`] writes, to check they don't clash.
+    fn pages(&self, tree: &Namespace) -> Vec<String> {
+        let mut pages = vec!["README.md".to_owned(), "SUMMARY.md".to_owned()];
+        pages.push(SYMBOLS.to_owned());
+        for ext in &tree.extensions {
+            pages.push(page(self.layout, Some(&ext.name)));
+        }
+        if self.layout == Layout::Item {
+            for i in self.syms.interfaces() {
+                pages.push(self.item_file(i.extension, i.name));
+            }
+            for t in self.syms.types() {
+                pages.push(self.item_file(t.extension, &t.def.name));
+            }
+        }
+        pages
+    }
+
+    /// Write the index page, of all the [`entries`].
+    fn index(&self, w: &mut dyn Write) -> Result<()> {
+        writeln!(w, "# Index")?;
+        for e in entries(self.syms) {
+            let href = self.href(e.item, &e.anchor)?;
+            write!(w, "- [`{}`]({}) {}", e.name, href, e.kind)?;
+            match e.extension {
+                Some(ext) => writeln!(w, " in `{}`", ext.name)?,
+                None => writeln!(w)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn extension(
+        &self,
+        ext: &Extension,
+        summary: &mut dyn Write,
+        out: &Utf8Path,
+        fs: &mut vfs::FS,
+    ) -> Result<()> {
+        let w = &mut fs.handle(out.join(&self.file));
+
+        writeln!(w, "# Extension `{}`", ext.name)?;
+        write_version(w, ext.version)?;
+        writeln!(
+            w,
+            "{}",
+            self.docs(&format!("extensions.{}", ext.name), &ext.docs)?
+        )?;
+
+        match self.layout {
+            Layout::Extension => {
+                self.interfaces(w)?;
+                self.types(w)?;
+            }
+            Layout::Item => self.item_pages(w, summary, out, fs)?,
+        }
+        Ok(())
+    }
+
+    /// Write a page for each interface and type on this page, which lists
+    /// them in `w`, and nests them under it in `summary`.
+    fn item_pages(
+        &self,
+        w: &mut dyn Write,
+        summary: &mut dyn Write,
+        out: &Utf8Path,
+        fs: &mut vfs::FS,
+    ) -> Result<()> {
+        // Extensions are nested under the namespace
+        let indent = if self.ext.is_some() { "    " } else { "  " };
+        let back = match self.ext {
+            Some(ext) => format!("Extension `{}`", ext),
+            None => "Core".to_owned(),
+        };
+
+        let mut page = |w: &mut dyn Write, ext, name: &str| -> Result<Docs<'a, 'b>> {
+            let file = self.item_file(ext, name);
+            writeln!(w, "- [`{}`]({})", name, relative(&self.file, &file))?;
+            writeln!(summary, "{}- [`{}`]({})", indent, name, file)?;
+            Ok(Docs { file, ..*self })
+        };
+
+        let interfaces = self.syms.interfaces().iter();
+        let interfaces = interfaces
+            .filter(|i| self.on_page(i.extension))
+            .collect::<Vec<_>>();
+        if !interfaces.is_empty() {
+            writeln!(w, "## Interfaces")?;
+        }
+        for i in interfaces {
+            let cx = page(w, i.extension, i.name)?;
+            let w = &mut fs.handle(out.join(&cx.file));
+            writeln!(w, "[← {}]({})\n", back, relative(&cx.file, &self.file))?;
+            cx.interface(w, i)?;
+        }
+
+        let types = self.syms.types().iter();
+        let types = types
+            .filter(|t| self.on_page(t.extension))
+            .collect::<Vec<_>>();
+        if !types.is_empty() {
+            writeln!(w, "## Types")?;
+        }
+        for t in types {
+            let cx = page(w, t.extension, &t.def.name)?;
+            let w = &mut fs.handle(out.join(&cx.file));
+            writeln!(w, "[← {}]({})\n", back, relative(&cx.file, &self.file))?;
+            cx.type_def(w, t.def)?;
+        }
+
+        Ok(())
+    }
+
+    fn interfaces(&self, w: &mut dyn Write) -> Result<()> {
+        let interfaces = self.syms.interfaces().iter();
+        let interfaces = interfaces
+            .filter(|i| self.on_page(i.extension))
+            .collect::<Vec<_>>();
+        if !interfaces.is_empty() {
+            writeln!(w, "## Interfaces")?;
+            for i in interfaces {
+                self.interface(w, i)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn types(&self, w: &mut dyn Write) -> Result<()> {
+        let types = self.syms.types().iter();
+        let types = types
+            .filter(|t| self.on_page(t.extension))
+            .collect::<Vec<_>>();
+        if !types.is_empty() {
+            writeln!(w, "## Types")?;
+            for t in types {
+                self.type_def(w, t.def)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn interface(&self, w: &mut dyn Write, i: &InterfaceRef) -> Result<()> {
+        writeln!(w, "<a id=\"{}\"></a>", i.name)?;
+        writeln!(w, "### Interface `{}`", i.name)?;
+        // Extension interfaces are versioned with the extension
+        if i.extension.is_none() {
+            write_version(w, i.version)?;
+        }
+        writeln!(w, "{}", self.docs(i.name, i.docs)?)?;
+
+        for (kind, heading, funcs) in [
+            ("events", "Events", i.events),
+            ("methods", "Methods", i.methods),
+        ] {
+            if !funcs.is_empty() {
+                writeln!(w, "#### {}", heading)?;
+                for f in funcs {
+                    self.func(w, &format!("{}.{}.{}", i.name, kind, f.name), f)?;
+                }
+            }
+        }
+        self.used_by(w, i.name)?;
+
+        Ok(())
+    }
+
+    fn func(&self, w: &mut dyn Write, anchor: &str, f: &Func) -> Result<()> {
+        let mut sig = Code::default();
+        sig.code(&format!("{}(", f.name));
+        for (n, arg) in f.args.iter().enumerate() {
+            if n != 0 {
+                sig.code(", ");
+            }
+            sig.code(&format!("{}: ", arg.name));
+            self.ty(&mut sig, &arg.ty)?;
+        }
+        sig.code(")");
+        if let Some(ret) = &f.ret {
+            sig.code(" -> ");
+            self.ty(&mut sig, ret)?;
+        }
+
+        writeln!(w, "<a id=\"{}\"></a>", anchor)?;
+        writeln!(w, "##### `{}`", f.name)?;
+        writeln!(w, "{}\n", sig.finish())?;
+        if !f.docs.is_empty() {
+            writeln!(w, "{}", self.docs(anchor, &f.docs)?)?;
+        }
+
+        Ok(())
+    }
+
+    fn type_def(&self, w: &mut dyn Write, ty: &TypeDef) -> Result<()> {
+        writeln!(w, "<a id=\"{}\"></a>", ty.name)?;
+        match &ty.kind {
+            TypeKind::Struct(_) => writeln!(w, "### Struct `{}`", ty.name)?,
+            // The backing type is how it's encoded, so it's part of the heading
+            TypeKind::Enum(e) => writeln!(w, "### Enum `{}` (`{}`)", ty.name, e.backing)?,
+            TypeKind::Flags(f) => writeln!(w, "### Flags `{}` (`{}`)", ty.name, f.backing)?,
+        }
+
+        writeln!(w, "{}", self.docs(&ty.name, &ty.docs)?)?;
+
+        match &ty.kind {
+            TypeKind::Struct(s) => {
+                for field in &s.fields {
+                    let mut ty = Code::default();
+                    self.ty(&mut ty, &field.ty)?;
+                    writeln!(w, "- `{}`: {}", field.name, ty.finish())?;
+                }
+            }
+            TypeKind::Enum(e) => {
+                writeln!(w, "| Name | Value |")?;
+                writeln!(w, "|------|------:|")?;
+
+                for field in &e.fields {
+                    // Always set in HIR
+                    let value = field.value.unwrap();
+                    writeln!(w, "| {} | `{}` |", field.name, value)?;
+                }
+            }
+            TypeKind::Flags(f) => {
+                let (width, hex_width) = flag_widths(f);
+
+                writeln!(w, "| Name | Value | Hex |")?;
+                writeln!(w, "|------|------:|----:|")?;
+
+                for field in &f.fields {
+                    writeln!(
+                        w,
+                        "| {} | `{:#0width$b}` | `{:#0hex_width$x}` |",
+                        field.name, field.value, field.value
+                    )?;
+                }
+            }
+        }
+        // So the next anchor isn't part of the list or table
+        writeln!(w)?;
+        self.used_by(w, &ty.name)?;
+
+        Ok(())
+    }
+
+    /// List the methods, events and fields that use the interface or type
+    /// `name`.
+    fn used_by(&self, w: &mut dyn Write, name: &str) -> Result<()> {
+        let uses = uses(self.syms, name);
+        if uses.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(w, "#### Used by")?;
+        for u in uses {
+            write!(
+                w,
+                "- {} [`{}.{}`]({})",
+                u.kind,
+                u.item,
+                u.member,
+                self.href(u.item, &u.anchor)?
+            )?;
+            if u.in_dictionary {
+                write!(w, ", in a dictionary")?;
+            }
+            writeln!(w)?;
+        }
+        writeln!(w)?;
+
+        Ok(())
+    }
+
+    /// Write `ty`, with custom types linked to their definitions.
+    fn ty(&self, out: &mut Code, ty: &Type) -> Result<()> {
+        match ty {
+            Type::Primitive(_) | Type::IntType(_) => out.code(&ty.to_string()),
+            Type::Array(inner) => {
+                out.code("[]");
+                self.ty(out, inner)?;
+            }
+            Type::Dictionary(d) => {
+                out.code("[");
+                self.ty(out, &d.key)?;
+                out.code("]");
+                self.ty(out, &d.value)?;
+            }
+            Type::Custom(name) => out.link(name, &self.href(name, name)?),
+        }
+        Ok(())
+    }
+
+    /// `docs` from `source`, with its intra-doc links as markdown links.
+    fn docs(&self, source: &str, docs: &str) -> Result<String> {
+        link_docs(self.syms, source, docs, |item, anchor| {
+            self.href(item, anchor)
+        })
+    }
+
+    /// A link to `anchor`, on the page of the interface or type `item`.
+    fn href(&self, item: &str, anchor: &str) -> Result<String> {
+        let ext = match self.syms.resolve(item)? {
+            Symbol::Interface(i) => i.extension,
+            Symbol::Type(t) => t.extension,
+        };
+        let file = self.item_file(ext, item);
+        Ok(format!("{}#{}", relative(&self.file, &file), anchor))
+    }
+}
+
+/// `docs` from `source`, with its intra-doc links as markdown links to
+/// `href(item, anchor)`, where `item` is the interface or type whose page
+/// `anchor` is on.
+fn link_docs(
+    syms: &Symbols,
+    source: &str,
+    docs: &str,
+    href: impl Fn(&str, &str) -> Result<String>,
+) -> Result<String> {
+    let links = &syms.namespace().doc_links;
+    let mut out = String::with_capacity(docs.len());
+    let mut last = 0;
+
+    for (range, text) in docs::find_links(docs) {
+        let Some(link) = links.iter().find(|l| l.source == source && l.text == text) else {
+            continue;
+        };
+        let href = match link.kind {
+            LinkKind::Interface | LinkKind::Type => href(&link.target, &link.target)?,
+            LinkKind::Method | LinkKind::Event | LinkKind::Field => {
+                let (item, _) = link.target.split_once('.').unwrap();
+                // Fields don't have their own anchors
+                let anchor = if link.kind == LinkKind::Field {
+                    item
+                } else {
+                    &link.target
+                };
+                href(item, anchor)?
+            }
+        };
+        out.push_str(&docs[last..range.start]);
+        write!(out, "[{}]({})", text, href)?;
+        last = range.end;
+    }
+    out.push_str(&docs[last..]);
+
+    Ok(out)
+}
+
+/// Something with an anchor, for the index.
+struct Entry<'a> {
+    /// `Item`, or `Interface.func`
+    name: String,
+    /// `interface`, `method`, `event`, `struct`, `enum` or `flags`
+    kind: &'static str,
+    extension: Option<&'a Extension>,
+    /// The interface or type whose page it's on
+    item: &'a str,
+    anchor: String,
+    docs: &'a str,
+}
+
+/// Every interface, method, event and type, in alphabetical order.
+fn entries<'a>(syms: &Symbols<'a>) -> Vec<Entry<'a>> {
+    let mut entries = Vec::new();
+    for i in syms.interfaces() {
+        entries.push(Entry {
+            name: i.name.to_owned(),
+            kind: "interface",
+            extension: i.extension,
+            item: i.name,
+            anchor: i.name.to_owned(),
+            docs: i.docs,
+        });
+        for (kind, anchor, funcs) in [
+            ("event", "events", i.events),
+            ("method", "methods", i.methods),
+        ] {
+            for f in funcs {
+                entries.push(Entry {
+                    name: format!("{}.{}", i.name, f.name),
+                    kind,
+                    extension: i.extension,
+                    item: i.name,
+                    anchor: format!("{}.{}.{}", i.name, anchor, f.name),
+                    docs: &f.docs,
+                });
+            }
+        }
+    }
+    for t in syms.types() {
+        entries.push(Entry {
+            name: t.def.name.clone(),
+            kind: match t.def.kind {
+                TypeKind::Struct(_) => "struct",
+                TypeKind::Enum(_) => "enum",
+                TypeKind::Flags(_) => "flags",
+            },
+            extension: t.extension,
+            item: &t.def.name,
+            anchor: t.def.name.clone(),
+            docs: &t.def.docs,
+        });
+    }
+    entries.sort_by_key(|e| e.name.to_lowercase());
+    entries
+}
+
+/// How wide to print the binary and hex values of `flags`, including the
+/// `0b` or `0x`, so they line up.
+fn flag_widths(flags: &Flags) -> (usize, usize) {
+    let min_leading_zeros = flags
+        .fields
+        .iter()
+        .map(|f| f.value.leading_zeros())
+        .min()
+        .unwrap();
+
+    // 64bits because f.value is i64, +2 because we need space for 0b
+    let width = 66usize
+        .checked_sub(min_leading_zeros.try_into().unwrap())
+        .unwrap();
+    // A hex digit for every 4 bits, rounded up, and 0x
+    let hex_width = (width - 2).div_ceil(4) + 2;
+
+    (width, hex_width)
+}
+
+/// Somewhere an interface or type is used.
+struct Use<'a> {
+    /// `method`, `event` or `field`
+    kind: &'static str,
+    /// The interface or type it's in
+    item: &'a str,
+    member: &'a str,
+    anchor: String,
+    /// Only as a dictionary key or value
+    in_dictionary: bool,
+}
+
+/// Every use of the interface or type `name`, in the order they're
+/// documented.
+fn uses<'a>(syms: &Symbols<'a>, name: &str) -> Vec<Use<'a>> {
+    let mut uses = Vec::new();
+
+    for i in syms.interfaces() {
+        for (kind, anchor, funcs) in [
+            ("event", "events", i.events),
+            ("method", "methods", i.methods),
+        ] {
+            for f in funcs {
+                let tys = f.args.iter().map(|a| &a.ty).chain(&f.ret);
+                if let Some(in_dictionary) = find(tys, name) {
+                    uses.push(Use {
+                        kind,
+                        item: i.name,
+                        member: &f.name,
+                        anchor: format!("{}.{}.{}", i.name, anchor, f.name),
+                        in_dictionary,
+                    });
+                }
+            }
+        }
+    }
+    for t in syms.types() {
+        if let TypeKind::Struct(s) = &t.def.kind {
+            for field in &s.fields {
+                if let Some(in_dictionary) = find([&field.ty], name) {
+                    uses.push(Use {
+                        kind: "field",
+                        item: &t.def.name,
+                        member: &field.name,
+                        anchor: t.def.name.clone(),
+                        in_dictionary,
+                    });
+                }
+            }
+        }
+    }
+
+    uses
+}
+
+/// Whether any of `tys` use `name`, and if so, if it's only in dictionaries.
+fn find<'t>(tys: impl IntoIterator<Item = &'t Type>, name: &str) -> Option<bool> {
+    fn walk(ty: &Type, name: &str, in_dictionary: bool, found: &mut Vec<bool>) {
+        match ty {
+            Type::Primitive(_) | Type::IntType(_) => {}
+            Type::Custom(n) => {
+                if n == name {
+                    found.push(in_dictionary);
+                }
+            }
+            Type::Array(inner) => walk(inner, name, in_dictionary, found),
+            Type::Dictionary(d) => {
+                walk(&d.key, name, true, found);
+                walk(&d.value, name, true, found);
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    for ty in tys {
+        walk(ty, name, false, &mut found);
+    }
+    if found.is_empty() {
+        None
+    } else {
+        Some(found.iter().all(|&d| d))
+    }
+}
+
+/// Markdown for code with links in it, as links can't go in code spans.
+#[derive(Default)]
+struct Code {
+    out: String,
+    /// Code that hasn't been put in a span yet
+    pending: String,
+}
+
+impl Code {
+    fn code(&mut self, code: &str) {
+        self.pending.push_str(code);
+    }
+
+    fn link(&mut self, code: &str, href: &str) {
+        self.flush();
+        write!(self.out, "[`{}`]({})", code, href).unwrap();
+    }
+
+    fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            write!(self.out, "`{}`", self.pending).unwrap();
+            self.pending.clear();
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        self.out
+    }
+}
+
+fn write_version(w: &mut dyn Write, v: Version) -> io::Result<()> {
+    writeln!(w, "*v{}.{}.{}*\n", v.0, v.1, v.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::Memory;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn links() {
+        let ns = crate::codegen::parse(
+            "namespace test
+            interface Core { methods { get(a: [Key]Val) -> []Ext } }
+            struct Key { a: u8 }
+            enum Val(u8) { A }
+            extension ext {
+                version 0 1 0
+                interface { events { changed(to: Ext, at: []u64) } }
+                struct Ext { key: Key next: Ext }
+            }",
+        );
+        let syms = Symbols::new(&ns);
+        let w = &mut Vec::new();
+
+        let core = Docs::new(&syms, Layout::Extension, None);
+        core.interfaces(w).unwrap();
+        let ext = Docs::new(&syms, Layout::Extension, Some("ext"));
+        ext.interfaces(w).unwrap();
+        ext.types(w).unwrap();
+
+        assert_eq!(
+            String::from_utf8(w.clone()).unwrap(),
+            r#"## Interfaces
+<a id="Core"></a>
+### Interface `Core`
+*v0.0.0*
+
+
+#### Methods
+<a id="Core.methods.get"></a>
+##### `get`
+`get(a: [`[`Key`](#Key)`]`[`Val`](#Val)`) -> []`[`Ext`](ext.md#Ext)
+
+## Interfaces
+<a id="ext"></a>
+### Interface `ext`
+
+#### Events
+<a id="ext.events.changed"></a>
+##### `changed`
+`changed(to: `[`Ext`](#Ext)`, at: []u64)`
+
+## Types
+<a id="Ext"></a>
+### Struct `Ext`
+
+- `key`: [`Key`](README.md#Key)
+- `next`: [`Ext`](#Ext)
+
+#### Used by
+- method [`Core.get`](README.md#Core.methods.get)
+- event [`ext.changed`](#ext.events.changed)
+- field [`Ext.next`](#Ext)
+
+"#
+        );
+
+        let key = uses(&syms, "Key")
+            .iter()
+            .map(|u| (u.kind, u.item, u.member, u.in_dictionary))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            key,
+            [
+                ("method", "Core", "get", true),
+                ("field", "Ext", "key", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn doc_links() {
+        let ns = crate::codegen::parse(
+            "namespace test
+            /** See [ext.load] for [Data.id], not [Nope] or `[Data]` */
+            struct Data { id: u64 }
+            extension ext {
+                version 0 1 0
+                /** Loads [Data], see also [Data](https://example.com) */
+                interface { methods { load() } }
+            }",
+        );
+        let syms = Symbols::new(&ns);
+        let core = Docs::new(&syms, Layout::Extension, None);
+        let ext = Docs::new(&syms, Layout::Extension, Some("ext"));
+
+        assert_eq!(
+            core.docs("Data", &ns.types[0].docs).unwrap(),
+            "See [ext.load](ext.md#ext.methods.load) for [Data.id](#Data), not [Nope] or `[Data]`\n"
+        );
+        assert_eq!(
+            ext.docs("ext", syms.interfaces()[0].docs).unwrap(),
+            "Loads [Data](README.md#Data), see also [Data](https://example.com)\n"
+        );
+    }
+
+    #[test]
+    fn values() {
+        let ns = crate::codegen::parse(
+            "namespace test
+            enum Mode(vu16) { Off On Auto = 10 }
+            flags Attrs(u16) { a = 1 b = 2 c = 256 }",
+        );
+        let syms = Symbols::new(&ns);
+        let w = &mut Vec::new();
+        Docs::new(&syms, Layout::Extension, None).types(w).unwrap();
+
+        assert_eq!(
+            String::from_utf8(w.clone()).unwrap(),
+            r#"## Types
+<a id="Mode"></a>
+### Enum `Mode` (`vu16`)
+
+| Name | Value |
+|------|------:|
+| Off | `0` |
+| On | `1` |
+| Auto | `10` |
+
+<a id="Attrs"></a>
+### Flags `Attrs` (`u16`)
+
+| Name | Value | Hex |
+|------|------:|----:|
+| a | `0b000000001` | `0x001` |
+| b | `0b000000010` | `0x002` |
+| c | `0b100000000` | `0x100` |
+
+"#
+        );
+    }
+
+    #[test]
+    fn split() {
+        let ns = crate::codegen::parse(
+            "namespace test
+            struct Key { a: u8 }
+            extension ext {
+                version 0 1 0
+                struct Ext { key: Key next: Ext }
+            }",
+        );
+        let mut fs = vfs::FS::new();
+        generate(&ns, "docs".into(), Layout::Item, &mut fs).unwrap();
+        let mut memory = Memory::new();
+        fs.save_to(&mut memory).unwrap();
+
+        assert_eq!(
+            memory.read_to_string("docs/SUMMARY.md").unwrap(),
+            "- [Test](README.md)
+  - [`Key`](Key.md)
+  - [Ext](ext/README.md)
+    - [`Ext`](ext/Ext.md)
+- [Index](symbols.md)
+"
+        );
+        assert_eq!(
+            memory.read_to_string("docs/ext/Ext.md").unwrap(),
+            r#"[← Extension `ext`](README.md)
+
+<a id="Ext"></a>
+### Struct `Ext`
+
+- `key`: [`Key`](../Key.md#Key)
+- `next`: [`Ext`](#Ext)
+
+#### Used by
+- field [`Ext.next`](#Ext)
+
+"#
+        );
+        assert_eq!(
+            memory.read_to_string("docs/README.md").unwrap(),
+            "# Hypercosm Protocol Docs
+## Types
+- [`Key`](Key.md)
+## Extensions
+- [ext](ext/README.md)
+
+See also the [index](symbols.md) of everything.
+"
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let ns = crate::codegen::parse(include_str!("../../hypercosm.hidl"));
+        let mut fs = vfs::FS::new();
+        generate(&ns, "docs".into(), Layout::Extension, &mut fs).unwrap();
+        let mut memory = Memory::new();
+        fs.save_to(&mut memory).unwrap();
+
+        assert_eq!(
+            memory.read_to_string("docs/SUMMARY.md").unwrap(),
+            include_str!("../../proto-docs/SUMMARY.md")
+        );
+        assert_eq!(
+            memory.files().map(|(p, _)| p.as_str()).collect::<Vec<_>>(),
+            [
+                "docs/README.md",
+                "docs/SUMMARY.md",
+                "docs/asset_delivery.md",
+                "docs/execution_context.md",
+                "docs/symbols.md",
+                "docs/world.md",
+            ]
+        );
+    }
+
+    #[test]
+    fn clashes() {
+        let gen = |hidl: &str, layout| {
+            let ns = crate::codegen::parse(hidl);
+            generate(&ns, "docs".into(), layout, &mut vfs::FS::new())
+        };
+
+        for name in ["index", "README", "SUMMARY", "symbols", "Readme"] {
+            let hidl = format!("namespace test extension {} {{ version 0 1 0 }}", name);
+            let err = gen(&hidl, Layout::Extension).unwrap_err();
+            assert!(
+                err.to_string().contains("would be the same page"),
+                "{}",
+                err
+            );
+
+            let hidl = format!("namespace test struct {} {{ a: u8 }}", name);
+            assert!(gen(&hidl, Layout::Extension).is_ok());
+            assert!(gen(&hidl, Layout::Item).is_err(), "{}", name);
+
+            let hidl = format!(
+                "namespace test extension ext {{ version 0 1 0 struct {} {{ a: u8 }} }}",
+                name
+            );
+            let clashes = name.eq_ignore_ascii_case("index") || name.eq_ignore_ascii_case("readme");
+            assert_eq!(gen(&hidl, Layout::Item).is_err(), clashes, "{}", name);
+        }
+
+        // Each extension has its own directory
+        let hidl = "namespace test
+            struct ext { a: u8 }
+            extension ext { version 0 1 0 }";
+        assert!(gen(hidl, Layout::Item).is_ok());
+    }
+}
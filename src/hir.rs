@@ -3,6 +3,7 @@
 //! - All enum variants have a number
 //! - Extensions implicit interface has been moved into interfaces list
 //! - Docs have been striped with the [`doc`] module
+//! - Links in docs are in `doc_links`, see [`docs::resolve_links`]
 
 // TODO: At some point make HIR a different type to AST
 // TODO: Lint agains []u8, suggest bytes
@@ -15,6 +16,7 @@ use crate::{
         Namespace, TypeDef, TypeKind,
     },
     docs,
+    symbols::Symbols,
 };
 
 pub fn lower_namespace(
@@ -23,13 +25,111 @@ pub fn lower_namespace(
         interfaces,
         types,
         extensions,
+        doc_links: _,
     }: Namespace,
 ) -> Namespace {
-    Namespace {
+    let mut ns = Namespace {
         name,
         interfaces: vmap(interfaces, lower_interface),
         types: vmap(types, lower_type_def),
         extensions: vmap(extensions, lower_extension),
+        doc_links: Vec::new(),
+    };
+
+    link_docs(&mut ns);
+    ns
+}
+
+/// Fill in `doc_links` for a lowered namespace, warning about broken links.
+pub fn link_docs(ns: &mut Namespace) {
+    let (links, warnings) = docs::resolve_links(&Symbols::new(ns));
+    for warning in &warnings {
+        eprintln!("warning: {}", warning);
+    }
+    ns.doc_links = links;
+}
+
+/// Check that a namespace that didn't come from [`lower_namespace`] (such
+/// as one loaded from JSON) upholds the HIR invariants.
+///
+/// Docs can't be checked, so are assumed to be lowered already.
+pub fn validate(ns: &Namespace) -> anyhow::Result<()> {
+    let mut errors = Vec::new();
+
+    let types = ns
+        .types
+        .iter()
+        .chain(ns.extensions.iter().flat_map(|e| &e.types));
+    for ty in types {
+        if let TypeKind::Enum(e) = &ty.kind {
+            let mut seen = BTreeSet::new();
+            for f in &e.fields {
+                match f.value {
+                    None => errors.push(format!("`{}.{}` has no value", ty.name, f.name)),
+                    Some(v) if !seen.insert(v) => errors.push(format!(
+                        "`{}.{}` has duplicate value {}",
+                        ty.name, f.name, v
+                    )),
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    for ext in &ns.extensions {
+        if ext.interface.is_some() {
+            errors.push(format!(
+                "Extension `{}` has an implicit interface, which should be in `interfaces`",
+                ext.name
+            ));
+        }
+    }
+
+    if !errors.is_empty() {
+        anyhow::bail!(
+            "Invalid HIR:\n{}",
+            errors
+                .iter()
+                .map(|e| format!("  - {}", e))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    Ok(())
+}
+
+/// Lower only the docs in `ns`, leaving it otherwise as written, so it can
+/// be printed back out by `fmt`.
+pub fn lower_docs(ns: &mut Namespace) {
+    fn funcs(funcs: &mut [Func]) {
+        for f in funcs {
+            f.docs = docs::lower(&f.docs);
+        }
+    }
+    fn types(types: &mut [TypeDef]) {
+        for ty in types {
+            ty.docs = docs::lower(&ty.docs);
+        }
+    }
+
+    for i in &mut ns.interfaces {
+        i.docs = docs::lower(&i.docs);
+        funcs(&mut i.methods);
+        funcs(&mut i.events);
+    }
+    types(&mut ns.types);
+    for ext in &mut ns.extensions {
+        ext.docs = docs::lower(&ext.docs);
+        if let Some(i) = &mut ext.interface {
+            i.docs = docs::lower(&i.docs);
+            funcs(&mut i.methods);
+            funcs(&mut i.events);
+        }
+        for i in &mut ext.interfaces {
+            i.docs = docs::lower(&i.docs);
+            funcs(&mut i.methods);
+            funcs(&mut i.events);
+        }
+        types(&mut ext.types);
     }
 }
 
@@ -56,7 +156,7 @@ fn lower_extension(
         version,
         interface: None,
         interfaces: new_interfaces,
-        types,
+        types: vmap(types, lower_type_def),
     }
 }
 
@@ -209,4 +309,32 @@ mod tests {
         };
         assert_eq!(lower_enum(enm), lowered);
     }
+
+    /// Types in extensions are lowered like core ones.
+    #[test]
+    fn extension_types() {
+        let ns = crate::grammar::NamespaceParser::new()
+            .parse(
+                "namespace test
+                extension ext {
+                    version 0 1 0
+                    /** An asset */
+                    struct Asset { id: u64 }
+                    enum Kind(u8) { A B }
+                }",
+            )
+            .unwrap();
+        let ns = lower_namespace(ns);
+        let types = &ns.extensions[0].types;
+
+        assert_eq!(types[0].docs, "An asset\n");
+        let TypeKind::Enum(kind) = &types[1].kind else {
+            panic!("not an enum: {:?}", types[1].kind);
+        };
+        assert_eq!(
+            kind.fields.iter().map(|f| f.value).collect::<Vec<_>>(),
+            [Some(0), Some(1)]
+        );
+        validate(&ns).unwrap();
+    }
 }
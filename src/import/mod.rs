@@ -0,0 +1,107 @@
+//! Importers, which turn definitions from other IDLs into a HIDL AST, to
+//! be printed with [`crate::print`] and edited as normal HIDL.
+//!
+//! Interfaces don't have versions in other IDLs, so they're given `0 1 0`.
+
+use anyhow::{bail, Result};
+use camino::Utf8Path;
+use heck::{ToSnakeCase, ToUpperCamelCase};
+
+use crate::{
+    ast::{Extension, ExtensionInterface, Namespace},
+    print,
+};
+
+pub mod proto;
+pub mod wit;
+
+/// Names accepted by [`import`].
+pub const FORMATS: &[&str] = &["proto", "wit"];
+
+/// The version given to everything imported.
+pub const VERSION: (u8, u8, u8) = (0, 1, 0);
+
+pub struct Imported {
+    pub namespace: Namespace,
+    /// Things that were imported, but lost some meaning on the way
+    pub warnings: Vec<String>,
+}
+
+pub fn import(format: &str, path: &Utf8Path) -> Result<Imported> {
+    match format {
+        "proto" => proto::import(path),
+        "wit" => wit::import(path),
+        _ => bail!(
+            "Unknown format `{}`, expected one of: {}",
+            format,
+            FORMATS.join(", ")
+        ),
+    }
+}
+
+/// Move everything in `ns` into an extension named `name`, so it can be
+/// added to an existing namespace.
+pub fn into_extension(ns: Namespace, name: &str) -> Namespace {
+    let extension = Extension {
+        name: name.to_owned(),
+        version: VERSION,
+        docs: String::new(),
+        interface: None,
+        interfaces: ns
+            .interfaces
+            .into_iter()
+            .map(|i| ExtensionInterface {
+                name: i.name,
+                docs: i.docs,
+                methods: i.methods,
+                events: i.events,
+            })
+            .collect(),
+        types: ns.types,
+    };
+
+    Namespace {
+        name: ns.name,
+        interfaces: Vec::new(),
+        types: Vec::new(),
+        extensions: vec![extension],
+        doc_links: Vec::new(),
+    }
+}
+
+/// The name of a type or interface, in HIDL's style.
+fn type_name(name: &str) -> String {
+    ident(name.to_upper_camel_case())
+}
+
+/// The name of anything else, in HIDL's style.
+fn name(name: &str) -> String {
+    ident(name.to_snake_case())
+}
+
+/// Avoid keywords, and names that don't start with a letter.
+fn ident(mut name: String) -> String {
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert(0, 'x');
+    }
+    if print::KEYWORDS.contains(&&name[..]) {
+        name.push('_');
+    }
+    name
+}
+
+/// The error for `errors`, if there are any.
+fn check(from: &str, errors: Vec<String>) -> Result<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    bail!(
+        "`{}` can't be imported:\n{}",
+        from,
+        errors
+            .iter()
+            .map(|e| format!("  - {}", e))
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
@@ -0,0 +1,478 @@
+//! Import proto3 files.
+//!
+//! - Messages become structs, and enums become enums with the enum name
+//!   prefix removed from their values. Nested messages and enums are
+//!   flattened, with the outer name as a prefix.
+//! - Services become interfaces, with a method per rpc taking the request
+//!   message as `request`, and returning the response message. Empty
+//!   messages are left out.
+//! - `repeated` fields become arrays, and `map<>` fields dictionaries.
+//! - `int32` and `int64` become `vi32` and `vi64`. They're encoded
+//!   differently, but hold the same values.
+//! - The `Object`, `Uuid` and `Matrix4x4` messages written by
+//!   [`codegen::proto`](crate::codegen::proto) become their HIDL
+//!   primitives again.
+//!
+//! Oneofs, streaming rpcs, groups and types from other files have no HIDL
+//! equivalent, so are errors. proto3 `optional` fields are imported as
+//! normal fields, with a warning.
+//!
+//! `protobuf-parse` doesn't keep comments, so nothing imported has docs.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use heck::ToShoutySnakeCase;
+use protobuf::descriptor::{
+    field_descriptor_proto::{Label, Type as FieldType},
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto,
+};
+
+use super::{check, name, type_name, Imported, VERSION};
+use crate::ast::{
+    Arg, Dictionary, Enum, EnumField, Func, IntType, Interface, Namespace, PrimType, Struct,
+    StructField, Type, TypeDef, TypeKind,
+};
+
+pub fn import(path: &Utf8Path) -> Result<Imported> {
+    let dir = path.parent().unwrap_or_else(|| Utf8Path::new("."));
+    let dir = if dir.as_str().is_empty() {
+        Utf8Path::new(".")
+    } else {
+        dir
+    };
+
+    let parsed = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir)
+        .input(path)
+        .parse_and_typecheck()
+        .with_context(|| format!("failed to parse `{}`", path))?;
+    let input = parsed.relative_paths[0].to_str();
+    let file = parsed
+        .file_descriptors
+        .iter()
+        .find(|f| f.name() == input)
+        .unwrap();
+
+    let mut cx = Cx {
+        types: BTreeMap::new(),
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let prefix = match file.package() {
+        "" => String::new(),
+        package => format!(".{}", package),
+    };
+    for m in &file.message_type {
+        cx.add_message(&prefix, "", m);
+    }
+    for e in &file.enum_type {
+        cx.add_enum(&prefix, "", e);
+    }
+
+    let mut types = Vec::new();
+    for m in &file.message_type {
+        cx.message(&mut types, "", m);
+    }
+    for e in &file.enum_type {
+        types.push(enum_def("", e));
+    }
+
+    let mut interfaces = Vec::new();
+    for s in &file.service {
+        let mut methods = Vec::new();
+        for m in &s.method {
+            let context = format!("{}.{}", s.name(), m.name());
+            if m.client_streaming() || m.server_streaming() {
+                cx.errors.push(format!(
+                    "`{}`: streaming rpcs have no HIDL equivalent",
+                    context
+                ));
+                continue;
+            }
+
+            let request = cx.message_type(&context, m.input_type());
+            let response = cx.message_type(&context, m.output_type());
+            methods.push(Func {
+                name: name(m.name()),
+                docs: String::new(),
+                args: request
+                    .map(|ty| Arg {
+                        name: "request".to_owned(),
+                        ty,
+                    })
+                    .into_iter()
+                    .collect(),
+                ret: response,
+            });
+        }
+
+        interfaces.push(Interface {
+            name: type_name(s.name()),
+            docs: String::new(),
+            version: VERSION,
+            methods,
+            events: Vec::new(),
+        });
+    }
+
+    check(path.as_str(), cx.errors)?;
+
+    let ns_name = match file.package().rsplit('.').next() {
+        Some(package) if !package.is_empty() => package,
+        _ => path.file_stem().unwrap_or("imported"),
+    };
+    Ok(Imported {
+        namespace: Namespace {
+            name: name(ns_name),
+            interfaces,
+            types,
+            extensions: Vec::new(),
+            doc_links: Vec::new(),
+        },
+        warnings: cx.warnings,
+    })
+}
+
+/// What a fully qualified proto name refers to.
+#[derive(Clone)]
+enum Known {
+    Type { ty: Type, empty: bool },
+    Map(Box<FieldDescriptorProto>, Box<FieldDescriptorProto>),
+}
+
+struct Cx {
+    types: BTreeMap<String, Known>,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Cx {
+    fn add_message(&mut self, prefix: &str, outer: &str, m: &DescriptorProto) {
+        let full = format!("{}.{}", prefix, m.name());
+        let hidl = format!("{}{}", outer, type_name(m.name()));
+
+        let known = if m.options.map_entry() {
+            Known::Map(Box::new(m.field[0].clone()), Box::new(m.field[1].clone()))
+        } else {
+            Known::Type {
+                ty: match builtin(outer, m) {
+                    Some(p) => Type::Primitive(p),
+                    None => Type::Custom(hidl.clone()),
+                },
+                empty: m.field.is_empty(),
+            }
+        };
+        self.types.insert(full.clone(), known);
+
+        for nested in &m.nested_type {
+            self.add_message(&full, &hidl, nested);
+        }
+        for e in &m.enum_type {
+            self.add_enum(&full, &hidl, e);
+        }
+    }
+
+    fn add_enum(&mut self, prefix: &str, outer: &str, e: &EnumDescriptorProto) {
+        self.types.insert(
+            format!("{}.{}", prefix, e.name()),
+            Known::Type {
+                ty: Type::Custom(format!("{}{}", outer, type_name(e.name()))),
+                empty: false,
+            },
+        );
+    }
+
+    fn lookup(&mut self, context: &str, full: &str) -> Option<Known> {
+        let known = self.types.get(full).cloned();
+        if known.is_none() {
+            self.errors.push(format!(
+                "`{}`: `{}` is from another file, which can't be imported",
+                context,
+                full.trim_start_matches('.')
+            ));
+        }
+        known
+    }
+
+    /// The type of an rpc's request or response, or `None` if it's empty.
+    fn message_type(&mut self, context: &str, full: &str) -> Option<Type> {
+        match self.lookup(context, full)? {
+            Known::Type { empty: true, .. } => None,
+            Known::Type { ty, .. } => Some(ty),
+            Known::Map(..) => unreachable!("Map entries can't be named"),
+        }
+    }
+
+    fn message(&mut self, types: &mut Vec<TypeDef>, outer: &str, m: &DescriptorProto) {
+        let hidl = format!("{}{}", outer, type_name(m.name()));
+        if m.options.map_entry() || builtin(outer, m).is_some() {
+            return;
+        }
+
+        let mut fields = m.field.iter().collect::<Vec<_>>();
+        fields.sort_by_key(|f| f.number());
+        let fields = fields
+            .into_iter()
+            .filter_map(|f| {
+                let context = format!("{}.{}", hidl, f.name());
+                Some(StructField {
+                    name: name(f.name()),
+                    ty: self.field_type(&context, f)?,
+                })
+            })
+            .collect();
+
+        types.push(TypeDef {
+            name: hidl.clone(),
+            docs: String::new(),
+            kind: TypeKind::Struct(Struct { fields }),
+        });
+
+        for nested in &m.nested_type {
+            self.message(types, &hidl, nested);
+        }
+        for e in &m.enum_type {
+            types.push(enum_def(&hidl, e));
+        }
+    }
+
+    fn field_type(&mut self, context: &str, f: &FieldDescriptorProto) -> Option<Type> {
+        if f.proto3_optional() {
+            self.warnings.push(format!(
+                "`{}`: HIDL has no optional fields, so presence is lost",
+                context
+            ));
+        } else if f.oneof_index.is_some() {
+            self.errors
+                .push(format!("`{}`: oneofs have no HIDL equivalent", context));
+            return None;
+        }
+
+        Some(match self.scalar(context, f)? {
+            Known::Map(key, value) => {
+                let key = self.plain(context, &key)?;
+                let value = self.plain(context, &value)?;
+                Type::Dictionary(Box::new(Dictionary { key, value }))
+            }
+            Known::Type { ty, .. } if f.label() == Label::LABEL_REPEATED => {
+                Type::Array(Box::new(ty))
+            }
+            Known::Type { ty, .. } => ty,
+        })
+    }
+
+    /// The type of a map's key or value.
+    fn plain(&mut self, context: &str, f: &FieldDescriptorProto) -> Option<Type> {
+        match self.scalar(context, f)? {
+            Known::Type { ty, .. } => Some(ty),
+            Known::Map(..) => unreachable!("Map values can't be maps"),
+        }
+    }
+
+    /// The type of `f`, ignoring its label.
+    fn scalar(&mut self, context: &str, f: &FieldDescriptorProto) -> Option<Known> {
+        let ty = match f.type_() {
+            FieldType::TYPE_DOUBLE => Type::Primitive(PrimType::F64),
+            FieldType::TYPE_FLOAT => Type::Primitive(PrimType::F32),
+            FieldType::TYPE_BOOL => Type::Primitive(PrimType::Bool),
+            FieldType::TYPE_STRING => Type::Primitive(PrimType::String),
+            FieldType::TYPE_BYTES => Type::Primitive(PrimType::Bytes),
+            FieldType::TYPE_INT64 | FieldType::TYPE_SINT64 => Type::IntType(IntType::VI64),
+            FieldType::TYPE_INT32 | FieldType::TYPE_SINT32 => Type::IntType(IntType::VI32),
+            FieldType::TYPE_UINT64 => Type::IntType(IntType::VU64),
+            FieldType::TYPE_UINT32 => Type::IntType(IntType::VU32),
+            FieldType::TYPE_FIXED64 => Type::IntType(IntType::U64),
+            FieldType::TYPE_FIXED32 => Type::IntType(IntType::U32),
+            FieldType::TYPE_SFIXED64 => Type::IntType(IntType::I64),
+            FieldType::TYPE_SFIXED32 => Type::IntType(IntType::I32),
+            FieldType::TYPE_GROUP => {
+                self.errors
+                    .push(format!("`{}`: groups have no HIDL equivalent", context));
+                return None;
+            }
+            FieldType::TYPE_MESSAGE | FieldType::TYPE_ENUM => {
+                return self.lookup(context, f.type_name());
+            }
+        };
+        Some(Known::Type { ty, empty: false })
+    }
+}
+
+fn enum_def(outer: &str, e: &EnumDescriptorProto) -> TypeDef {
+    let prefix = format!("{}_", e.name().to_shouty_snake_case());
+    let negative = e.value.iter().any(|v| v.number() < 0);
+
+    TypeDef {
+        name: format!("{}{}", outer, type_name(e.name())),
+        docs: String::new(),
+        kind: TypeKind::Enum(Enum {
+            backing: if negative {
+                IntType::VI32
+            } else {
+                IntType::VU32
+            },
+            fields: e
+                .value
+                .iter()
+                .map(|v| EnumField {
+                    name: type_name(v.name().strip_prefix(&prefix).unwrap_or(v.name())),
+                    value: Some(v.number().into()),
+                })
+                .collect(),
+        }),
+    }
+}
+
+/// The primitive for a message [`codegen::proto`](crate::codegen::proto)
+/// writes for it, if `m` is one.
+fn builtin(outer: &str, m: &DescriptorProto) -> Option<PrimType> {
+    if !outer.is_empty() {
+        return None;
+    }
+
+    let fields = m
+        .field
+        .iter()
+        .map(|f| (f.name(), f.type_(), f.label(), f.number()))
+        .collect::<Vec<_>>();
+    let optional = Label::LABEL_OPTIONAL;
+    let repeated = Label::LABEL_REPEATED;
+
+    match (m.name(), &fields[..]) {
+        ("Object", [("id", FieldType::TYPE_UINT64, l, 1)]) if *l == optional => {
+            Some(PrimType::Object)
+        }
+        (
+            "Uuid",
+            [("high", FieldType::TYPE_FIXED64, l1, 1), ("low", FieldType::TYPE_FIXED64, l2, 2)],
+        ) if *l1 == optional && *l2 == optional => Some(PrimType::Uuid),
+        ("Matrix4x4", [("values", FieldType::TYPE_FLOAT, l, 1)]) if *l == repeated => {
+            Some(PrimType::Matrix4x4)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn import_str(proto: &str) -> Result<Imported> {
+        let dir = std::env::temp_dir().join(format!(
+            "hidl-import-{}-{}",
+            std::process::id(),
+            proto.len()
+        ));
+        fs_err::create_dir_all(&dir).unwrap();
+        let path = camino::Utf8PathBuf::try_from(dir.join("assets.proto")).unwrap();
+        fs_err::write(&path, proto).unwrap();
+        let res = import(&path);
+        fs_err::remove_dir_all(&dir).unwrap();
+        res
+    }
+
+    #[test]
+    fn assets() {
+        let imported = import_str(
+            r#"syntax = "proto3";
+            package legacy.assets;
+
+            message Uuid { fixed64 high = 1; fixed64 low = 2; }
+
+            message AssetMetadata {
+                enum Format { FORMAT_UNKNOWN = 0; FORMAT_GLTF = 1; FORMAT_PNG = 2; }
+
+                Uuid id = 1;
+                optional string name = 2;
+                repeated string tags = 4;
+                map<string, int64> sizes = 5;
+                Format format = 3;
+            }
+
+            message GetRequest { Uuid id = 1; }
+            message Empty {}
+
+            service Metadata {
+                rpc Get(GetRequest) returns (AssetMetadata);
+                rpc Refresh(Empty) returns (Empty);
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            imported.warnings,
+            ["`AssetMetadata.name`: HIDL has no optional fields, so presence is lost"]
+        );
+        assert_eq!(
+            crate::print::namespace(&imported.namespace).unwrap(),
+            "namespace assets
+
+interface Metadata {
+    version 0 1 0
+
+    methods {
+        get(request: GetRequest) -> AssetMetadata
+        refresh()
+    }
+}
+
+struct AssetMetadata {
+    id: uuid
+    name: string
+    format: AssetMetadataFormat
+    tags: []string
+    sizes: [string]vi64
+}
+
+enum AssetMetadataFormat(vu32) {
+    Unknown = 0
+    Gltf = 1
+    Png = 2
+}
+
+struct GetRequest {
+    id: uuid
+}
+
+struct Empty {}
+"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = import_str(
+            r#"syntax = "proto3";
+            import "google/protobuf/timestamp.proto";
+
+            message Event {
+                oneof kind { string name = 1; int32 id = 2; }
+                google.protobuf.Timestamp at = 3;
+            }
+
+            service Events {
+                rpc Watch(Event) returns (stream Event);
+            }"#,
+        )
+        .map(|_| ())
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string()
+                .lines()
+                .skip(1)
+                .collect::<Vec<_>>(),
+            [
+                "  - `Event.name`: oneofs have no HIDL equivalent",
+                "  - `Event.id`: oneofs have no HIDL equivalent",
+                "  - `Event.at`: `google.protobuf.Timestamp` is from another file, which can't be imported",
+                "  - `Events.Watch`: streaming rpcs have no HIDL equivalent",
+            ]
+        );
+    }
+}
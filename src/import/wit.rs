@@ -0,0 +1,547 @@
+//! Import WIT packages.
+//!
+//! The file's top level package becomes the namespace, named after the
+//! package's namespace, and any nested packages become extensions, taking
+//! their version from the package.
+//!
+//! - Records become structs, enums become enums, and flags become flags
+//!   with a bit per flag. Both use the smallest int that fits.
+//! - Resources become interfaces, with their methods. Freestanding
+//!   functions become methods of an interface named after their WIT
+//!   interface.
+//! - `list<u8>` becomes `bytes`, and `list<tuple<K, V>>` a dictionary, which
+//!   is how [`codegen::wit`](crate::codegen::wit) writes them. The `uuid`
+//!   and `matrix4x4` aliases it writes become their HIDL primitives again.
+//! - Other aliases are replaced with what they alias.
+//!
+//! Variants, options, results, tuples, `char`, async functions, static
+//! functions and constructors have no HIDL equivalent, so are errors.
+//! Fixed size lists become arrays, with a warning.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use wit_parser::{
+    Docs, FunctionKind, Handle, PackageId, Resolve, Type as WitType, TypeDefKind, TypeId,
+};
+
+use super::{check, name, type_name, Imported, VERSION};
+use crate::ast::{
+    Arg, Dictionary, Enum, EnumField, Extension, ExtensionInterface, FlagField, Flags, Func,
+    IntType, Interface, Namespace, PrimType, Struct, StructField, Type, TypeDef, TypeKind,
+};
+
+pub fn import(path: &Utf8Path) -> Result<Imported> {
+    let mut resolve = Resolve::new();
+    let main = resolve
+        .push_file(path)
+        .with_context(|| format!("failed to parse `{}`", path))?;
+
+    let mut cx = Cx {
+        resolve: &resolve,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let (interfaces, types) = cx.package(main);
+    let mut extensions = Vec::new();
+    for (id, package) in resolve.packages.iter() {
+        if id == main {
+            continue;
+        }
+
+        let version = match &package.name.version {
+            Some(v) => match (v.major.try_into(), v.minor.try_into(), v.patch.try_into()) {
+                (Ok(major), Ok(minor), Ok(patch)) => (major, minor, patch),
+                _ => {
+                    cx.errors.push(format!(
+                        "`{}`: HIDL versions must fit in a u8",
+                        package.name
+                    ));
+                    VERSION
+                }
+            },
+            None => VERSION,
+        };
+
+        let (interfaces, types) = cx.package(id);
+        extensions.push(Extension {
+            name: name(&package.name.name),
+            version,
+            docs: docs(&package.docs),
+            interface: None,
+            interfaces: interfaces
+                .into_iter()
+                .map(|i| ExtensionInterface {
+                    name: i.name,
+                    docs: i.docs,
+                    methods: i.methods,
+                    events: i.events,
+                })
+                .collect(),
+            types,
+        });
+    }
+
+    check(path.as_str(), cx.errors)?;
+
+    Ok(Imported {
+        namespace: Namespace {
+            name: name(&resolve.packages[main].name.namespace),
+            interfaces,
+            types,
+            extensions,
+            doc_links: Vec::new(),
+        },
+        warnings: cx.warnings,
+    })
+}
+
+struct Cx<'a> {
+    resolve: &'a Resolve,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Cx<'_> {
+    fn package(&mut self, id: PackageId) -> (Vec<Interface>, Vec<TypeDef>) {
+        let resolve = self.resolve;
+        let mut interfaces = Vec::new();
+        let mut types = Vec::new();
+
+        for (iface_name, iface) in &resolve.packages[id].interfaces {
+            let iface = &resolve.interfaces[*iface];
+
+            // Resources, so their methods can be found
+            let mut resources = BTreeMap::<TypeId, usize>::new();
+
+            for (ty_name, ty) in &iface.types {
+                let def = &resolve.types[*ty];
+                let context = format!("{}/{}", iface_name, ty_name);
+
+                let kind = match &def.kind {
+                    TypeDefKind::Record(r) => TypeKind::Struct(Struct {
+                        fields: r
+                            .fields
+                            .iter()
+                            .filter_map(|f| {
+                                let context = format!("{}.{}", context, f.name);
+                                Some(StructField {
+                                    name: name(&f.name),
+                                    ty: self.ty(&context, &f.ty)?,
+                                })
+                            })
+                            .collect(),
+                    }),
+                    TypeDefKind::Enum(e) => TypeKind::Enum(Enum {
+                        backing: smallest_int(e.cases.len() as u64),
+                        fields: e
+                            .cases
+                            .iter()
+                            .enumerate()
+                            .map(|(n, c)| EnumField {
+                                name: type_name(&c.name),
+                                value: Some(n as i64),
+                            })
+                            .collect(),
+                    }),
+                    TypeDefKind::Flags(f) => TypeKind::Flags(Flags {
+                        backing: smallest_int(1 << f.flags.len()),
+                        fields: f
+                            .flags
+                            .iter()
+                            .enumerate()
+                            .map(|(n, f)| FlagField {
+                                name: type_name(&f.name),
+                                value: 1 << n,
+                            })
+                            .collect(),
+                    }),
+                    TypeDefKind::Resource => {
+                        resources.insert(*ty, interfaces.len());
+                        let (docs, version) = versioned_docs(&def.docs);
+                        interfaces.push(Interface {
+                            name: type_name(ty_name),
+                            docs,
+                            version,
+                            methods: Vec::new(),
+                            events: Vec::new(),
+                        });
+                        continue;
+                    }
+                    TypeDefKind::Variant(_) => {
+                        self.errors
+                            .push(format!("`{}`: variants have no HIDL equivalent", context));
+                        continue;
+                    }
+                    // Aliases, which are replaced where they're used
+                    _ => continue,
+                };
+
+                types.push(TypeDef {
+                    name: type_name(ty_name),
+                    docs: docs(&def.docs),
+                    kind,
+                });
+            }
+
+            let mut freestanding = Vec::new();
+            for (func_name, f) in &iface.functions {
+                let context = format!("{}/{}", iface_name, func_name);
+                let (resource, args) = match f.kind {
+                    FunctionKind::Freestanding => (None, &f.params[..]),
+                    // Skip `self`
+                    FunctionKind::Method(id) => (Some(id), &f.params[1..]),
+                    _ => {
+                        self.errors.push(format!(
+                            "`{}`: async and static functions, and constructors, have no HIDL equivalent",
+                            context
+                        ));
+                        continue;
+                    }
+                };
+
+                let args = args
+                    .iter()
+                    .map(|(arg, ty)| {
+                        let context = format!("{}({})", context, arg);
+                        Some(Arg {
+                            name: name(arg),
+                            ty: self.ty(&context, ty)?,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let ret = f.result.as_ref().map(|ty| self.ty(&context, ty));
+                let (Some(args), false) = (args.into_iter().collect(), matches!(ret, Some(None)))
+                else {
+                    continue;
+                };
+
+                let func = Func {
+                    // Methods are named `[method]resource.name`
+                    name: name(func_name.rsplit('.').next().unwrap()),
+                    docs: docs(&f.docs),
+                    args,
+                    ret: ret.flatten(),
+                };
+                match resource {
+                    Some(id) => interfaces[resources[&id]].methods.push(func),
+                    None => freestanding.push(func),
+                }
+            }
+
+            if !freestanding.is_empty() {
+                interfaces.push(Interface {
+                    name: type_name(iface_name),
+                    docs: docs(&iface.docs),
+                    version: VERSION,
+                    methods: freestanding,
+                    events: Vec::new(),
+                });
+            }
+        }
+
+        (interfaces, types)
+    }
+
+    fn ty(&mut self, context: &str, ty: &WitType) -> Option<Type> {
+        let resolve = self.resolve;
+        let int = |i| Some(Type::IntType(i));
+
+        let id = match ty {
+            WitType::Bool => return Some(Type::Primitive(PrimType::Bool)),
+            WitType::U8 => return int(IntType::U8),
+            WitType::U16 => return int(IntType::U16),
+            WitType::U32 => return int(IntType::U32),
+            WitType::U64 => return int(IntType::U64),
+            WitType::S8 => return int(IntType::I8),
+            WitType::S16 => return int(IntType::I16),
+            WitType::S32 => return int(IntType::I32),
+            WitType::S64 => return int(IntType::I64),
+            WitType::F32 => return Some(Type::Primitive(PrimType::F32)),
+            WitType::F64 => return Some(Type::Primitive(PrimType::F64)),
+            WitType::String => return Some(Type::Primitive(PrimType::String)),
+            WitType::Char | WitType::ErrorContext => {
+                return self.unsupported(context, ty);
+            }
+            WitType::Id(id) => *id,
+        };
+
+        let def = &resolve.types[id];
+        if let Some(p) = builtin(resolve, ty) {
+            if def.name.as_deref() == Some(&p.to_string()) {
+                return Some(Type::Primitive(p));
+            }
+        }
+        match &def.kind {
+            TypeDefKind::Record(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Resource => Some(Type::Custom(type_name(def.name.as_ref()?))),
+            TypeDefKind::Handle(Handle::Own(r) | Handle::Borrow(r)) => {
+                self.ty(context, &WitType::Id(*r))
+            }
+            TypeDefKind::Type(inner) => self.ty(context, inner),
+            TypeDefKind::List(WitType::U8) => Some(Type::Primitive(PrimType::Bytes)),
+            TypeDefKind::List(WitType::Id(inner)) => match &resolve.types[*inner].kind {
+                TypeDefKind::Tuple(t) if t.types.len() == 2 => {
+                    Some(Type::Dictionary(Box::new(Dictionary {
+                        key: self.ty(context, &t.types[0])?,
+                        value: self.ty(context, &t.types[1])?,
+                    })))
+                }
+                _ => Some(Type::Array(Box::new(
+                    self.ty(context, &WitType::Id(*inner))?,
+                ))),
+            },
+            TypeDefKind::List(inner) => Some(Type::Array(Box::new(self.ty(context, inner)?))),
+            TypeDefKind::FixedSizeList(inner, len) => {
+                self.warnings.push(format!(
+                    "`{}`: HIDL arrays don't have a fixed size, so the length of {} is lost",
+                    context, len
+                ));
+                Some(Type::Array(Box::new(self.ty(context, inner)?)))
+            }
+            TypeDefKind::Map(key, value) => Some(Type::Dictionary(Box::new(Dictionary {
+                key: self.ty(context, key)?,
+                value: self.ty(context, value)?,
+            }))),
+            _ => self.unsupported(context, ty),
+        }
+    }
+
+    fn unsupported(&mut self, context: &str, ty: &WitType) -> Option<Type> {
+        self.errors.push(format!(
+            "`{}`: `{}` has no HIDL equivalent",
+            context,
+            describe(self.resolve, ty)
+        ));
+        None
+    }
+}
+
+/// `ty` as it would be written in WIT, for errors.
+fn describe(resolve: &Resolve, ty: &WitType) -> String {
+    let id = match ty {
+        WitType::Bool => return "bool".to_owned(),
+        WitType::U8 => return "u8".to_owned(),
+        WitType::U16 => return "u16".to_owned(),
+        WitType::U32 => return "u32".to_owned(),
+        WitType::U64 => return "u64".to_owned(),
+        WitType::S8 => return "s8".to_owned(),
+        WitType::S16 => return "s16".to_owned(),
+        WitType::S32 => return "s32".to_owned(),
+        WitType::S64 => return "s64".to_owned(),
+        WitType::F32 => return "f32".to_owned(),
+        WitType::F64 => return "f64".to_owned(),
+        WitType::Char => return "char".to_owned(),
+        WitType::String => return "string".to_owned(),
+        WitType::ErrorContext => return "error-context".to_owned(),
+        WitType::Id(id) => *id,
+    };
+
+    let def = &resolve.types[id];
+    if let Some(name) = &def.name {
+        return name.clone();
+    }
+    let opt = |ty: &Option<WitType>| match ty {
+        Some(ty) => describe(resolve, ty),
+        None => "_".to_owned(),
+    };
+    match &def.kind {
+        TypeDefKind::Option(ty) => format!("option<{}>", describe(resolve, ty)),
+        TypeDefKind::Result(r) => format!("result<{}, {}>", opt(&r.ok), opt(&r.err)),
+        TypeDefKind::List(ty) => format!("list<{}>", describe(resolve, ty)),
+        TypeDefKind::Tuple(t) => format!(
+            "tuple<{}>",
+            t.types
+                .iter()
+                .map(|ty| describe(resolve, ty))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeDefKind::Future(ty) => format!("future<{}>", opt(ty)),
+        TypeDefKind::Stream(ty) => format!("stream<{}>", opt(ty)),
+        kind => kind.as_str().to_owned(),
+    }
+}
+
+/// The primitive [`codegen::wit`](crate::codegen::wit) aliases `ty` as, if
+/// it has the right shape.
+fn builtin(resolve: &Resolve, ty: &WitType) -> Option<PrimType> {
+    let WitType::Id(id) = ty else {
+        return None;
+    };
+    let TypeDefKind::Tuple(t) = &resolve.types[*id].kind else {
+        return None;
+    };
+
+    match &t.types[..] {
+        [WitType::U64, WitType::U64] => Some(PrimType::Uuid),
+        types if types.len() == 16 && types.iter().all(|t| *t == WitType::F32) => {
+            Some(PrimType::Matrix4x4)
+        }
+        _ => None,
+    }
+}
+
+/// The smallest int that has at least `values` different values.
+fn smallest_int(values: u64) -> IntType {
+    if values <= 1 << 8 {
+        IntType::U8
+    } else if values <= 1 << 16 {
+        IntType::U16
+    } else if values <= 1 << 32 {
+        IntType::U32
+    } else {
+        IntType::U64
+    }
+}
+
+fn docs(docs: &Docs) -> String {
+    docs.contents.clone().unwrap_or_default()
+}
+
+/// Resource docs, without the `Version: x.y.z` line that
+/// [`codegen::wit`](crate::codegen::wit) ends them with.
+fn versioned_docs(docs: &Docs) -> (String, (u8, u8, u8)) {
+    let docs = self::docs(docs);
+    let (rest, last) = docs.trim_end().rsplit_once('\n').unwrap_or(("", &docs));
+    let version = last
+        .trim()
+        .strip_prefix("Version: ")
+        .map(|v| v.split('.').map(str::parse).collect::<Vec<_>>());
+
+    match version.as_deref() {
+        Some([Ok(major), Ok(minor), Ok(patch)]) => {
+            (rest.trim_end().to_owned(), (*major, *minor, *patch))
+        }
+        _ => (docs, VERSION),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn import_str(wit: &str) -> Result<Imported> {
+        let dir = std::env::temp_dir().join(format!(
+            "hidl-import-wit-{}-{}",
+            std::process::id(),
+            wit.len()
+        ));
+        fs_err::create_dir_all(&dir).unwrap();
+        let path = camino::Utf8PathBuf::try_from(dir.join("import.wit")).unwrap();
+        fs_err::write(&path, wit).unwrap();
+        let res = import(&path);
+        fs_err::remove_dir_all(&dir).unwrap();
+        res
+    }
+
+    #[test]
+    fn assets() {
+        let imported = import_str(
+            "package legacy:assets;
+
+            interface metadata {
+                /// Where an asset came from
+                enum source { upload, generated }
+
+                flags access { read, write }
+
+                record asset-metadata {
+                    id: string,
+                    source: source,
+                    tags: list<string>,
+                    sizes: list<tuple<string, u64>>,
+                    thumbnail: list<u8>,
+                }
+
+                resource store {
+                    get: func(id: string) -> asset-metadata;
+                    put: func(metadata: asset-metadata, access: access);
+                }
+
+                ping: func();
+            }",
+        )
+        .map_err(|e| e.to_string());
+
+        assert_eq!(imported.as_ref().map(|i| &i.warnings), Ok(&vec![]));
+        assert_eq!(
+            crate::print::namespace(&imported.unwrap().namespace).unwrap(),
+            "namespace legacy
+
+interface Store {
+    version 0 1 0
+
+    methods {
+        get(id: string) -> AssetMetadata
+        put(metadata: AssetMetadata, access: Access)
+    }
+}
+
+interface Metadata {
+    version 0 1 0
+
+    methods {
+        ping()
+    }
+}
+
+/** Where an asset came from */
+enum Source(u8) {
+    Upload = 0
+    Generated = 1
+}
+
+flags Access(u8) {
+    Read = 1
+    Write = 2
+}
+
+struct AssetMetadata {
+    id: string
+    source: Source
+    tags: []string
+    sizes: [string]u64
+    thumbnail: bytes
+}
+"
+        );
+        let err = import_str("package a:b; interface c { d: func() -> option<u8>; }")
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string().lines().nth(1),
+            Some("  - `c/d`: `option<u8>` has no HIDL equivalent")
+        );
+    }
+
+    /// What `codegen::wit` writes can be imported.
+    #[test]
+    fn hypercosm() {
+        let ns = crate::codegen::parse(include_str!("../../hypercosm.hidl"));
+        let mut wit = Vec::new();
+        crate::codegen::wit::generate(&ns, &mut wit).unwrap();
+        let imported = import_str(&String::from_utf8(wit).unwrap()).unwrap();
+
+        let printed = crate::print::namespace(&imported.namespace).unwrap();
+        let expected = "    flags EntityAttrs(u8) {
+        Interactable = 1
+        Collidable = 2
+    }
+
+    struct EntityInfo {
+        asset_id: uuid
+        entity: Entity
+        transformation: matrix4x4
+        attrs: EntityAttrs
+    }
+";
+        assert!(printed.contains(expected), "{}", printed);
+        assert_eq!(imported.namespace.interfaces[0].version, (1, 0, 0));
+    }
+}
@@ -0,0 +1,63 @@
+//! Loading a namespace for the tools, from either HIDL or the JSON written
+//! by `generate`.
+
+use anyhow::Result;
+use camino::Utf8Path;
+use fs_err as fs;
+
+use crate::{ast::Namespace, symbols::Symbols};
+
+/// Load and lower the namespace in `path`, and check it with
+/// [`Symbols::check`], so every tool can rely on its names.
+///
+/// `.json` files are read as the output of `generate`, which is already
+/// lowered. Anything else is parsed as HIDL.
+pub fn load(path: &Utf8Path) -> Result<Namespace> {
+    let src = fs::read_to_string(path)?;
+
+    let ns = if path.extension() == Some("json") {
+        crate::json::from_str(&src).map_err(|e| e.context(format!("Invalid `{}`", path)))?
+    } else {
+        crate::hir::lower_namespace(parse(path, &src)?)
+    };
+    Symbols::new(&ns).check()?;
+
+    Ok(ns)
+}
+
+/// Parse the HIDL source `src` of `path`, without lowering it.
+///
+/// Syntax errors are reported with the source, and returned.
+pub fn parse(path: &Utf8Path, src: &str) -> Result<Namespace> {
+    let tree = crate::grammar::NamespaceParser::new().parse(src);
+    lalrpop_codespan::report_error(tree, path.as_str(), src)
+}
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn load_checks() {
+        let dir = std::env::temp_dir().join(format!("hidl-input-{}", std::process::id()));
+        let dir = Utf8PathBuf::try_from(dir).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        let hidl = dir.join("bad.hidl");
+        fs::write(&hidl, "namespace bad struct A { b: B }").unwrap();
+        let json = dir.join("bad.json");
+        let ns = crate::codegen::parse("namespace bad struct A { b: B }");
+        fs::write(&json, crate::json::to_string(&ns).unwrap()).unwrap();
+
+        let errors = [load(&hidl), load(&json)].map(|r| r.unwrap_err().to_string());
+        fs::remove_dir_all(&dir).unwrap();
+
+        for err in errors {
+            assert_eq!(
+                err,
+                "Invalid namespace `bad`:\n  - `A.b` uses unknown type `B`"
+            );
+        }
+    }
+}
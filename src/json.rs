@@ -0,0 +1,168 @@
+//! The JSON description of a namespace, written by the `generate` binary.
+//!
+//! Its shape is described by `spec/hidl-json-v2.schema.json`. Any change
+//! to the AST that changes the JSON must bump [`FORMAT_VERSION`] and add a
+//! new schema, so consumers can tell which one they're reading.
+//!
+//! Older versions are still loaded, as long as the current one is a
+//! superset of them:
+//!
+//! - v1: before `doc_links`, which are resolved when it's loaded
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{ast::Namespace, hir};
+
+pub const FORMAT_VERSION: u32 = 2;
+
+/// The schema for the current [`FORMAT_VERSION`].
+pub const SCHEMA: &str = include_str!("../spec/hidl-json-v2.schema.json");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub format_version: u32,
+    #[serde(flatten)]
+    pub namespace: Namespace,
+}
+
+/// Serialize a lowered namespace in the current format.
+pub fn to_string(ns: &Namespace) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&Document {
+        format_version: FORMAT_VERSION,
+        namespace: ns.clone(),
+    })
+}
+
+/// Load a namespace written by [`to_string`], checking it's valid HIR.
+pub fn from_str(json: &str) -> Result<Namespace> {
+    #[derive(Deserialize)]
+    struct Header {
+        format_version: Option<u32>,
+    }
+
+    // Checked first, as other versions may not deserialize at all
+    match serde_json::from_str::<Header>(json)?.format_version {
+        Some(1..=FORMAT_VERSION) => {}
+        Some(v) => bail!(
+            "Unsupported `format_version` {}, expected 1 to {}",
+            v,
+            FORMAT_VERSION
+        ),
+        None => bail!("No `format_version`, is this the output of `generate`?"),
+    }
+
+    let mut doc: Document = serde_json::from_str(json)?;
+    hir::validate(&doc.namespace)?;
+    if doc.format_version == 1 {
+        hir::link_docs(&mut doc.namespace);
+    }
+    Ok(doc.namespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn validator() -> jsonschema::Validator {
+        let schema = serde_json::from_str(SCHEMA).unwrap();
+        jsonschema::validator_for(&schema).unwrap()
+    }
+
+    /// The committed JSON matches the schema.
+    #[test]
+    fn committed() {
+        let json: Value = serde_json::from_str(include_str!("../json/hypercosm.json")).unwrap();
+        let errors = validator()
+            .iter_errors(&json)
+            .map(|e| format!("{} at {}", e, e.instance_path()))
+            .collect::<Vec<_>>();
+        assert!(errors.is_empty(), "{}", errors.join("\n"));
+
+        let doc: Document = serde_json::from_value(json).unwrap();
+        assert_eq!(doc.format_version, FORMAT_VERSION);
+    }
+
+    #[test]
+    fn rejects() {
+        let ns = crate::codegen::parse(
+            "namespace test
+            struct Foo { a: [string][]u8 b: Bar }
+            enum Bar(vu8) { A B }",
+        );
+        let valid: Value = serde_json::from_str(&to_string(&ns).unwrap()).unwrap();
+        let validator = validator();
+        assert!(validator.is_valid(&valid));
+
+        let mut old = valid.clone();
+        old["format_version"] = json!(0);
+        assert!(!validator.is_valid(&old));
+
+        let mut unknown_type = valid.clone();
+        unknown_type["types"][0]["kind"]["Struct"]["fields"][0]["ty"] = json!({ "Tuple": [] });
+        assert!(!validator.is_valid(&unknown_type));
+
+        let mut missing_value = valid;
+        let field = &mut missing_value["types"][1]["kind"]["Enum"]["fields"][0];
+        field.as_object_mut().unwrap().remove("value");
+        assert!(!validator.is_valid(&missing_value));
+    }
+
+    #[test]
+    fn load() {
+        let ns = crate::codegen::parse(include_str!("../hypercosm.hidl"));
+        assert_eq!(from_str(&to_string(&ns).unwrap()).unwrap(), ns);
+
+        let err = |json: Value| from_str(&json.to_string()).unwrap_err().to_string();
+        let json = json!({
+            "format_version": 2,
+            "name": "test",
+            "interfaces": [],
+            "types": [{
+                "name": "Bar",
+                "docs": "",
+                "kind": { "Enum": { "backing": "U8", "fields": [
+                    { "name": "A", "value": 1 },
+                    { "name": "B" },
+                    { "name": "C", "value": 1 },
+                ] } },
+            }],
+            "extensions": [{
+                "name": "ext",
+                "docs": "",
+                "version": [0, 1, 0],
+                "interface": { "docs": "", "methods": [], "events": [] },
+                "interfaces": [],
+                "types": [],
+            }],
+        });
+        assert_eq!(
+            err(json.clone()),
+            "Invalid HIR:
+  - `Bar.B` has no value
+  - `Bar.C` has duplicate value 1
+  - Extension `ext` has an implicit interface, which should be in `interfaces`"
+        );
+
+        let mut future = json;
+        future["format_version"] = json!(3);
+        assert_eq!(
+            err(future),
+            "Unsupported `format_version` 3, expected 1 to 2"
+        );
+
+        let v1 = json!({
+            "format_version": 1,
+            "name": "test",
+            "interfaces": [],
+            "types": [
+                { "name": "A", "docs": "Not a [B]\n", "kind": { "Struct": { "fields": [] } } },
+                { "name": "B", "docs": "", "kind": { "Struct": { "fields": [] } } },
+            ],
+            "extensions": [],
+        });
+        let v1 = from_str(&v1.to_string()).unwrap();
+        assert_eq!(v1.doc_links[0].target, "B");
+    }
+}
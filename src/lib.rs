@@ -9,6 +9,15 @@ lalrpop_util::lalrpop_mod!(
 // pub mod grammar;
 
 pub mod ast;
+pub mod cli;
+pub mod codegen;
+pub mod diff;
 mod docs;
+pub mod document;
 pub mod hir;
+pub mod import;
+pub mod input;
+pub mod json;
+pub mod print;
+pub mod symbols;
 pub mod vfs;
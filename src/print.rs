@@ -0,0 +1,342 @@
+//! Print an AST back out as HIDL.
+//!
+//! Docs are printed as is, so must already be lowered, as they are in HIR
+//! (including HIR loaded from JSON) and imported namespaces. Comments that
+//! aren't docs are lost when parsing, so can't be printed.
+
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+
+use crate::ast::{
+    Extension, ExtensionInterface, Func, ImplicitInterface, Interface, Namespace, TypeDef,
+    TypeKind, Version,
+};
+
+/// Words that can't be used as identifiers.
+pub const KEYWORDS: &[&str] = &[
+    "bool",
+    "bytes",
+    "enum",
+    "events",
+    "extension",
+    "f32",
+    "f64",
+    "flags",
+    "i16",
+    "i32",
+    "i64",
+    "i8",
+    "interface",
+    "matrix4x4",
+    "methods",
+    "namespace",
+    "object",
+    "string",
+    "struct",
+    "u16",
+    "u32",
+    "u64",
+    "u8",
+    "uuid",
+    "version",
+    "vi16",
+    "vi32",
+    "vi64",
+    "vi8",
+    "vu16",
+    "vu32",
+    "vu64",
+    "vu8",
+];
+
+/// Whether `name` can be used as an identifier.
+pub fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+pub fn namespace(ns: &Namespace) -> Result<String> {
+    let mut p = Printer {
+        out: String::new(),
+        indent: 0,
+    };
+
+    p.line(format_args!("namespace {}", ident(&ns.name)?))?;
+    for i in &ns.interfaces {
+        p.blank()?;
+        p.interface(i)?;
+    }
+    for ty in &ns.types {
+        p.blank()?;
+        p.type_def(ty)?;
+    }
+    for ext in &ns.extensions {
+        p.blank()?;
+        p.extension(ext)?;
+    }
+
+    Ok(p.out)
+}
+
+fn ident(name: &str) -> Result<&str> {
+    if !is_ident(name) {
+        bail!("`{}` isn't a valid HIDL identifier", name);
+    }
+    Ok(name)
+}
+
+struct Printer {
+    out: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn line(&mut self, line: std::fmt::Arguments) -> Result<()> {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        writeln!(self.out, "{}", line)?;
+        Ok(())
+    }
+
+    fn blank(&mut self) -> Result<()> {
+        self.out.push('\n');
+        Ok(())
+    }
+
+    fn open(&mut self, line: std::fmt::Arguments) -> Result<()> {
+        self.line(format_args!("{} {{", line))?;
+        self.indent += 1;
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.indent -= 1;
+        self.line(format_args!("}}"))
+    }
+
+    fn docs(&mut self, docs: &str) -> Result<()> {
+        let docs = docs.trim_end().replace("*/", "* /");
+        let lines = docs.lines().collect::<Vec<_>>();
+
+        match &lines[..] {
+            [] => Ok(()),
+            // Lowering would strip these from a single line
+            [line] if !line.starts_with(|c: char| c == '*' || c.is_whitespace()) => {
+                self.line(format_args!("/** {} */", line))
+            }
+            lines => {
+                self.line(format_args!("/**"))?;
+                for line in lines {
+                    if line.is_empty() {
+                        self.line(format_args!(" *"))?;
+                    } else {
+                        self.line(format_args!(" * {}", line))?;
+                    }
+                }
+                self.line(format_args!(" */"))
+            }
+        }
+    }
+
+    fn version(&mut self, (a, b, c): Version) -> Result<()> {
+        self.line(format_args!("version {} {} {}", a, b, c))
+    }
+
+    fn interface(&mut self, i: &Interface) -> Result<()> {
+        self.docs(&i.docs)?;
+        self.open(format_args!("interface {}", ident(&i.name)?))?;
+        self.version(i.version)?;
+        self.funcs(&i.events, &i.methods, true)?;
+        self.close()
+    }
+
+    fn extension(&mut self, ext: &Extension) -> Result<()> {
+        self.docs(&ext.docs)?;
+        self.open(format_args!("extension {}", ident(&ext.name)?))?;
+        self.version(ext.version)?;
+
+        if let Some(ImplicitInterface {
+            docs,
+            methods,
+            events,
+        }) = &ext.interface
+        {
+            self.blank()?;
+            self.docs(docs)?;
+            self.open(format_args!("interface"))?;
+            self.funcs(events, methods, false)?;
+            self.close()?;
+        }
+        for ExtensionInterface {
+            name,
+            docs,
+            methods,
+            events,
+        } in &ext.interfaces
+        {
+            self.blank()?;
+            self.docs(docs)?;
+            self.open(format_args!("interface {}", ident(name)?))?;
+            self.funcs(events, methods, false)?;
+            self.close()?;
+        }
+        for ty in &ext.types {
+            self.blank()?;
+            self.type_def(ty)?;
+        }
+
+        self.close()
+    }
+
+    /// `spaced` is whether there's something before the events.
+    fn funcs(&mut self, events: &[Func], methods: &[Func], spaced: bool) -> Result<()> {
+        let mut spaced = spaced;
+        for (kind, funcs) in [("events", events), ("methods", methods)] {
+            if funcs.is_empty() {
+                continue;
+            }
+            if spaced {
+                self.blank()?;
+            }
+            spaced = true;
+
+            self.open(format_args!("{}", kind))?;
+            for (n, f) in funcs.iter().enumerate() {
+                if n != 0 && !f.docs.trim().is_empty() {
+                    self.blank()?;
+                }
+                self.func(f)?;
+            }
+            self.close()?;
+        }
+        Ok(())
+    }
+
+    fn func(&mut self, f: &Func) -> Result<()> {
+        self.docs(&f.docs)?;
+        let args = f
+            .args
+            .iter()
+            .map(|a| Ok(format!("{}: {}", ident(&a.name)?, a.ty)))
+            .collect::<Result<Vec<_>>>()?;
+        match &f.ret {
+            Some(ret) => self.line(format_args!(
+                "{}({}) -> {}",
+                ident(&f.name)?,
+                args.join(", "),
+                ret
+            )),
+            None => self.line(format_args!("{}({})", ident(&f.name)?, args.join(", "))),
+        }
+    }
+
+    fn type_def(&mut self, ty: &TypeDef) -> Result<()> {
+        self.docs(&ty.docs)?;
+        let name = ident(&ty.name)?;
+
+        match &ty.kind {
+            TypeKind::Struct(s) if s.fields.is_empty() => {
+                return self.line(format_args!("struct {} {{}}", name));
+            }
+            TypeKind::Enum(e) if e.fields.is_empty() => {
+                return self.line(format_args!("enum {}({}) {{}}", name, e.backing));
+            }
+            TypeKind::Flags(f) if f.fields.is_empty() => {
+                return self.line(format_args!("flags {}({}) {{}}", name, f.backing));
+            }
+            _ => {}
+        }
+
+        match &ty.kind {
+            TypeKind::Struct(s) => {
+                self.open(format_args!("struct {}", name))?;
+                for f in &s.fields {
+                    self.line(format_args!("{}: {}", ident(&f.name)?, f.ty))?;
+                }
+            }
+            TypeKind::Enum(e) => {
+                self.open(format_args!("enum {}({})", name, e.backing))?;
+                for f in &e.fields {
+                    match f.value {
+                        Some(value) => {
+                            self.line(format_args!("{} = {}", ident(&f.name)?, value))?
+                        }
+                        None => self.line(format_args!("{}", ident(&f.name)?))?,
+                    }
+                }
+            }
+            TypeKind::Flags(f) => {
+                self.open(format_args!("flags {}({})", name, f.backing))?;
+                for field in &f.fields {
+                    self.line(format_args!("{} = {}", ident(&field.name)?, field.value))?;
+                }
+            }
+        }
+
+        self.close()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    use crate::codegen::parse;
+
+    /// Printing then parsing gives the same HIR.
+    #[test]
+    fn round_trip() {
+        let ns = parse(include_str!("../hypercosm.hidl"));
+        let printed = namespace(&ns).unwrap();
+
+        assert_eq!(parse(&printed), ns);
+        // Printing is stable
+        assert_eq!(namespace(&parse(&printed)).unwrap(), printed);
+
+        let bullet = parse("namespace test /**\n * * Bullet\n */ struct A {}");
+        assert_eq!(parse(&namespace(&bullet).unwrap()), bullet);
+    }
+
+    #[test]
+    fn format() {
+        let ns = parse(
+            "namespace test
+            /** Docs
+            *
+            * More docs */ interface Foo { methods { a() /** B */ b(x: [string][]u8) -> Bar } }
+            enum Bar(u8) { A B = 4 }",
+        );
+
+        assert_eq!(
+            namespace(&ns).unwrap(),
+            "namespace test
+
+/**
+ * Docs
+ *
+ * More docs
+ */
+interface Foo {
+    version 0 0 0
+
+    methods {
+        a()
+
+        /** B */
+        b(x: [string][]u8) -> Bar
+    }
+}
+
+enum Bar(u8) {
+    A = 0
+    B = 4
+}
+"
+        );
+    }
+}
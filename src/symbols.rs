@@ -0,0 +1,230 @@
+//! Name resolution over a lowered [`Namespace`].
+//!
+//! HIDL has a single flat namespace for custom types: a `Type::Custom` can
+//! name either a [`TypeDef`] or an interface (in which case it's an object
+//! reference), from the core namespace or any extension.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::ast::{Extension, Func, Namespace, Type, TypeDef, TypeKind, Version};
+
+/// A view over an interface, which hides the difference between core
+/// [`Interface`](crate::ast::Interface)s and
+/// [`ExtensionInterface`](crate::ast::ExtensionInterface)s.
+#[derive(Debug, Clone, Copy)]
+pub struct InterfaceRef<'a> {
+    pub name: &'a str,
+    pub docs: &'a str,
+    /// Extension interfaces get their version from the extension
+    pub version: Version,
+    pub methods: &'a [Func],
+    pub events: &'a [Func],
+    pub extension: Option<&'a Extension>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TypeRef<'a> {
+    pub def: &'a TypeDef,
+    pub extension: Option<&'a Extension>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Symbol<'a> {
+    Interface(InterfaceRef<'a>),
+    Type(TypeRef<'a>),
+}
+
+pub struct Symbols<'a> {
+    namespace: &'a Namespace,
+    interfaces: Vec<InterfaceRef<'a>>,
+    types: Vec<TypeRef<'a>>,
+    by_name: BTreeMap<&'a str, Symbol<'a>>,
+}
+
+impl<'a> Symbols<'a> {
+    pub fn new(namespace: &'a Namespace) -> Self {
+        let mut interfaces = Vec::new();
+        let mut types = Vec::new();
+
+        for i in &namespace.interfaces {
+            interfaces.push(InterfaceRef {
+                name: &i.name,
+                docs: &i.docs,
+                version: i.version,
+                methods: &i.methods,
+                events: &i.events,
+                extension: None,
+            });
+        }
+        for def in &namespace.types {
+            types.push(TypeRef {
+                def,
+                extension: None,
+            });
+        }
+        for ext in &namespace.extensions {
+            assert!(ext.interface.is_none(), "Symbols must be built from HIR");
+
+            for i in &ext.interfaces {
+                interfaces.push(InterfaceRef {
+                    name: &i.name,
+                    docs: &i.docs,
+                    version: ext.version,
+                    methods: &i.methods,
+                    events: &i.events,
+                    extension: Some(ext),
+                });
+            }
+            for def in &ext.types {
+                types.push(TypeRef {
+                    def,
+                    extension: Some(ext),
+                });
+            }
+        }
+
+        let mut by_name = BTreeMap::new();
+        // First definition wins, so types can't shadow interfaces.
+        for &i in &interfaces {
+            by_name.entry(i.name).or_insert(Symbol::Interface(i));
+        }
+        for &t in &types {
+            by_name.entry(&t.def.name[..]).or_insert(Symbol::Type(t));
+        }
+
+        Self {
+            namespace,
+            interfaces,
+            types,
+            by_name,
+        }
+    }
+
+    pub fn namespace(&self) -> &'a Namespace {
+        self.namespace
+    }
+
+    /// All interfaces, core first, then each extension in order.
+    pub fn interfaces(&self) -> &[InterfaceRef<'a>] {
+        &self.interfaces
+    }
+
+    /// All type definitions, core first, then each extension in order.
+    pub fn types(&self) -> &[TypeRef<'a>] {
+        &self.types
+    }
+
+    pub fn get(&self, name: &str) -> Option<Symbol<'a>> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Like [`Symbols::get`], but with an error for codegens to bubble up.
+    pub fn resolve(&self, name: &str) -> anyhow::Result<Symbol<'a>> {
+        self.get(name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown type `{}`", name))
+    }
+
+    /// Check that every name is defined once, and every type used is
+    /// defined, reporting all the problems at once.
+    pub fn check(&self) -> anyhow::Result<()> {
+        let mut errors = Vec::new();
+
+        let mut defined = BTreeSet::new();
+        let names = self.interfaces.iter().map(|i| i.name);
+        for name in names.chain(self.types.iter().map(|t| &t.def.name[..])) {
+            if !defined.insert(name) {
+                errors.push(format!("`{}` is defined more than once", name));
+            }
+        }
+
+        let mut uses = |place: String, ty: &Type| {
+            let mut names = Vec::new();
+            custom_names(ty, &mut names);
+            for name in names {
+                if self.get(name).is_none() {
+                    errors.push(format!("`{}` uses unknown type `{}`", place, name));
+                }
+            }
+        };
+        for i in &self.interfaces {
+            for f in i.methods.iter().chain(i.events) {
+                let place = format!("{}.{}", i.name, f.name);
+                for a in &f.args {
+                    uses(place.clone(), &a.ty);
+                }
+                if let Some(ret) = &f.ret {
+                    uses(place, ret);
+                }
+            }
+        }
+        for t in &self.types {
+            if let TypeKind::Struct(s) = &t.def.kind {
+                for f in &s.fields {
+                    uses(format!("{}.{}", t.def.name, f.name), &f.ty);
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            anyhow::bail!(
+                "Invalid namespace `{}`:\n{}",
+                self.namespace.name,
+                errors
+                    .iter()
+                    .map(|e| format!("  - {}", e))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+        }
+        Ok(())
+    }
+}
+
+fn custom_names<'t>(ty: &'t Type, out: &mut Vec<&'t str>) {
+    match ty {
+        Type::Custom(name) => out.push(name),
+        Type::Array(inner) => custom_names(inner, out),
+        Type::Dictionary(d) => {
+            custom_names(&d.key, out);
+            custom_names(&d.value, out);
+        }
+        Type::Primitive(_) | Type::IntType(_) => {}
+    }
+}
+
+impl<'a> InterfaceRef<'a> {
+    /// The name used on the wire, in the format
+    /// `{namespace}.{extension}.{interface_name}`.
+    pub fn qualified_name(&self, namespace: &str) -> String {
+        match self.extension {
+            Some(ext) => format!("{}.{}.{}", namespace, ext.name, self.name),
+            None => format!("{}.{}", namespace, self.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn check() {
+        let ns = crate::codegen::parse(include_str!("../hypercosm.hidl"));
+        Symbols::new(&ns).check().unwrap();
+
+        let ns = crate::codegen::parse(
+            "namespace test
+            interface Foo { version 1 0 0 methods { get(a: [string][]Nope) -> Foo } }
+            struct Foo { b: Bar }",
+        );
+        let err = Symbols::new(&ns).check().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid namespace `test`:
+  - `Foo` is defined more than once
+  - `Foo.get` uses unknown type `Nope`
+  - `Foo.b` uses unknown type `Bar`"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Saving into a tarball or zip archive, for shipping generated code
+//! somewhere else.
+
+use std::io::{Cursor, Write};
+
+use anyhow::{bail, Result};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use fs_err as fs;
+
+use super::{Backend, Output};
+
+/// A [`Backend`] that writes every file into a new archive at `path`,
+/// replacing it if it exists.
+///
+/// Owned directories are ignored, as there's nothing old in the archive.
+#[derive(Debug, Clone)]
+pub struct Archive {
+    path: Utf8PathBuf,
+    format: Format,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Tar,
+    Zip,
+}
+
+impl Archive {
+    pub fn new(path: impl Into<Utf8PathBuf>, format: Format) -> Self {
+        Self {
+            path: path.into(),
+            format,
+        }
+    }
+
+    /// An archive with the format given by the extension of `path`, which
+    /// must be `.tar` or `.zip`.
+    pub fn from_path(path: impl Into<Utf8PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let format = match path.extension() {
+            Some("tar") => Format::Tar,
+            Some("zip") => Format::Zip,
+            _ => bail!(
+                "Can't tell the format of `{}`, expected `.tar` or `.zip`",
+                path
+            ),
+        };
+        Ok(Self::new(path, format))
+    }
+}
+
+impl Backend for Archive {
+    fn save(&mut self, output: Output) -> Result<()> {
+        for path in output.files.keys() {
+            check_name(path)?;
+        }
+        let archive = match self.format {
+            Format::Tar => tar(&output)?,
+            Format::Zip => zip(&output)?,
+        };
+
+        eprintln!("Updating {}", self.path);
+        fs::write(&self.path, archive)?;
+        Ok(())
+    }
+}
+
+fn tar(output: &Output) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, contents) in &output.files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, path, &contents[..])?;
+    }
+    Ok(builder.into_inner()?)
+}
+
+fn zip(output: &Output) -> Result<Vec<u8>> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    for (path, contents) in &output.files {
+        writer.start_file(path.as_str(), options)?;
+        writer.write_all(contents)?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+/// Paths in archives have to be relative, and stay inside it.
+fn check_name(path: &Utf8Path) -> Result<()> {
+    let normal = path
+        .components()
+        .all(|c| matches!(c, Utf8Component::Normal(_) | Utf8Component::CurDir));
+    if !normal {
+        bail!(
+            "`{}` can't be put in an archive, as it isn't relative",
+            path
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use anyhow::Context;
+
+    use super::*;
+    use crate::vfs::FS;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn formats() {
+        let dir = std::env::temp_dir().join(format!("hidl-archive-{}", std::process::id()));
+        let dir = Utf8PathBuf::try_from(dir).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+
+        let generate = |name: &str| {
+            let mut fs = FS::new();
+            fs.open("docs/README.md").write_all(b"# Docs\n").unwrap();
+            let mut archive = Archive::from_path(dir.join(name))?;
+            fs.save_to(&mut archive)?;
+            fs::read(dir.join(name)).context("no archive")
+        };
+
+        let tarball = generate("out.tar").unwrap();
+        let mut tarball = tar::Archive::new(&tarball[..]);
+        let mut entry = tarball.entries().unwrap().next().unwrap().unwrap();
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents).unwrap();
+        assert_eq!(entry.path().unwrap().to_str(), Some("docs/README.md"));
+        assert_eq!(contents, "# Docs\n");
+
+        let zipped = generate("out.zip").unwrap();
+        let mut zipped = zip::ZipArchive::new(Cursor::new(zipped)).unwrap();
+        let mut contents = String::new();
+        zipped
+            .by_name("docs/README.md")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "# Docs\n");
+
+        let err = generate("out.rar").unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Can't tell the format of `{}/out.rar`, expected `.tar` or `.zip`",
+                dir
+            )
+        );
+        assert!(check_name("/etc/passwd".into()).is_err());
+        assert!(check_name("../x".into()).is_err());
+    }
+}
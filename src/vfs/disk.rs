@@ -0,0 +1,414 @@
+//! Saving to the real filesystem.
+
+use std::{
+    io,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::{bail, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use fs_err as fs;
+use similar::TextDiff;
+
+use super::{normalise, Backend, Output};
+
+/// A [`Backend`] that writes files to disk, or checks them against it.
+#[derive(Debug, Clone, Copy)]
+pub struct Disk {
+    mode: Mode,
+}
+
+/// What [`Disk`] does with the files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Write every file, creating directories as needed
+    #[default]
+    Write,
+    /// Write nothing, and fail if any file on disk is missing, different,
+    /// or an orphan
+    Check,
+}
+
+impl Disk {
+    pub fn new(mode: Mode) -> Self {
+        Self { mode }
+    }
+}
+
+impl Backend for Disk {
+    /// Write or check the files, depending on the [`Mode`].
+    ///
+    /// Files that haven't changed aren't touched. Everything is written to a
+    /// temporary file first, and only renamed into place once every file
+    /// has been written, so a failure while writing leaves the old files as
+    /// they were. Files are renamed one at a time though, so a failure while
+    /// renaming can leave some of them updated and the rest not.
+    ///
+    /// In check mode, a unified diff of each stale file is printed, and the
+    /// error lists them all, along with any orphans.
+    fn save(&mut self, output: Output) -> Result<()> {
+        let orphans = orphans(&output)?;
+
+        match self.mode {
+            Mode::Write => {
+                let mut staged = Vec::new();
+                if let Err(e) = stage(&output, &mut staged) {
+                    for (tmp, _) in &staged {
+                        let _ = fs::remove_file(tmp);
+                    }
+                    return Err(e);
+                }
+
+                for (tmp, path) in staged {
+                    eprintln!("Updating {}", path);
+                    fs::rename(tmp, path)?;
+                }
+                for path in orphans {
+                    eprintln!("Removing {}", &path);
+                    fs::remove_file(path)?;
+                }
+                // Such as the directory of a removed extension
+                for dir in &output.owned {
+                    prune(dir)?;
+                }
+            }
+            Mode::Check => {
+                let mut ood = Vec::new();
+
+                for (path, should_contents) in &output.files {
+                    match changes(path, should_contents)? {
+                        Some(diff) => {
+                            eprint!("{}", diff);
+                            ood.push(path.to_string());
+                        }
+                        None => eprintln!("Up to date: {}", path),
+                    }
+                }
+                for path in orphans {
+                    eprintln!("!!! File isn't generated any more: {}", path);
+                    ood.push(format!("{} (orphan)", path));
+                }
+
+                if !ood.is_empty() {
+                    bail!(
+                        "Some files were out of date, run `run.sh` or `run.ps1` to update them:\n{}",
+                        ood.iter()
+                            .map(|p| format!("  - {}", p))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Files in owned directories that aren't generated or kept.
+fn orphans(output: &Output) -> Result<Vec<Utf8PathBuf>> {
+    let mut orphans = Vec::new();
+    for dir in &output.owned {
+        let mut found = Vec::new();
+        walk(dir, &mut found)?;
+        found.sort();
+        for path in found {
+            if path.as_str().contains(TEMP_MARKER) {
+                continue;
+            }
+            let relative = path.strip_prefix(dir).unwrap().as_str();
+            let kept = output.keep.iter().any(|k| matches(k, relative));
+            if !kept && !output.files.contains_key(&path) {
+                orphans.push(path);
+            }
+        }
+    }
+    Ok(orphans)
+}
+
+/// Every file under `dir`, which may not exist yet.
+fn walk(dir: &Utf8Path, out: &mut Vec<Utf8PathBuf>) -> Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let path = Utf8PathBuf::try_from(entry.path())?;
+        if entry.file_type()?.is_dir() {
+            walk(&path, out)?;
+        } else {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Remove the empty directories under `dir`, and return whether `dir` is
+/// empty now. `dir` itself is left, and may not exist.
+fn prune(dir: &Utf8Path) -> Result<bool> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    let mut empty = true;
+    for entry in entries {
+        let entry = entry?;
+        let path = Utf8PathBuf::try_from(entry.path())?;
+        if entry.file_type()?.is_dir() && prune(&path)? {
+            eprintln!("Removing {}", &path);
+            fs::remove_dir(path)?;
+        } else {
+            empty = false;
+        }
+    }
+    Ok(empty)
+}
+
+/// Whether `path` matches the [`FS::keep`](super::FS::keep) `pattern`.
+fn matches(pattern: &str, path: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == path,
+        Some((prefix, rest)) => {
+            let Some(path) = path.strip_prefix(prefix) else {
+                return false;
+            };
+            // Try every split of what `*` could match, up to the next `/`
+            let end = path.find('/').unwrap_or(path.len());
+            (0..=end).any(|i| matches(rest, &path[i..]))
+        }
+    }
+}
+
+/// Part of the name of temporary files, which may belong to another save
+/// running at the same time.
+const TEMP_MARKER: &str = ".hidl-tmp-";
+
+/// Numbers the temporary files of each save in this process, so saves on
+/// different threads don't share them.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Write each changed file next to where it's going, and add it and where
+/// it's going to `staged`.
+fn stage<'a>(output: &'a Output, staged: &mut Vec<(Utf8PathBuf, &'a Utf8Path)>) -> Result<()> {
+    for (path, contents) in &output.files {
+        if on_disk(path)?.as_deref() == Some(&contents[..]) {
+            continue;
+        }
+
+        if let Some(dir) = path.parent().filter(|d| !d.as_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_file_name(format!(
+            ".{}{}{}-{}",
+            path.file_name().unwrap_or_default(),
+            TEMP_MARKER,
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        staged.push((tmp.clone(), path));
+        fs::write(tmp, contents)?;
+    }
+    Ok(())
+}
+
+/// What's at `path` with normalised line endings, if it exists.
+fn on_disk(path: &Utf8Path) -> Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(contents) => Ok(Some(normalise(&contents).into_owned())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A unified diff from what's at `path` to `contents`, or `None` if they're
+/// the same, ignoring line endings. Missing files are diffed as if they were
+/// empty.
+fn changes(path: &Utf8Path, contents: &[u8]) -> Result<Option<String>> {
+    let new = String::from_utf8_lossy(contents);
+
+    let Some(real_contents) = on_disk(path)? else {
+        let diff = TextDiff::from_lines("", &new)
+            .unified_diff()
+            .header("/dev/null", path.as_str())
+            .to_string();
+        return Ok(Some(diff));
+    };
+    if real_contents == contents {
+        return Ok(None);
+    }
+
+    let old = String::from_utf8_lossy(&real_contents);
+    let diff = TextDiff::from_lines(&old, &new)
+        .unified_diff()
+        .header(path.as_str(), &format!("{} (generated)", path))
+        .to_string();
+    Ok(Some(diff))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::vfs::FS;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn check() {
+        let dir = std::env::temp_dir().join(format!("hidl-vfs-{}", std::process::id()));
+        let dir = Utf8PathBuf::try_from(dir).unwrap();
+
+        let mut vfs = FS::new();
+        vfs.open(dir.join("a/same.txt"))
+            .write_all(b"same\n")
+            .unwrap();
+        vfs.open(dir.join("stale.txt"))
+            .write_all(b"one\ntwo\n")
+            .unwrap();
+        vfs.save().unwrap();
+
+        let mut vfs = FS::new();
+        vfs.open(dir.join("a/same.txt"))
+            .write_all(b"same\n")
+            .unwrap();
+        vfs.open(dir.join("stale.txt"))
+            .write_all(b"one\nthree\n")
+            .unwrap();
+        vfs.open(dir.join("missing.txt"))
+            .write_all(b"new\n")
+            .unwrap();
+        let err = vfs
+            .save_to(&mut Disk::new(Mode::Check))
+            .unwrap_err()
+            .to_string();
+
+        let stale_diff = changes(&dir.join("stale.txt"), b"one\nthree\n").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            err,
+            format!(
+                "Some files were out of date, run `run.sh` or `run.ps1` to update them:\n  \
+                 - {0}/missing.txt\n  - {0}/stale.txt",
+                dir
+            )
+        );
+        assert_eq!(
+            stale_diff.unwrap(),
+            format!(
+                "--- {0}/stale.txt\n+++ {0}/stale.txt (generated)\n@@ -1,2 +1,2 @@\n one\n-two\n+three\n",
+                dir
+            )
+        );
+    }
+
+    #[test]
+    fn orphans() {
+        let dir = std::env::temp_dir().join(format!("hidl-orphans-{}", std::process::id()));
+        let dir = Utf8PathBuf::try_from(dir).unwrap();
+        for name in [
+            "old.md",
+            "old/nested/a.md",
+            "kept/notes.md",
+            "kept/img/a.png",
+            "new.md",
+        ] {
+            fs::create_dir_all(dir.join(name).parent().unwrap()).unwrap();
+            fs::write(dir.join(name), "x").unwrap();
+        }
+
+        let generate = |mode| {
+            let mut vfs = FS::new();
+            vfs.own_dir(&dir);
+            vfs.keep("kept/*.md");
+            vfs.open(dir.join("new.md")).write_all(b"x").unwrap();
+            vfs.save_to(&mut Disk::new(mode))
+        };
+
+        let err = generate(Mode::Check).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            format!(
+                "Some files were out of date, run `run.sh` or `run.ps1` to update them:\n  \
+                 - {0}/kept/img/a.png (orphan)\n  - {0}/old/nested/a.md (orphan)\n  \
+                 - {0}/old.md (orphan)",
+                dir
+            )
+        );
+
+        generate(Mode::Write).unwrap();
+        let mut left = Vec::new();
+        walk(&dir, &mut left).unwrap();
+        left.sort();
+        let dirs = fs::read_dir(&dir)
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_type().unwrap().is_dir())
+            .count();
+        let img = dir.join("kept/img").exists();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(left, [dir.join("kept/notes.md"), dir.join("new.md")]);
+        // `old` and `kept/img` are empty now, so removed
+        assert_eq!(dirs, 1);
+        assert!(!img);
+
+        assert!(matches("*.md", "a.md"));
+        assert!(!matches("*.md", "a/b.md"));
+        assert!(matches("a/*", "a/b.md"));
+    }
+
+    #[test]
+    fn writes() {
+        let dir = std::env::temp_dir().join(format!("hidl-writes-{}", std::process::id()));
+        let dir = Utf8PathBuf::try_from(dir).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("same.txt"), "a\r\nb\n").unwrap();
+        let mtime = |name| fs::metadata(dir.join(name)).unwrap().modified().unwrap();
+        let before = mtime("same.txt");
+
+        let mut vfs = FS::new();
+        vfs.own_dir(&dir);
+        vfs.open(dir.join("same.txt")).write_all(b"a\nb\n").unwrap();
+        vfs.open(dir.join("crlf.txt"))
+            .write_all(b"a\r\nb\r\n")
+            .unwrap();
+        vfs.save().unwrap();
+
+        let same_mtime = mtime("same.txt") == before;
+        let crlf = fs::read_to_string(dir.join("crlf.txt")).unwrap();
+        let mut left = Vec::new();
+        walk(&dir, &mut left).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(same_mtime);
+        assert_eq!(crlf, "a\nb\n");
+        assert_eq!(left.len(), 2, "no temporary files are left");
+    }
+
+    /// Saves on different threads don't share temporary files.
+    #[test]
+    fn concurrent() {
+        let dir = std::env::temp_dir().join(format!("hidl-concurrent-{}", std::process::id()));
+        let dir = Utf8PathBuf::try_from(dir).unwrap();
+
+        std::thread::scope(|s| {
+            for thread in 0..4 {
+                let dir = &dir;
+                s.spawn(move || {
+                    for n in 0..50 {
+                        let mut vfs = FS::new();
+                        write!(vfs.open(dir.join("out.txt")), "{} {}", thread, n).unwrap();
+                        vfs.save().unwrap();
+                    }
+                });
+            }
+        });
+
+        let mut left = Vec::new();
+        walk(&dir, &mut left).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(left, [dir.join("out.txt")]);
+    }
+}
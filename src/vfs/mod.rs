@@ -0,0 +1,219 @@
+//! Generated files are built up in memory, then saved all at once to a
+//! [`Backend`]: the real filesystem ([`Disk`]), an in-memory map
+//! ([`Memory`]), or an [`Archive`].
+//!
+//! Directories can also be owned by the FS, in which case any file in them
+//! that wasn't generated (such as the page for a removed extension) is an
+//! orphan, and is removed from disk, along with any directories that leaves
+//! empty. Hand written files can be kept with [`FS::keep`].
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    io,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+
+mod archive;
+mod disk;
+
+pub use archive::{Archive, Format};
+pub use disk::{Disk, Mode};
+
+/// A file being generated.
+///
+/// There can be several handles to the same file (see [`FS::handle`]),
+/// which all write to the end of it.
+pub struct File {
+    contents: Arc<Mutex<Vec<u8>>>,
+}
+
+#[derive(Default)]
+pub struct FS {
+    files: BTreeMap<Utf8PathBuf, File>,
+    owned: BTreeSet<Utf8PathBuf>,
+    keep: Vec<String>,
+}
+
+/// Somewhere an [`FS`] can be saved.
+pub trait Backend {
+    fn save(&mut self, output: Output) -> Result<()>;
+}
+
+/// Everything written to an [`FS`], with line endings normalised to `\n`.
+pub struct Output {
+    pub files: BTreeMap<Utf8PathBuf, Vec<u8>>,
+    /// See [`FS::own_dir`]
+    pub owned: BTreeSet<Utf8PathBuf>,
+    /// See [`FS::keep`]
+    pub keep: Vec<String>,
+}
+
+/// A [`Backend`] that keeps files in memory, so they can be read back.
+///
+/// Saving more than once adds to (or replaces) what's already there.
+#[derive(Debug, Default)]
+pub struct Memory {
+    files: BTreeMap<Utf8PathBuf, Vec<u8>>,
+}
+
+impl FS {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take ownership of everything in `dir`, so files in it that aren't
+    /// generated are removed on save.
+    pub fn own_dir(&mut self, dir: impl Into<Utf8PathBuf>) {
+        self.owned.insert(dir.into());
+    }
+
+    /// Never treat files matching `pattern` as orphans. Patterns are paths
+    /// relative to an owned directory, where `*` matches anything but `/`.
+    pub fn keep(&mut self, pattern: impl Into<String>) {
+        self.keep.push(pattern.into());
+    }
+
+    /// The file at `path`, which is created if it doesn't exist yet.
+    ///
+    /// To write to more than one file at once, use [`FS::handle`].
+    pub fn open(&mut self, path: impl Into<Utf8PathBuf>) -> &mut File {
+        self.files.entry(path.into()).or_default()
+    }
+
+    /// Like [`FS::open`], but returns a new handle to the file, which
+    /// doesn't borrow the FS.
+    pub fn handle(&mut self, path: impl Into<Utf8PathBuf>) -> File {
+        File {
+            contents: self.open(path).contents.clone(),
+        }
+    }
+
+    pub fn add_file(&mut self, file: File, path: impl Into<Utf8PathBuf>) {
+        let r = self.files.insert(path.into(), file);
+        assert!(r.is_none());
+    }
+
+    /// Write the files to disk. Use [`FS::save_to`] for anything else.
+    pub fn save(self) -> Result<()> {
+        self.save_to(&mut Disk::new(Mode::Write))
+    }
+
+    pub fn save_to(self, backend: &mut dyn Backend) -> Result<()> {
+        let files = self
+            .files
+            .into_iter()
+            .map(|(path, file)| {
+                let contents = file.contents.lock().unwrap();
+                let contents = normalise(&contents).into_owned();
+                (path, contents)
+            })
+            .collect();
+
+        backend.save(Output {
+            files,
+            owned: self.owned,
+            keep: self.keep,
+        })
+    }
+}
+
+impl Backend for Memory {
+    fn save(&mut self, output: Output) -> Result<()> {
+        self.files.extend(output.files);
+        Ok(())
+    }
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, path: impl AsRef<Utf8Path>) -> Option<&[u8]> {
+        self.files.get(path.as_ref()).map(|c| &c[..])
+    }
+
+    pub fn read_to_string(&self, path: impl AsRef<Utf8Path>) -> Result<&str> {
+        let path = path.as_ref();
+        let contents = self
+            .get(path)
+            .with_context(|| format!("`{}` wasn't generated", path))?;
+        std::str::from_utf8(contents).with_context(|| format!("`{}` isn't UTF-8", path))
+    }
+
+    /// Every file, in order of their paths.
+    pub fn files(&self) -> impl Iterator<Item = (&Utf8Path, &[u8])> {
+        self.files.iter().map(|(p, c)| (p.as_path(), &c[..]))
+    }
+}
+
+/// Replace `\r\n` with `\n`, so output is the same on every platform, even
+/// if it includes files checked out with Windows line endings.
+fn normalise(contents: &[u8]) -> Cow<'_, [u8]> {
+    if !contents.contains(&b'\r') {
+        return Cow::Borrowed(contents);
+    }
+    let mut out = Vec::with_capacity(contents.len());
+    for (i, &b) in contents.iter().enumerate() {
+        if !(b == b'\r' && contents.get(i + 1) == Some(&b'\n')) {
+            out.push(b);
+        }
+    }
+    Cow::Owned(out)
+}
+
+impl File {
+    pub fn new() -> Self {
+        Self {
+            contents: Arc::default(),
+        }
+    }
+}
+
+impl io::Write for File {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.contents.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Default for File {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn memory() {
+        let mut fs = FS::new();
+        let mut a = fs.handle("a.txt");
+        let mut b = fs.handle("b.txt");
+        writeln!(a, "one").unwrap();
+        writeln!(b, "two\r").unwrap();
+        writeln!(fs.open("a.txt"), "three").unwrap();
+
+        let mut memory = Memory::new();
+        fs.save_to(&mut memory).unwrap();
+
+        assert_eq!(memory.read_to_string("a.txt").unwrap(), "one\nthree\n");
+        assert_eq!(memory.get("b.txt"), Some(&b"two\n"[..]));
+        assert_eq!(
+            memory.read_to_string("c.txt").unwrap_err().to_string(),
+            "`c.txt` wasn't generated"
+        );
+    }
+}
@@ -0,0 +1,629 @@
+// Generated by hidl from the `hypercosm` namespace. Do not edit.
+
+// Runtime support for generated code, see `spec/encoding.md` for the format.
+
+export type ObjectId = bigint;
+export type Uuid = string;
+export type Matrix4x4 = Float32Array;
+
+export interface Transport {
+    /** Call `method` on `object`, and resolve with the encoded result. */
+    call(object: ObjectId, iface: string, method: string, args: Uint8Array): Promise<Uint8Array>;
+    /** Register a callback for every event `object` emits on `iface`. */
+    listen(object: ObjectId, iface: string, listener: (event: string, args: Uint8Array) => void): void;
+}
+
+type Listeners<E> = { [K in keyof E]?: E[K][] };
+
+export class Emitter<E extends { [K in keyof E]: (...args: any[]) => void }> {
+    private listeners: Listeners<E> = {};
+
+    on<K extends keyof E>(event: K, listener: E[K]): this {
+        (this.listeners[event] ??= []).push(listener);
+        return this;
+    }
+
+    off<K extends keyof E>(event: K, listener: E[K]): this {
+        const listeners = this.listeners[event];
+        if (listeners !== undefined) {
+            const i = listeners.indexOf(listener);
+            if (i !== -1) {
+                listeners.splice(i, 1);
+            }
+        }
+        return this;
+    }
+
+    protected emit<K extends keyof E>(event: K, ...args: Parameters<E[K]>): void {
+        for (const listener of this.listeners[event] ?? []) {
+            listener(...args);
+        }
+    }
+}
+
+export class Writer {
+    private buf = new Uint8Array(64);
+    private view = new DataView(this.buf.buffer);
+    private len = 0;
+
+    private reserve(n: number): number {
+        if (this.len + n > this.buf.length) {
+            const buf = new Uint8Array(Math.max(this.buf.length * 2, this.len + n));
+            buf.set(this.buf);
+            this.buf = buf;
+            this.view = new DataView(buf.buffer);
+        }
+        const at = this.len;
+        this.len += n;
+        return at;
+    }
+
+    finish(): Uint8Array {
+        return this.buf.slice(0, this.len);
+    }
+
+    bool(v: boolean): void { this.u8(v ? 1 : 0); }
+
+    u8(v: number): void { this.view.setUint8(this.reserve(1), v); }
+    u16(v: number): void { this.view.setUint16(this.reserve(2), v, true); }
+    u32(v: number): void { this.view.setUint32(this.reserve(4), v, true); }
+    u64(v: bigint): void { this.view.setBigUint64(this.reserve(8), v, true); }
+    i8(v: number): void { this.view.setInt8(this.reserve(1), v); }
+    i16(v: number): void { this.view.setInt16(this.reserve(2), v, true); }
+    i32(v: number): void { this.view.setInt32(this.reserve(4), v, true); }
+    i64(v: bigint): void { this.view.setBigInt64(this.reserve(8), v, true); }
+    f32(v: number): void { this.view.setFloat32(this.reserve(4), v, true); }
+    f64(v: number): void { this.view.setFloat64(this.reserve(8), v, true); }
+
+    vu32(v: number): void {
+        do {
+            let byte = v % 0x80;
+            v = Math.floor(v / 0x80);
+            if (v !== 0) {
+                byte |= 0x80;
+            }
+            this.u8(byte);
+        } while (v !== 0);
+    }
+
+    vu64(v: bigint): void {
+        do {
+            let byte = Number(v & 0x7fn);
+            v >>= 7n;
+            if (v !== 0n) {
+                byte |= 0x80;
+            }
+            this.u8(byte);
+        } while (v !== 0n);
+    }
+
+    vi32(v: number): void { this.vu32(((v << 1) ^ (v >> 31)) >>> 0); }
+    vi64(v: bigint): void { this.vu64(v >= 0n ? v << 1n : ((-v) << 1n) - 1n); }
+
+    raw(v: Uint8Array): void { this.buf.set(v, this.reserve(v.length)); }
+
+    bytes(v: Uint8Array): void {
+        this.vu32(v.length);
+        this.raw(v);
+    }
+
+    string(v: string): void { this.bytes(new TextEncoder().encode(v)); }
+
+    uuid(v: Uuid): void {
+        const hex = v.replace(/-/g, "");
+        if (!/^[0-9a-fA-F]{32}$/.test(hex)) {
+            throw new Error(`Invalid uuid: ${v}`);
+        }
+        for (let i = 0; i < 32; i += 2) {
+            this.u8(parseInt(hex.slice(i, i + 2), 16));
+        }
+    }
+
+    object(v: ObjectId): void { this.vu64(v); }
+
+    matrix4x4(v: Matrix4x4): void {
+        if (v.length !== 16) {
+            throw new Error(`matrix4x4 must have 16 elements, got ${v.length}`);
+        }
+        for (const x of v) {
+            this.f32(x);
+        }
+    }
+
+    array<T>(v: T[], item: (w: Writer, x: T) => void): void {
+        this.vu32(v.length);
+        for (const x of v) {
+            item(this, x);
+        }
+    }
+
+    map<K, V>(v: Map<K, V>, key: (w: Writer, k: K) => void, value: (w: Writer, v: V) => void): void {
+        this.vu32(v.size);
+        for (const [k, x] of v) {
+            key(this, k);
+            value(this, x);
+        }
+    }
+}
+
+export class Reader {
+    private view: DataView;
+    private pos = 0;
+
+    constructor(private readonly buf: Uint8Array) {
+        this.view = new DataView(buf.buffer, buf.byteOffset, buf.byteLength);
+    }
+
+    private advance(n: number): number {
+        if (this.pos + n > this.buf.length) {
+            throw new Error("Unexpected end of input");
+        }
+        const at = this.pos;
+        this.pos += n;
+        return at;
+    }
+
+    /** Throw if there is unread input left. */
+    finish(): void {
+        if (this.pos !== this.buf.length) {
+            throw new Error(`${this.buf.length - this.pos} trailing bytes`);
+        }
+    }
+
+    bool(): boolean {
+        const v = this.u8();
+        if (v > 1) {
+            throw new Error(`Invalid bool: ${v}`);
+        }
+        return v === 1;
+    }
+
+    u8(): number { return this.view.getUint8(this.advance(1)); }
+    u16(): number { return this.view.getUint16(this.advance(2), true); }
+    u32(): number { return this.view.getUint32(this.advance(4), true); }
+    u64(): bigint { return this.view.getBigUint64(this.advance(8), true); }
+    i8(): number { return this.view.getInt8(this.advance(1)); }
+    i16(): number { return this.view.getInt16(this.advance(2), true); }
+    i32(): number { return this.view.getInt32(this.advance(4), true); }
+    i64(): bigint { return this.view.getBigInt64(this.advance(8), true); }
+    f32(): number { return this.view.getFloat32(this.advance(4), true); }
+    f64(): number { return this.view.getFloat64(this.advance(8), true); }
+
+    /** Read an unsigned LEB128 that must fit in `bits` bits. */
+    vu32(bits = 32): number {
+        const v = this.vu64(BigInt(bits));
+        return Number(v);
+    }
+
+    vu64(bits = 64n): bigint {
+        let v = 0n;
+        let shift = 0n;
+        for (;;) {
+            const byte = this.u8();
+            v |= BigInt(byte & 0x7f) << shift;
+            shift += 7n;
+            if ((byte & 0x80) === 0) {
+                break;
+            }
+            if (shift >= bits) {
+                throw new Error(`Varint longer than ${bits} bits`);
+            }
+        }
+        if (v >> bits !== 0n) {
+            throw new Error(`Varint doesn't fit in ${bits} bits`);
+        }
+        return v;
+    }
+
+    vi32(bits = 32): number {
+        const n = this.vu32(bits);
+        return (n >>> 1) ^ -(n & 1);
+    }
+
+    vi64(bits = 64n): bigint {
+        const n = this.vu64(bits);
+        return (n & 1n) !== 0n ? -((n >> 1n) + 1n) : n >> 1n;
+    }
+
+    /** Lengths are `vu64`s on the wire, but must fit in an array. */
+    private length(): number {
+        const len = this.vu64();
+        if (len > BigInt(Number.MAX_SAFE_INTEGER)) {
+            throw new Error(`Length ${len} is too large`);
+        }
+        return Number(len);
+    }
+
+    raw(n: number): Uint8Array {
+        const at = this.advance(n);
+        return this.buf.slice(at, at + n);
+    }
+
+    bytes(): Uint8Array { return this.raw(this.length()); }
+
+    string(): string { return new TextDecoder("utf-8", { fatal: true }).decode(this.bytes()); }
+
+    uuid(): Uuid {
+        const hex = Array.from(this.raw(16), (b) => b.toString(16).padStart(2, "0")).join("");
+        return [hex.slice(0, 8), hex.slice(8, 12), hex.slice(12, 16), hex.slice(16, 20), hex.slice(20)].join("-");
+    }
+
+    object(): ObjectId { return this.vu64(); }
+
+    matrix4x4(): Matrix4x4 {
+        const m = new Float32Array(16);
+        for (let i = 0; i < 16; i++) {
+            m[i] = this.f32();
+        }
+        return m;
+    }
+
+    array<T>(item: (r: Reader) => T): T[] {
+        const len = this.length();
+        const v: T[] = [];
+        for (let i = 0; i < len; i++) {
+            v.push(item(this));
+        }
+        return v;
+    }
+
+    map<K, V>(key: (r: Reader) => K, value: (r: Reader) => V): Map<K, V> {
+        const len = this.length();
+        const v = new Map<K, V>();
+        for (let i = 0; i < len; i++) {
+            const k = key(this);
+            if (v.has(k)) {
+                throw new Error(`Duplicate key: ${k}`);
+            }
+            v.set(k, value(this));
+        }
+        return v;
+    }
+}
+
+/**
+ * An asset: the type is given by TODO
+ */
+export interface Asset {
+    id: Uuid;
+    name: string;
+    data: Uint8Array;
+}
+
+export function encodeAsset(w: Writer, v: Asset): void {
+    w.uuid(v.id);
+    w.string(v.name);
+    w.bytes(v.data);
+}
+
+export function decodeAsset(r: Reader): Asset {
+    return {
+        id: r.uuid(),
+        name: r.string(),
+        data: r.bytes(),
+    };
+}
+
+export interface EntityInfo {
+    assetId: Uuid;
+    entity: ObjectId;
+    transformation: Matrix4x4;
+    attrs: EntityAttrs;
+}
+
+export function encodeEntityInfo(w: Writer, v: EntityInfo): void {
+    w.uuid(v.assetId);
+    w.object(v.entity);
+    w.matrix4x4(v.transformation);
+    encodeEntityAttrs(w, v.attrs);
+}
+
+export function decodeEntityInfo(r: Reader): EntityInfo {
+    return {
+        assetId: r.uuid(),
+        entity: r.object(),
+        transformation: r.matrix4x4(),
+        attrs: decodeEntityAttrs(r),
+    };
+}
+
+export type EntityAttrs = bigint;
+
+export const EntityAttrs = {
+    None: 0n as EntityAttrs,
+    Interactable: 1n as EntityAttrs,
+    Collidable: 2n as EntityAttrs,
+    /** Check if all bits in `flag` are set in `value` */
+    has(value: EntityAttrs, flag: EntityAttrs): boolean { return (value & flag) === flag; },
+    set(value: EntityAttrs, flag: EntityAttrs): EntityAttrs { return (value | flag); },
+    clear(value: EntityAttrs, flag: EntityAttrs): EntityAttrs { return (value & ~flag); },
+    /** The names of all non zero flags set in `value` */
+    names(value: EntityAttrs): string[] {
+        const names: string[] = [];
+        if ((value & 1n) === 1n) names.push("Interactable");
+        if ((value & 2n) === 2n) names.push("Collidable");
+        return names;
+    },
+} as const;
+
+export function encodeEntityAttrs(w: Writer, v: EntityAttrs): void {
+    w.vu64(v);
+}
+
+export function decodeEntityAttrs(r: Reader): EntityAttrs {
+    return r.vu64();
+}
+
+/**
+ * The root interface, that all interfaces inherit from and
+ * all objects implement
+ */
+export class ObjectClient {
+    static readonly interfaceName = "hypercosm.Object";
+    static readonly version = "1.0.0";
+
+    constructor(readonly transport: Transport, readonly id: ObjectId) {}
+
+    /**
+     * Get all interfaces implemented by an object
+     *
+     * The interfaces will be listed in the format `{namespace}.{extension}.{interface_name}.{version}`
+     */
+    async listInterfaces(): Promise<string[]> {
+        const _w = new Writer();
+        const _r = new Reader(await this.transport.call(this.id, ObjectClient.interfaceName, "list_interfaces", _w.finish()));
+        const _ret = _r.array((r) => r.string());
+        _r.finish();
+        return _ret;
+    }
+
+    /**
+     * Remove the object from the object list
+     *
+     * Future attempts to make calls on the object ID **MUST** fail.
+     *
+     * The resources associated with the object may be released, but the
+     * object ID must not be reused, we have plenty of them
+     *
+     * It is an error to remove the root singleton (id 0)
+     */
+    async release(): Promise<void> {
+        const _w = new Writer();
+        const _r = new Reader(await this.transport.call(this.id, ObjectClient.interfaceName, "release", _w.finish()));
+        _r.finish();
+    }
+}
+
+/**
+ * Singleton with known id 0
+ *
+ * This is the only object that is known at the start of connection
+ * and is used to discover other objects
+ */
+export class RootClient {
+    static readonly interfaceName = "hypercosm.Root";
+    static readonly version = "0.1.0";
+
+    constructor(readonly transport: Transport, readonly id: ObjectId) {}
+
+    /**
+     * List the extensions implemented by the conected node
+     */
+    async listExtensions(): Promise<string[]> {
+        const _w = new Writer();
+        const _r = new Reader(await this.transport.call(this.id, RootClient.interfaceName, "list_extensions", _w.finish()));
+        const _ret = _r.array((r) => r.string());
+        _r.finish();
+        return _ret;
+    }
+
+    /**
+     * Check the conection status
+     */
+    async ping(): Promise<void> {
+        const _w = new Writer();
+        const _r = new Reader(await this.transport.call(this.id, RootClient.interfaceName, "ping", _w.finish()));
+        _r.finish();
+    }
+
+    async getObjectById(id: Uuid): Promise<ObjectId> {
+        const _w = new Writer();
+        _w.uuid(id);
+        const _r = new Reader(await this.transport.call(this.id, RootClient.interfaceName, "get_object_by_id", _w.finish()));
+        const _ret = _r.object();
+        _r.finish();
+        return _ret;
+    }
+
+    /**
+     * Get the id of a singleton by the name of the interface
+     */
+    async getObjectByName(name: string): Promise<ObjectId> {
+        const _w = new Writer();
+        _w.string(name);
+        const _r = new Reader(await this.transport.call(this.id, RootClient.interfaceName, "get_object_by_name", _w.finish()));
+        const _ret = _r.object();
+        _r.finish();
+        return _ret;
+    }
+}
+
+export interface AssetDeliveryEvents {
+    loadAssets: (assets: Asset[]) => void;
+    unloadAssets: (assets: Uuid[]) => void;
+}
+
+/**
+ * Singleton for asset delivery
+ */
+export class AssetDeliveryClient extends Emitter<AssetDeliveryEvents> {
+    static readonly interfaceName = "hypercosm.asset_delivery.asset_delivery";
+    static readonly version = "0.1.0";
+
+    constructor(readonly transport: Transport, readonly id: ObjectId) {
+        super();
+        transport.listen(id, AssetDeliveryClient.interfaceName, (event, args) => {
+            const _r = new Reader(args);
+            switch (event) {
+                case "load_assets": {
+                    const assets = _r.array((r) => decodeAsset(r));
+                    _r.finish();
+                    this.emit("loadAssets", assets);
+                    break;
+                }
+                case "unload_assets": {
+                    const assets = _r.array((r) => r.uuid());
+                    _r.finish();
+                    this.emit("unloadAssets", assets);
+                    break;
+                }
+            }
+        });
+    }
+
+    async fetchById(id: Uuid): Promise<Asset> {
+        const _w = new Writer();
+        _w.uuid(id);
+        const _r = new Reader(await this.transport.call(this.id, AssetDeliveryClient.interfaceName, "fetch_by_id", _w.finish()));
+        const _ret = decodeAsset(_r);
+        _r.finish();
+        return _ret;
+    }
+
+    async fetchByName(name: string): Promise<Asset> {
+        const _w = new Writer();
+        _w.string(name);
+        const _r = new Reader(await this.transport.call(this.id, AssetDeliveryClient.interfaceName, "fetch_by_name", _w.finish()));
+        const _ret = decodeAsset(_r);
+        _r.finish();
+        return _ret;
+    }
+
+    async fetchByIds(ids: Uuid[]): Promise<Asset[]> {
+        const _w = new Writer();
+        _w.array(ids, (w, x) => w.uuid(x));
+        const _r = new Reader(await this.transport.call(this.id, AssetDeliveryClient.interfaceName, "fetch_by_ids", _w.finish()));
+        const _ret = _r.array((r) => decodeAsset(r));
+        _r.finish();
+        return _ret;
+    }
+
+    async fetchByNames(names: string[]): Promise<Asset[]> {
+        const _w = new Writer();
+        _w.array(names, (w, x) => w.string(x));
+        const _r = new Reader(await this.transport.call(this.id, AssetDeliveryClient.interfaceName, "fetch_by_names", _w.finish()));
+        const _ret = _r.array((r) => decodeAsset(r));
+        _r.finish();
+        return _ret;
+    }
+
+    async getId(name: string): Promise<Uuid> {
+        const _w = new Writer();
+        _w.string(name);
+        const _r = new Reader(await this.transport.call(this.id, AssetDeliveryClient.interfaceName, "get_id", _w.finish()));
+        const _ret = _r.uuid();
+        _r.finish();
+        return _ret;
+    }
+}
+
+export interface WorldEvents {
+    addEntities: (entities: EntityInfo[]) => void;
+    updateEntities: (entities: EntityInfo[]) => void;
+    removeEntities: (entities: ObjectId[]) => void;
+}
+
+export class WorldClient extends Emitter<WorldEvents> {
+    static readonly interfaceName = "hypercosm.world.world";
+    static readonly version = "0.1.0";
+
+    constructor(readonly transport: Transport, readonly id: ObjectId) {
+        super();
+        transport.listen(id, WorldClient.interfaceName, (event, args) => {
+            const _r = new Reader(args);
+            switch (event) {
+                case "add_entities": {
+                    const entities = _r.array((r) => decodeEntityInfo(r));
+                    _r.finish();
+                    this.emit("addEntities", entities);
+                    break;
+                }
+                case "update_entities": {
+                    const entities = _r.array((r) => decodeEntityInfo(r));
+                    _r.finish();
+                    this.emit("updateEntities", entities);
+                    break;
+                }
+                case "remove_entities": {
+                    const entities = _r.array((r) => r.object());
+                    _r.finish();
+                    this.emit("removeEntities", entities);
+                    break;
+                }
+            }
+        });
+    }
+}
+
+export class EntityClient {
+    static readonly interfaceName = "hypercosm.world.Entity";
+    static readonly version = "0.1.0";
+
+    constructor(readonly transport: Transport, readonly id: ObjectId) {}
+
+    async interact(): Promise<void> {
+        const _w = new Writer();
+        const _r = new Reader(await this.transport.call(this.id, EntityClient.interfaceName, "interact", _w.finish()));
+        _r.finish();
+    }
+}
+
+export class ExecutionContextClient {
+    static readonly interfaceName = "hypercosm.execution_context.execution_context";
+    static readonly version = "0.1.0";
+
+    constructor(readonly transport: Transport, readonly id: ObjectId) {}
+
+    async loadWasmModule(assetId: Uuid, exports: Map<string, string>): Promise<bigint> {
+        const _w = new Writer();
+        _w.uuid(assetId);
+        _w.map(exports, (w, k) => w.string(k), (w, x) => w.string(x));
+        const _r = new Reader(await this.transport.call(this.id, ExecutionContextClient.interfaceName, "load_wasm_module", _w.finish()));
+        const _ret = _r.vu64();
+        _r.finish();
+        return _ret;
+    }
+
+    async loadLuaScript(assetId: Uuid): Promise<bigint> {
+        const _w = new Writer();
+        _w.uuid(assetId);
+        const _r = new Reader(await this.transport.call(this.id, ExecutionContextClient.interfaceName, "load_lua_script", _w.finish()));
+        const _ret = _r.vu64();
+        _r.finish();
+        return _ret;
+    }
+
+    async loadInlineLuaScript(script: string): Promise<bigint> {
+        const _w = new Writer();
+        _w.string(script);
+        const _r = new Reader(await this.transport.call(this.id, ExecutionContextClient.interfaceName, "load_inline_lua_script", _w.finish()));
+        const _ret = _r.vu64();
+        _r.finish();
+        return _ret;
+    }
+
+    async beginExecution(moduleOrScript: bigint, entryPoint: string): Promise<void> {
+        const _w = new Writer();
+        _w.vu64(moduleOrScript);
+        _w.string(entryPoint);
+        const _r = new Reader(await this.transport.call(this.id, ExecutionContextClient.interfaceName, "begin_execution", _w.finish()));
+        _r.finish();
+    }
+
+    async beginInlineLuaExecution(script: string): Promise<void> {
+        const _w = new Writer();
+        _w.string(script);
+        const _r = new Reader(await this.transport.call(this.id, ExecutionContextClient.interfaceName, "begin_inline_lua_execution", _w.finish()));
+        _r.finish();
+    }
+}